@@ -0,0 +1,170 @@
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum ClassItem {
+    Char(char),
+    Range(char, char),
+    Named(String),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Class {
+    negated: bool,
+    items: Vec<ClassItem>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ClassError {
+    Unmatched,
+    InvalidName(String),
+    InvalidCollation,
+    InvalidRange(char, char),
+}
+
+impl Class {
+    // Parses a bracket expression that starts after its `[` and returns it
+    // with the position after the closing `]`.
+    pub fn parse(chars: &[char], start: usize) -> Result<(Self, usize), ClassError> {
+        let mut idx = start;
+        let negated = chars.get(idx) == Some(&'^');
+        if negated {
+            idx += 1;
+        }
+        let mut items = Vec::new();
+        let mut first = true;
+        loop {
+            let Some(&char) = chars.get(idx) else {
+                return Err(ClassError::Unmatched);
+            };
+            idx += 1;
+            match char {
+                ']' if !first => break,
+                '[' if matches!(chars.get(idx), Some(':' | '=' | '.')) => {
+                    let kind = chars[idx];
+                    let Some(length) = chars[idx + 1..]
+                        .windows(2)
+                        .position(|pair| pair == [kind, ']'])
+                    else {
+                        return Err(ClassError::Unmatched);
+                    };
+                    let name: String = chars[idx + 1..idx + 1 + length].iter().collect();
+                    idx += length + 3;
+                    match kind {
+                        ':' if is_class_name(&name) => items.push(ClassItem::Named(name)),
+                        ':' => return Err(ClassError::InvalidName(name)),
+                        _ if length == 1 => items.push(ClassItem::Char(chars[idx - 3])),
+                        _ => return Err(ClassError::InvalidCollation),
+                    }
+                }
+                char if chars.get(idx) == Some(&'-')
+                    && chars.get(idx + 1).is_some_and(|next| *next != ']') =>
+                {
+                    let to = chars[idx + 1];
+                    if to < char {
+                        return Err(ClassError::InvalidRange(char, to));
+                    }
+                    idx += 2;
+                    items.push(ClassItem::Range(char, to));
+                }
+                char => items.push(ClassItem::Char(char)),
+            }
+            first = false;
+        }
+        Ok((Self { negated, items }, idx))
+    }
+
+    // A named class with some extra characters, as for `\w` and `\s`.
+    pub fn named(name: &str, negated: bool, extra: &[char]) -> Self {
+        let mut items = vec![ClassItem::Named(name.to_string())];
+        items.extend(extra.iter().map(|char| ClassItem::Char(*char)));
+        Self { negated, items }
+    }
+
+    pub(crate) fn matches(&self, char: char, ignore_case: bool) -> bool {
+        let contains = |char: char| {
+            self.items.iter().any(|item| match item {
+                ClassItem::Char(expected) => *expected == char,
+                ClassItem::Range(from, to) => (*from..=*to).contains(&char),
+                ClassItem::Named(name) => is_named(name, char),
+            })
+        };
+        let found = match ignore_case {
+            true => contains(char) || contains(crate::fold(char)) || contains(upper(char)),
+            false => contains(char),
+        };
+        found != self.negated
+    }
+}
+
+fn is_class_name(name: &str) -> bool {
+    matches!(
+        name,
+        "alnum"
+            | "alpha"
+            | "blank"
+            | "cntrl"
+            | "digit"
+            | "graph"
+            | "lower"
+            | "print"
+            | "punct"
+            | "space"
+            | "upper"
+            | "xdigit"
+    )
+}
+
+fn is_named(name: &str, char: char) -> bool {
+    match name {
+        "alnum" => char.is_alphanumeric(),
+        "alpha" => char.is_alphabetic(),
+        "blank" => char == ' ' || char == '\t',
+        "cntrl" => char.is_control(),
+        "digit" => char.is_ascii_digit(),
+        "graph" => !char.is_whitespace() && !char.is_control(),
+        "lower" => char.is_lowercase(),
+        "print" => !char.is_control(),
+        "punct" => char.is_ascii_punctuation(),
+        "space" => char.is_whitespace(),
+        "upper" => char.is_uppercase(),
+        "xdigit" => char.is_ascii_hexdigit(),
+        _ => false,
+    }
+}
+
+fn upper(char: char) -> char {
+    char.to_uppercase().next().unwrap_or(char)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::class::{Class, ClassError};
+
+    #[test]
+    fn check_class() {
+        let parse = |text: &str| {
+            let chars: Vec<char> = text.chars().collect();
+            Class::parse(&chars, 0)
+        };
+        let (class, next) = parse("]a-c[:digit:][.-.]]x").unwrap();
+        assert_eq!(next, 19);
+        assert!(
+            ["]", "b", "7", "-"]
+                .iter()
+                .all(|text| { class.matches(text.chars().next().unwrap(), false) })
+        );
+        assert!(!class.matches('x', false));
+        assert!(!class.matches('B', false));
+        assert!(class.matches('B', true));
+
+        let (class, _) = parse("^[:space:]]").unwrap();
+        assert!(!class.matches('\t', false));
+        assert!(class.matches('a', false));
+
+        assert_eq!(parse("abc"), Err(ClassError::Unmatched));
+        assert_eq!(
+            parse("[:foo:]]"),
+            Err(ClassError::InvalidName("foo".into()))
+        );
+        assert_eq!(parse("[=ab=]]"), Err(ClassError::InvalidCollation));
+        assert_eq!(parse("z-a]"), Err(ClassError::InvalidRange('z', 'a')));
+    }
+}
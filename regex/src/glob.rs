@@ -0,0 +1,233 @@
+// Shell wildcards, for pathname expansion and `case` as well as `find -name`.
+// `*` and `?` match any character, including `/` and a leading `.`, which
+// callers exclude themselves where they have to.
+#[derive(Debug, PartialEq, Eq)]
+enum Item {
+    Char(char),
+    AnyChar,
+    AnyString,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Pattern {
+    items: Vec<Item>,
+    ignore_case: bool,
+}
+
+impl Pattern {
+    // Quoted characters stand for themselves.
+    pub fn new(chars: &[char], quoted: &[bool], ignore_case: bool) -> Self {
+        let mut items = Vec::new();
+        let mut idx = 0;
+        while idx < chars.len() {
+            if quoted[idx] {
+                items.push(Item::Char(chars[idx]));
+                idx += 1;
+                continue;
+            }
+            match chars[idx] {
+                '*' => {
+                    if items.last() != Some(&Item::AnyString) {
+                        items.push(Item::AnyString);
+                    }
+                }
+                '?' => items.push(Item::AnyChar),
+                '[' => match parse_class(chars, quoted, idx + 1) {
+                    Some((class, next)) => {
+                        items.push(class);
+                        idx = next;
+                        continue;
+                    }
+                    None => items.push(Item::Char('[')),
+                },
+                char => items.push(Item::Char(char)),
+            }
+            idx += 1;
+        }
+        if ignore_case {
+            for item in &mut items {
+                if let Item::Char(char) = item {
+                    *char = lowercase(*char);
+                }
+            }
+        }
+        Self { items, ignore_case }
+    }
+
+    // A pattern in which a backslash quotes the next character.
+    pub fn escaped(pattern: &str, ignore_case: bool) -> Self {
+        let (mut chars, mut quoted) = (Vec::new(), Vec::new());
+        let mut iter = pattern.chars().peekable();
+        while let Some(char) = iter.next() {
+            match iter.peek() {
+                Some(next) if char == '\\' => {
+                    chars.push(*next);
+                    quoted.push(true);
+                    iter.next();
+                }
+                _ => {
+                    chars.push(char);
+                    quoted.push(false);
+                }
+            }
+        }
+        Self::new(&chars, &quoted, ignore_case)
+    }
+
+    pub fn is_literal(&self) -> bool {
+        self.items.iter().all(|item| matches!(item, Item::Char(_)))
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        let name: Vec<char> = match self.ignore_case {
+            true => name.chars().map(lowercase).collect(),
+            false => name.chars().collect(),
+        };
+        let (mut p_idx, mut n_idx) = (0, 0);
+        let mut backtrack: Option<(usize, usize)> = None;
+        while n_idx < name.len() {
+            match self.items.get(p_idx) {
+                Some(Item::AnyString) => {
+                    backtrack = Some((p_idx, n_idx));
+                    p_idx += 1;
+                    continue;
+                }
+                Some(item) if self.matches_char(item, name[n_idx]) => {
+                    p_idx += 1;
+                    n_idx += 1;
+                    continue;
+                }
+                _ => {}
+            }
+            match backtrack {
+                Some((star_idx, star_n_idx)) => {
+                    p_idx = star_idx + 1;
+                    n_idx = star_n_idx + 1;
+                    backtrack = Some((star_idx, star_n_idx + 1));
+                }
+                None => return false,
+            }
+        }
+        self.items[p_idx..]
+            .iter()
+            .all(|item| *item == Item::AnyString)
+    }
+
+    fn matches_char(&self, item: &Item, char: char) -> bool {
+        match item {
+            Item::Char(expected) => *expected == char,
+            Item::AnyChar => true,
+            Item::AnyString => false,
+            Item::Class { negated, ranges } => {
+                let upper = match self.ignore_case {
+                    true => char.to_uppercase().next().unwrap_or(char),
+                    false => char,
+                };
+                ranges.iter().any(|(low, high)| {
+                    (*low..=*high).contains(&char) || (*low..=*high).contains(&upper)
+                }) != *negated
+            }
+        }
+    }
+}
+
+fn lowercase(char: char) -> char {
+    char.to_lowercase().next().unwrap_or(char)
+}
+
+fn parse_class(chars: &[char], quoted: &[bool], start: usize) -> Option<(Item, usize)> {
+    let mut idx = start;
+    let negated = matches!(chars.get(idx), Some('!' | '^')) && !quoted[idx];
+    if negated {
+        idx += 1;
+    }
+
+    let mut ranges = Vec::new();
+    let first = idx;
+    while idx < chars.len() {
+        if chars[idx] == ']' && !quoted[idx] && idx > first {
+            return Some((Item::Class { negated, ranges }, idx + 1));
+        }
+        if chars[idx] == '['
+            && !quoted[idx]
+            && chars.get(idx + 1) == Some(&':')
+            && let Some((mut class_ranges, next)) = parse_named_class(chars, idx + 2)
+        {
+            ranges.append(&mut class_ranges);
+            idx = next;
+            continue;
+        }
+        if chars.get(idx + 1) == Some(&'-')
+            && chars
+                .get(idx + 2)
+                .is_some_and(|char| *char != ']' || quoted[idx + 2])
+        {
+            ranges.push((chars[idx], chars[idx + 2]));
+            idx += 3;
+        } else {
+            ranges.push((chars[idx], chars[idx]));
+            idx += 1;
+        }
+    }
+    None
+}
+
+fn parse_named_class(chars: &[char], start: usize) -> Option<(Vec<(char, char)>, usize)> {
+    let end = start
+        + chars[start..]
+            .windows(2)
+            .position(|pair| pair == [':', ']'])?;
+    let name: String = chars[start..end].iter().collect();
+    let ranges = match name.as_str() {
+        "alpha" => vec![('a', 'z'), ('A', 'Z')],
+        "digit" => vec![('0', '9')],
+        "alnum" => vec![('a', 'z'), ('A', 'Z'), ('0', '9')],
+        "upper" => vec![('A', 'Z')],
+        "lower" => vec![('a', 'z')],
+        "space" => vec![('\t', '\r'), (' ', ' ')],
+        "xdigit" => vec![('0', '9'), ('a', 'f'), ('A', 'F')],
+        "punct" => vec![('!', '/'), (':', '@'), ('[', '`'), ('{', '~')],
+        _ => return None,
+    };
+    Some((ranges, end + 2))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::glob::Pattern;
+
+    #[test]
+    fn check_glob() {
+        let matches = |pattern: &str, name: &str| Pattern::escaped(pattern, false).matches(name);
+        assert!(matches("*.txt", "a.txt"));
+        assert!(matches("*", ""));
+        assert!(matches("*", ".hidden"));
+        assert!(!matches("*.txt", "a.txt.bak"));
+        assert!(matches("a*b*c", "aXXbYbc"));
+        assert!(matches("./src/*", "./src/a/b.rs"));
+        assert!(matches("?.rs", "é.rs"));
+        assert!(!matches("?.rs", "ab.rs"));
+        assert!(matches("?[0-9][!a]", "x1b"));
+        assert!(!matches("?[0-9][^a]", "x1a"));
+        assert!(matches("[]]", "]"));
+        assert!(matches("[\\]a]", "]"));
+        assert!(matches("[[:upper:]]*", "Cargo.toml"));
+        assert!(matches("[abc", "[abc"));
+        assert!(matches("\\*", "*"));
+        assert!(!matches("\\*", "x"));
+        assert!(!matches("*.RS", "main.rs"));
+        assert!(Pattern::escaped("*.RS", true).matches("main.rs"));
+        assert!(Pattern::escaped("[A-Z]*", true).matches("cargo"));
+        assert!(Pattern::escaped("a\\*", false).is_literal());
+        assert!(!Pattern::escaped("a*", false).is_literal());
+
+        let chars: Vec<char> = "[!a]*".chars().collect();
+        let pattern = Pattern::new(&chars, &[false, true, false, false, false], false);
+        assert!(pattern.matches("!x"));
+        assert!(!pattern.matches("bx"));
+    }
+}
@@ -0,0 +1,372 @@
+mod class;
+pub mod glob;
+mod parser;
+
+pub use {
+    class::{Class, ClassError},
+    parser::{ParseError, Parsed, Syntax, parse},
+};
+
+use std::collections::HashSet;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Assert {
+    LineStart,
+    LineEnd,
+    WordStart,
+    WordEnd,
+    WordBoundary,
+    NotWordBoundary,
+    NoWordBefore,
+    NoWordAfter,
+}
+
+impl Assert {
+    fn holds(self, text: &[char], pos: usize) -> bool {
+        let before = pos > 0 && is_word(text[pos - 1]);
+        let after = text.get(pos).is_some_and(|char| is_word(*char));
+        match self {
+            Assert::LineStart => pos == 0,
+            Assert::LineEnd => pos == text.len(),
+            Assert::WordStart => !before && after,
+            Assert::WordEnd => before && !after,
+            Assert::WordBoundary => before != after,
+            Assert::NotWordBoundary => before == after,
+            Assert::NoWordBefore => !before,
+            Assert::NoWordAfter => !after,
+        }
+    }
+}
+
+// A parsed expression. Groups are numbered from 1 in the order they open.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Node {
+    Char(char),
+    Any,
+    Class(Class),
+    Assert(Assert),
+    Group(Box<Node>, usize),
+    Backref(usize),
+    Concat(Vec<Node>),
+    Alternation(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum Inst {
+    Char(char),
+    Any,
+    Class(Class),
+    Assert(Assert),
+    Backref(usize),
+    Save(usize),
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+enum Job {
+    Step(usize, usize),
+    Restore(usize, Option<usize>),
+}
+
+// Without back-references a state is just an instruction and a position, so
+// each one is tried once. With them the captured groups are part of the state.
+enum Visited {
+    States(Vec<u64>, usize),
+    Captures(HashSet<(usize, usize, Vec<Option<usize>>)>),
+}
+
+impl Visited {
+    fn insert(&mut self, pc: usize, pos: usize, slots: &[Option<usize>]) -> bool {
+        match self {
+            Visited::States(bits, width) => {
+                let state = pc * *width + pos;
+                let (word, bit) = (state / 64, 1 << (state % 64));
+                let new = bits[word] & bit == 0;
+                bits[word] |= bit;
+                new
+            }
+            Visited::Captures(states) => states.insert((pc, pos, slots.to_vec())),
+        }
+    }
+}
+
+type Spans = Vec<Option<(usize, usize)>>;
+
+// A compiled expression run by a backtracking machine. Positions are counted
+// in characters.
+#[derive(Debug, Clone)]
+pub struct Program {
+    insts: Vec<Inst>,
+    groups: usize,
+    backrefs: bool,
+    ignore_case: bool,
+}
+
+impl Program {
+    pub fn new(node: &Node, groups: usize, ignore_case: bool) -> Self {
+        let mut insts = vec![Inst::Save(0)];
+        compile(node, &mut insts);
+        insts.push(Inst::Save(1));
+        insts.push(Inst::Match);
+        Self {
+            backrefs: insts.iter().any(|inst| matches!(inst, Inst::Backref(_))),
+            insts,
+            groups,
+            ignore_case,
+        }
+    }
+
+    // The leftmost match found first, with the spans of the whole match and
+    // of each group, as for `[[ =~ ]]`.
+    pub fn captures(&self, text: &[char], from: usize) -> Option<Spans> {
+        self.search(text, from, false)
+    }
+
+    // The leftmost-longest match, as in grep.
+    pub fn find(&self, text: &[char], from: usize) -> Option<(usize, usize)> {
+        self.search(text, from, true)?[0]
+    }
+
+    fn search(&self, text: &[char], from: usize, longest: bool) -> Option<Spans> {
+        let mut visited = match self.backrefs {
+            true => Visited::Captures(HashSet::new()),
+            false => {
+                let width = text.len() + 1;
+                Visited::States(vec![0; (self.insts.len() * width).div_ceil(64)], width)
+            }
+        };
+        let slots =
+            (from..=text.len()).find_map(|start| self.run(text, start, longest, &mut visited))?;
+        Some(
+            slots
+                .chunks(2)
+                .map(|slot| match slot {
+                    [Some(from), Some(to)] => Some((*from, *to)),
+                    _ => None,
+                })
+                .collect(),
+        )
+    }
+
+    fn run(
+        &self,
+        text: &[char],
+        start: usize,
+        longest: bool,
+        visited: &mut Visited,
+    ) -> Option<Vec<Option<usize>>> {
+        let mut slots = vec![None; 2 * (self.groups + 1)];
+        let mut stack = vec![Job::Step(0, start)];
+        let mut best: Option<Vec<Option<usize>>> = None;
+        while let Some(job) = stack.pop() {
+            let (mut pc, mut pos) = match job {
+                Job::Restore(slot, value) => {
+                    slots[slot] = value;
+                    continue;
+                }
+                Job::Step(pc, pos) => (pc, pos),
+            };
+            loop {
+                if !visited.insert(pc, pos, &slots) {
+                    break;
+                }
+
+                match &self.insts[pc] {
+                    Inst::Char(char) if text.get(pos).is_some_and(|c| self.equal(*c, *char)) => {
+                        pos += 1
+                    }
+                    Inst::Any if pos < text.len() => pos += 1,
+                    Inst::Class(class)
+                        if text
+                            .get(pos)
+                            .is_some_and(|char| class.matches(*char, self.ignore_case)) =>
+                    {
+                        pos += 1
+                    }
+                    Inst::Assert(assert) if assert.holds(text, pos) => {}
+                    Inst::Backref(group) => match (slots[2 * group], slots[2 * group + 1]) {
+                        (Some(from), Some(to))
+                            if text.len() - pos >= to - from
+                                && (from..to)
+                                    .zip(pos..)
+                                    .all(|(left, right)| self.equal(text[left], text[right])) =>
+                        {
+                            pos += to - from
+                        }
+                        _ => break,
+                    },
+                    Inst::Save(slot) => {
+                        stack.push(Job::Restore(*slot, slots[*slot]));
+                        slots[*slot] = Some(pos);
+                    }
+                    Inst::Split(first, second) => {
+                        stack.push(Job::Step(*second, pos));
+                        pc = *first;
+                        continue;
+                    }
+                    Inst::Jump(target) => {
+                        pc = *target;
+                        continue;
+                    }
+                    Inst::Match if !longest => return Some(slots),
+                    Inst::Match => {
+                        if best.as_ref().is_none_or(|best| best[1] < slots[1]) {
+                            best = Some(slots.clone());
+                        }
+                        break;
+                    }
+                    _ => break,
+                }
+                pc += 1;
+            }
+        }
+        best
+    }
+
+    fn equal(&self, left: char, right: char) -> bool {
+        left == right || self.ignore_case && fold(left) == fold(right)
+    }
+}
+
+fn compile(node: &Node, insts: &mut Vec<Inst>) {
+    match node {
+        Node::Char(char) => insts.push(Inst::Char(*char)),
+        Node::Any => insts.push(Inst::Any),
+        Node::Class(class) => insts.push(Inst::Class(class.clone())),
+        Node::Assert(assert) => insts.push(Inst::Assert(*assert)),
+        Node::Backref(group) => insts.push(Inst::Backref(*group)),
+        Node::Group(node, group) => {
+            insts.push(Inst::Save(2 * group));
+            compile(node, insts);
+            insts.push(Inst::Save(2 * group + 1));
+        }
+        Node::Concat(nodes) => nodes.iter().for_each(|node| compile(node, insts)),
+        Node::Alternation(branches) => {
+            let mut jumps = Vec::new();
+            for (idx, branch) in branches.iter().enumerate() {
+                let split = insts.len();
+                if idx + 1 < branches.len() {
+                    insts.push(Inst::Split(split + 1, 0));
+                }
+                compile(branch, insts);
+                if idx + 1 < branches.len() {
+                    jumps.push(insts.len());
+                    insts.push(Inst::Jump(0));
+                    insts[split] = Inst::Split(split + 1, insts.len());
+                }
+            }
+            let end = insts.len();
+            for jump in jumps {
+                insts[jump] = Inst::Jump(end);
+            }
+        }
+        Node::Repeat { node, min, max } => {
+            for _ in 0..*min {
+                compile(node, insts);
+            }
+            match max {
+                None => {
+                    let split = insts.len();
+                    insts.push(Inst::Split(split + 1, 0));
+                    compile(node, insts);
+                    insts.push(Inst::Jump(split));
+                    insts[split] = Inst::Split(split + 1, insts.len());
+                }
+                Some(max) => {
+                    let mut splits = Vec::new();
+                    for _ in *min..*max {
+                        splits.push(insts.len());
+                        insts.push(Inst::Split(insts.len() + 1, 0));
+                        compile(node, insts);
+                    }
+                    let end = insts.len();
+                    for split in splits {
+                        insts[split] = Inst::Split(split + 1, end);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn is_word(char: char) -> bool {
+    char.is_alphanumeric() || char == '_'
+}
+
+fn fold(char: char) -> char {
+    char.to_lowercase().next().unwrap_or(char)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Assert, Node, Program};
+
+    #[test]
+    fn check_program() {
+        let chars = |text: &str| text.chars().map(Node::Char).collect::<Vec<Node>>();
+        let text: Vec<char> = "xabab-ab".chars().collect();
+
+        // (a|ab)(b*)
+        let node = Node::Concat(vec![
+            Node::Group(
+                Box::new(Node::Alternation(vec![
+                    Node::Concat(chars("a")),
+                    Node::Concat(chars("ab")),
+                ])),
+                1,
+            ),
+            Node::Group(
+                Box::new(Node::Repeat {
+                    node: Box::new(Node::Char('b')),
+                    min: 0,
+                    max: None,
+                }),
+                2,
+            ),
+        ]);
+        let program = Program::new(&node, 2, false);
+        assert_eq!(
+            program.captures(&text, 0),
+            Some(vec![Some((1, 3)), Some((1, 2)), Some((2, 3))])
+        );
+        assert_eq!(program.find(&text, 0), Some((1, 3)));
+        assert_eq!(program.find(&text, 4), Some((6, 8)));
+
+        // \<(ab)-\1$ ignoring case
+        let node = Node::Concat(vec![
+            Node::Assert(Assert::WordStart),
+            Node::Group(Box::new(Node::Concat(chars("AB"))), 1),
+            Node::Char('-'),
+            Node::Backref(1),
+            Node::Assert(Assert::LineEnd),
+        ]);
+        let text: Vec<char> = "xab ab-AB".chars().collect();
+        assert_eq!(Program::new(&node, 1, true).find(&text, 0), Some((4, 9)));
+        assert_eq!(Program::new(&node, 1, false).find(&text, 0), None);
+
+        // (a*)*b never matches, and must not loop
+        let node = Node::Concat(vec![
+            Node::Repeat {
+                node: Box::new(Node::Group(
+                    Box::new(Node::Repeat {
+                        node: Box::new(Node::Char('a')),
+                        min: 0,
+                        max: None,
+                    }),
+                    1,
+                )),
+                min: 0,
+                max: None,
+            },
+            Node::Char('b'),
+        ]);
+        let text: Vec<char> = "aaac".chars().collect();
+        assert_eq!(Program::new(&node, 1, false).captures(&text, 0), None);
+    }
+}
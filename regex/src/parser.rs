@@ -0,0 +1,295 @@
+use crate::{Assert, Class, ClassError, Node};
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Syntax {
+    #[default]
+    Basic,
+    Extended,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseError {
+    UnmatchedOpen,
+    UnmatchedClose,
+    UnmatchedBrace,
+    InvalidInterval,
+    InvalidBackref,
+    TrailingBackslash,
+    Class(ClassError),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Parsed {
+    pub node: Node,
+    pub groups: usize,
+    // A repetition operator with nothing before it in an extended expression.
+    // It is skipped, which grep warns about and `[[ =~ ]]` refuses.
+    pub dangling: Option<char>,
+}
+
+// Parses both syntaxes. In basic expressions `(`, `)`, `{`, `}`, `|`, `+` and
+// `?` are operators only when escaped, in extended ones only when they are not.
+pub fn parse(pattern: &str, syntax: Syntax) -> Result<Parsed, ParseError> {
+    let mut parser = Parser {
+        chars: pattern.chars().collect(),
+        idx: 0,
+        groups: 0,
+        closed: vec![],
+        dangling: None,
+        extended: syntax == Syntax::Extended,
+    };
+    let node = parser.parse_alternation(0)?;
+    Ok(Parsed {
+        node,
+        groups: parser.groups,
+        dangling: parser.dangling,
+    })
+}
+
+struct Parser {
+    chars: Vec<char>,
+    idx: usize,
+    groups: usize,
+    closed: Vec<usize>,
+    dangling: Option<char>,
+    extended: bool,
+}
+
+impl Parser {
+    fn parse_alternation(&mut self, depth: usize) -> Result<Node, ParseError> {
+        let mut branches = vec![self.parse_concat(depth)?];
+        while self.operator() == Some('|') {
+            self.skip_operator();
+            branches.push(self.parse_concat(depth)?);
+        }
+        Ok(match branches.len() {
+            1 => branches.remove(0),
+            _ => Node::Alternation(branches),
+        })
+    }
+
+    fn parse_concat(&mut self, depth: usize) -> Result<Node, ParseError> {
+        let mut nodes = Vec::new();
+        while self.idx < self.chars.len() {
+            match self.operator() {
+                Some('|') => break,
+                Some(')') if depth > 0 => break,
+                Some(')') if !self.extended => return Err(ParseError::UnmatchedClose),
+                Some(operator @ ('*' | '+' | '?')) if self.extended && nodes.is_empty() => {
+                    self.dangling.get_or_insert(operator);
+                    self.skip_operator();
+                    continue;
+                }
+                _ => {}
+            }
+            let mut node =
+                self.parse_atom(nodes.is_empty() || nodes == [Node::Assert(Assert::LineStart)])?;
+            while let Some((min, max)) = self.parse_quantifier()? {
+                node = Node::Repeat {
+                    node: Box::new(node),
+                    min,
+                    max,
+                };
+            }
+            nodes.push(node);
+        }
+        Ok(Node::Concat(nodes))
+    }
+
+    fn parse_atom(&mut self, leading: bool) -> Result<Node, ParseError> {
+        match self.operator() {
+            Some('(') => {
+                self.skip_operator();
+                self.groups += 1;
+                let group = self.groups;
+                let node = self.parse_alternation(1)?;
+                if self.operator() != Some(')') {
+                    return Err(ParseError::UnmatchedOpen);
+                }
+                self.skip_operator();
+                self.closed.push(group);
+                return Ok(Node::Group(Box::new(node), group));
+            }
+            Some(operator @ ('*' | '{')) if leading => {
+                self.skip_operator();
+                return Ok(Node::Char(operator));
+            }
+            Some(operator) if operator != '*' && !self.extended => {
+                self.skip_operator();
+                return Ok(Node::Char(operator));
+            }
+            _ => {}
+        }
+
+        let char = self.next().unwrap();
+        Ok(match char {
+            '[' => Node::Class(self.parse_class()?),
+            '.' => Node::Any,
+            '^' if self.extended || leading => Node::Assert(Assert::LineStart),
+            '$' if self.extended || self.at_branch_end() => Node::Assert(Assert::LineEnd),
+            '\\' => match self.next() {
+                Some(digit @ '1'..='9') => {
+                    let group = digit.to_digit(10).unwrap() as usize;
+                    if !self.closed.contains(&group) {
+                        return Err(ParseError::InvalidBackref);
+                    }
+                    Node::Backref(group)
+                }
+                Some('w') => Node::Class(Class::named("alnum", false, &['_'])),
+                Some('W') => Node::Class(Class::named("alnum", true, &['_'])),
+                Some('s') => Node::Class(Class::named("space", false, &[])),
+                Some('S') => Node::Class(Class::named("space", true, &[])),
+                Some('<') => Node::Assert(Assert::WordStart),
+                Some('>') => Node::Assert(Assert::WordEnd),
+                Some('b') => Node::Assert(Assert::WordBoundary),
+                Some('B') => Node::Assert(Assert::NotWordBoundary),
+                Some('`') => Node::Assert(Assert::LineStart),
+                Some('\'') => Node::Assert(Assert::LineEnd),
+                Some(char) => Node::Char(char),
+                None => return Err(ParseError::TrailingBackslash),
+            },
+            char => Node::Char(char),
+        })
+    }
+
+    fn parse_quantifier(&mut self) -> Result<Option<(usize, Option<usize>)>, ParseError> {
+        let quantifier = match self.operator() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => return self.parse_interval(),
+            _ => return Ok(None),
+        };
+        self.skip_operator();
+        Ok(Some(quantifier))
+    }
+
+    // An invalid interval is an error in basic expressions and a literal `{`
+    // in extended ones.
+    fn parse_interval(&mut self) -> Result<Option<(usize, Option<usize>)>, ParseError> {
+        let start = self.idx + if self.extended { 1 } else { 2 };
+        let closing: &[char] = if self.extended { &['}'] } else { &['\\', '}'] };
+        let Some(length) = self.chars[start..]
+            .windows(closing.len())
+            .position(|window| window == closing)
+        else {
+            return match self.extended {
+                true => Ok(None),
+                false => Err(ParseError::UnmatchedBrace),
+            };
+        };
+        let body: String = self.chars[start..start + length].iter().collect();
+        let parse = |number: &str| number.parse::<usize>().ok();
+        let interval = match body.split_once(',') {
+            Some((min, "")) => parse(min).map(|min| (min, None)),
+            Some(("", max)) => parse(max).map(|max| (0, Some(max))),
+            Some((min, max)) => parse(min)
+                .zip(parse(max))
+                .map(|(min, max)| (min, Some(max))),
+            None => parse(&body).map(|count| (count, Some(count))),
+        };
+        match interval {
+            Some((min, max)) if max.is_none_or(|max| min <= max) => {
+                self.idx = start + length + closing.len();
+                Ok(Some((min, max)))
+            }
+            _ if self.extended => Ok(None),
+            _ => Err(ParseError::InvalidInterval),
+        }
+    }
+
+    fn parse_class(&mut self) -> Result<Class, ParseError> {
+        let (class, next) = Class::parse(&self.chars, self.idx).map_err(ParseError::Class)?;
+        self.idx = next;
+        Ok(class)
+    }
+
+    // Returns the operator at the current position, if there is one.
+    fn operator(&self) -> Option<char> {
+        let char = self.peek()?;
+        match (self.extended, char) {
+            (_, '*') => Some('*'),
+            (true, '|' | '(' | ')' | '+' | '?' | '{') => Some(char),
+            (false, '\\') => self
+                .chars
+                .get(self.idx + 1)
+                .filter(|next| matches!(next, '|' | '(' | ')' | '+' | '?' | '{' | '}'))
+                .copied(),
+            _ => None,
+        }
+    }
+
+    fn skip_operator(&mut self) {
+        self.idx += if self.extended || self.peek() == Some('*') {
+            1
+        } else {
+            2
+        };
+    }
+
+    fn at_branch_end(&self) -> bool {
+        self.idx == self.chars.len() || matches!(self.operator(), Some('|' | ')'))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.idx).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let char = self.peek()?;
+        self.idx += 1;
+        Some(char)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        ClassError, Program,
+        parser::{ParseError, Syntax, parse},
+    };
+
+    fn find(pattern: &str, syntax: Syntax, text: &str) -> Option<String> {
+        let parsed = parse(pattern, syntax).unwrap();
+        let text: Vec<char> = text.chars().collect();
+        let (from, to) = Program::new(&parsed.node, parsed.groups, false).find(&text, 0)?;
+        Some(text[from..to].iter().collect())
+    }
+
+    #[test]
+    fn check_parse() {
+        let (basic, extended) = (Syntax::Basic, Syntax::Extended);
+        assert_eq!(find("a|ab", extended, "xab"), Some("ab".into()));
+        assert_eq!(find("a\\|ab", basic, "xab"), Some("ab".into()));
+        assert_eq!(find("a|b", basic, "a|b"), Some("a|b".into()));
+        assert_eq!(find("\\(ab\\)*c", basic, "ababc"), Some("ababc".into()));
+        assert_eq!(find("(ab)*c", basic, "(ab)c"), Some("(ab)c".into()));
+        assert_eq!(find("x\\{2,\\}", basic, "xxxx"), Some("xxxx".into()));
+        assert_eq!(find("*a", basic, "b*a"), Some("*a".into()));
+        assert_eq!(find("a^b$c", basic, "a^b$c"), Some("a^b$c".into()));
+        assert_eq!(find("\\(.\\)\\1", basic, "abccd"), Some("cc".into()));
+        assert_eq!(
+            find("([a-z]+)-\\1", extended, "ab-ab-b"),
+            Some("ab-ab".into())
+        );
+        assert_eq!(find("a{,2}b", extended, "aaab"), Some("aab".into()));
+        assert_eq!(find("a{x", extended, "a{x"), Some("a{x".into()));
+        assert_eq!(find("a)", extended, "a)"), Some("a)".into()));
+        assert_eq!(find("\\<is\\>", basic, "this is"), Some("is".into()));
+        assert_eq!(find("[[:digit:]]+", extended, "ab123"), Some("123".into()));
+        assert_eq!(find("^(a*)*$", extended, "aaab"), None);
+
+        assert_eq!(parse("b|*a", extended).unwrap().dangling, Some('*'));
+        assert_eq!(parse("\\(a", basic), Err(ParseError::UnmatchedOpen));
+        assert_eq!(parse("a\\)", basic), Err(ParseError::UnmatchedClose));
+        assert_eq!(parse("a\\{1", basic), Err(ParseError::UnmatchedBrace));
+        assert_eq!(parse("a\\{2,1\\}", basic), Err(ParseError::InvalidInterval));
+        assert_eq!(parse("(a", extended), Err(ParseError::UnmatchedOpen));
+        assert_eq!(parse("\\1", extended), Err(ParseError::InvalidBackref));
+        assert_eq!(parse("a\\", extended), Err(ParseError::TrailingBackslash));
+        assert_eq!(
+            parse("[z-a]", extended),
+            Err(ParseError::Class(ClassError::InvalidRange('z', 'a')))
+        );
+    }
+}
@@ -0,0 +1,406 @@
+use crate::global_state::environment::Environment;
+
+const MAX_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Item {
+    Number(i64),
+    Name(String),
+    Operator(&'static str),
+    Open,
+    Close,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expression {
+    Number(i64),
+    Variable(String),
+    Unary(&'static str, Box<Expression>),
+    Binary(&'static str, Box<Expression>, Box<Expression>),
+    Assign(String, &'static str, Box<Expression>),
+    Step {
+        name: String,
+        delta: i64,
+        prefix: bool,
+    },
+    Ternary(Box<Expression>, Box<Expression>, Box<Expression>),
+}
+
+const OPERATORS: &[&str] = &[
+    "<<=", ">>=", "**", "++", "--", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "+=", "-=",
+    "*=", "/=", "%=", "&=", "^=", "|=", "+", "-", "*", "/", "%", "<", ">", "&", "|", "^", "!", "~",
+    "?", ":", "=", ",",
+];
+
+pub fn evaluate(expression: &str, environment: &mut Environment) -> anyhow::Result<i64> {
+    evaluate_with_depth(expression, environment, 0)
+}
+
+fn evaluate_with_depth(
+    expression: &str,
+    environment: &mut Environment,
+    depth: usize,
+) -> anyhow::Result<i64> {
+    if depth > MAX_DEPTH {
+        anyhow::bail!("{}: expression recursion level exceeded", expression.trim())
+    }
+    let items = tokenize(expression)?;
+    if items.is_empty() {
+        return Ok(0);
+    }
+    let mut parser = ExpressionParser { items, position: 0 };
+    let tree = parser.parse_comma()?;
+    if let Some(item) = parser.items.get(parser.position) {
+        anyhow::bail!(
+            "{}: syntax error in expression (error token is {:?})",
+            expression.trim(),
+            item
+        )
+    }
+    Evaluator { environment, depth }.evaluate(&tree)
+}
+
+fn tokenize(expression: &str) -> anyhow::Result<Vec<Item>> {
+    let bytes = expression.as_bytes();
+    let mut items = Vec::new();
+    let mut idx = 0;
+    'outer: while idx < bytes.len() {
+        let byte = bytes[idx];
+        if byte.is_ascii_whitespace() {
+            idx += 1;
+            continue;
+        }
+        if byte.is_ascii_digit() {
+            let start = idx;
+            while idx < bytes.len() && (bytes[idx].is_ascii_alphanumeric() || bytes[idx] == b'#') {
+                idx += 1;
+            }
+            items.push(Item::Number(parse_number(&expression[start..idx])?));
+            continue;
+        }
+        if byte.is_ascii_alphabetic() || byte == b'_' || byte == b'$' {
+            let start = if byte == b'$' { idx + 1 } else { idx };
+            idx = start;
+            while idx < bytes.len() && (bytes[idx].is_ascii_alphanumeric() || bytes[idx] == b'_') {
+                idx += 1;
+            }
+            items.push(Item::Name(expression[start..idx].to_string()));
+            continue;
+        }
+        match byte {
+            b'(' => items.push(Item::Open),
+            b')' => items.push(Item::Close),
+            _ => {
+                for operator in OPERATORS {
+                    if bytes[idx..].starts_with(operator.as_bytes()) {
+                        items.push(Item::Operator(operator));
+                        idx += operator.len();
+                        continue 'outer;
+                    }
+                }
+                anyhow::bail!(
+                    "{}: syntax error: operand expected (error token is {:?})",
+                    expression.trim(),
+                    &expression[idx..]
+                )
+            }
+        }
+        idx += 1;
+    }
+    Ok(items)
+}
+
+fn parse_number(text: &str) -> anyhow::Result<i64> {
+    let error = || anyhow::Error::msg(format!("{}: value too great for base", text));
+    if let Some((base, digits)) = text.split_once('#') {
+        let base = base.parse::<u32>().map_err(|_| error())?;
+        if !(2..=36).contains(&base) {
+            anyhow::bail!("{}: invalid arithmetic base", text)
+        }
+        return i64::from_str_radix(digits, base).map_err(|_| error());
+    }
+    if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        return i64::from_str_radix(hex, 16).map_err(|_| error());
+    }
+    if text.len() > 1 && text.starts_with('0') {
+        return i64::from_str_radix(&text[1..], 8).map_err(|_| error());
+    }
+    text.parse::<i64>().map_err(|_| error())
+}
+
+struct ExpressionParser {
+    items: Vec<Item>,
+    position: usize,
+}
+
+impl ExpressionParser {
+    fn peek_operator(&self) -> Option<&'static str> {
+        match self.items.get(self.position) {
+            Some(Item::Operator(operator)) => Some(operator),
+            _ => None,
+        }
+    }
+
+    fn next_if_operator(&mut self, operators: &[&str]) -> Option<&'static str> {
+        let operator = self
+            .peek_operator()
+            .filter(|operator| operators.contains(operator))?;
+        self.position += 1;
+        Some(operator)
+    }
+
+    fn parse_comma(&mut self) -> anyhow::Result<Expression> {
+        let mut left = self.parse_assignment()?;
+        while self.next_if_operator(&[","]).is_some() {
+            let right = self.parse_assignment()?;
+            left = Expression::Binary(",", Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_assignment(&mut self) -> anyhow::Result<Expression> {
+        if let (Some(Item::Name(name)), Some(Item::Operator(operator))) = (
+            self.items.get(self.position).cloned(),
+            self.items.get(self.position + 1).cloned(),
+        ) && matches!(
+            operator,
+            "=" | "+=" | "-=" | "*=" | "/=" | "%=" | "<<=" | ">>=" | "&=" | "^=" | "|="
+        ) {
+            self.position += 2;
+            let value = self.parse_assignment()?;
+            return Ok(Expression::Assign(
+                name,
+                operator.trim_end_matches('='),
+                Box::new(value),
+            ));
+        }
+        self.parse_ternary()
+    }
+
+    fn parse_ternary(&mut self) -> anyhow::Result<Expression> {
+        let condition = self.parse_binary(0)?;
+        if self.next_if_operator(&["?"]).is_none() {
+            return Ok(condition);
+        }
+        let positive = self.parse_assignment()?;
+        if self.next_if_operator(&[":"]).is_none() {
+            anyhow::bail!("`:' expected for conditional expression")
+        }
+        let negative = self.parse_assignment()?;
+        Ok(Expression::Ternary(
+            Box::new(condition),
+            Box::new(positive),
+            Box::new(negative),
+        ))
+    }
+
+    fn parse_binary(&mut self, level: usize) -> anyhow::Result<Expression> {
+        const LEVELS: [&[&str]; 10] = [
+            &["||"],
+            &["&&"],
+            &["|"],
+            &["^"],
+            &["&"],
+            &["==", "!="],
+            &["<", "<=", ">", ">="],
+            &["<<", ">>"],
+            &["+", "-"],
+            &["*", "/", "%"],
+        ];
+        if level == LEVELS.len() {
+            return self.parse_power();
+        }
+        let mut left = self.parse_binary(level + 1)?;
+        while let Some(operator) = self.next_if_operator(LEVELS[level]) {
+            let right = self.parse_binary(level + 1)?;
+            left = Expression::Binary(operator, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_power(&mut self) -> anyhow::Result<Expression> {
+        let base = self.parse_unary()?;
+        if self.next_if_operator(&["**"]).is_some() {
+            let exponent = self.parse_power()?;
+            return Ok(Expression::Binary("**", Box::new(base), Box::new(exponent)));
+        }
+        Ok(base)
+    }
+
+    fn parse_unary(&mut self) -> anyhow::Result<Expression> {
+        if let Some(operator) = self.next_if_operator(&["++", "--"]) {
+            return match self.items.get(self.position).cloned() {
+                Some(Item::Name(name)) => {
+                    self.position += 1;
+                    Ok(Expression::Step {
+                        name,
+                        delta: if operator == "++" { 1 } else { -1 },
+                        prefix: true,
+                    })
+                }
+                _ => anyhow::bail!("syntax error: operand expected"),
+            };
+        }
+        if let Some(operator) = self.next_if_operator(&["!", "~", "-", "+"]) {
+            let operand = self.parse_unary()?;
+            return Ok(Expression::Unary(operator, Box::new(operand)));
+        }
+        self.parse_postfix()
+    }
+
+    fn parse_postfix(&mut self) -> anyhow::Result<Expression> {
+        let item = self.items.get(self.position).cloned();
+        self.position += 1;
+        match item {
+            Some(Item::Number(number)) => Ok(Expression::Number(number)),
+            Some(Item::Name(name)) => match self.next_if_operator(&["++", "--"]) {
+                Some(operator) => Ok(Expression::Step {
+                    name,
+                    delta: if operator == "++" { 1 } else { -1 },
+                    prefix: false,
+                }),
+                None => Ok(Expression::Variable(name)),
+            },
+            Some(Item::Open) => {
+                let inner = self.parse_comma()?;
+                match self.items.get(self.position) {
+                    Some(Item::Close) => {
+                        self.position += 1;
+                        Ok(inner)
+                    }
+                    _ => anyhow::bail!("missing `)'"),
+                }
+            }
+            _ => anyhow::bail!("syntax error: operand expected"),
+        }
+    }
+}
+
+struct Evaluator<'a> {
+    environment: &'a mut Environment,
+    depth: usize,
+}
+
+impl Evaluator<'_> {
+    fn evaluate(&mut self, expression: &Expression) -> anyhow::Result<i64> {
+        match expression {
+            Expression::Number(number) => Ok(*number),
+            Expression::Variable(name) => self.variable(name),
+            Expression::Unary(operator, operand) => {
+                let value = self.evaluate(operand)?;
+                Ok(match *operator {
+                    "!" => (value == 0) as i64,
+                    "~" => !value,
+                    "-" => value.wrapping_neg(),
+                    _ => value,
+                })
+            }
+            Expression::Binary("&&", left, right) => {
+                Ok((self.evaluate(left)? != 0 && self.evaluate(right)? != 0) as i64)
+            }
+            Expression::Binary("||", left, right) => {
+                Ok((self.evaluate(left)? != 0 || self.evaluate(right)? != 0) as i64)
+            }
+            Expression::Binary(operator, left, right) => {
+                let left = self.evaluate(left)?;
+                let right = self.evaluate(right)?;
+                apply(operator, left, right)
+            }
+            Expression::Assign(name, operator, value) => {
+                let mut value = self.evaluate(value)?;
+                if !operator.is_empty() {
+                    value = apply(operator, self.variable(name)?, value)?;
+                }
+                self.environment.insert(name, &value.to_string())?;
+                Ok(value)
+            }
+            Expression::Step {
+                name,
+                delta,
+                prefix,
+            } => {
+                let old_value = self.variable(name)?;
+                let new_value = old_value.wrapping_add(*delta);
+                self.environment.insert(name, &new_value.to_string())?;
+                Ok(if *prefix { new_value } else { old_value })
+            }
+            Expression::Ternary(condition, positive, negative) => {
+                if self.evaluate(condition)? != 0 {
+                    self.evaluate(positive)
+                } else {
+                    self.evaluate(negative)
+                }
+            }
+        }
+    }
+
+    fn variable(&mut self, name: &str) -> anyhow::Result<i64> {
+        match self.environment.get(name).cloned() {
+            Some(value) if !value.trim().is_empty() => {
+                evaluate_with_depth(&value, self.environment, self.depth + 1)
+            }
+            _ => Ok(0),
+        }
+    }
+}
+
+fn apply(operator: &str, left: i64, right: i64) -> anyhow::Result<i64> {
+    Ok(match operator {
+        "," => right,
+        "|" => left | right,
+        "^" => left ^ right,
+        "&" => left & right,
+        "==" => (left == right) as i64,
+        "!=" => (left != right) as i64,
+        "<" => (left < right) as i64,
+        "<=" => (left <= right) as i64,
+        ">" => (left > right) as i64,
+        ">=" => (left >= right) as i64,
+        "<<" => left.wrapping_shl(right as u32),
+        ">>" => left.wrapping_shr(right as u32),
+        "+" => left.wrapping_add(right),
+        "-" => left.wrapping_sub(right),
+        "*" => left.wrapping_mul(right),
+        "/" | "%" if right == 0 => anyhow::bail!("division by 0"),
+        "/" => left.wrapping_div(right),
+        "%" => left.wrapping_rem(right),
+        "**" if right < 0 => anyhow::bail!("exponent less than 0"),
+        "**" => left.wrapping_pow(right.min(u32::MAX as i64) as u32),
+        _ => anyhow::bail!("{}: unknown operator", operator),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{arithmetic::evaluate, global_state::environment::Environment};
+
+    #[test]
+    fn check_evaluate() {
+        let mut environment = Environment::default();
+
+        assert_eq!(evaluate("1 + 2 * 3", &mut environment).unwrap(), 7);
+        assert_eq!(evaluate("(1 + 2) * 3", &mut environment).unwrap(), 9);
+        assert_eq!(evaluate("2 ** 3 ** 2", &mut environment).unwrap(), 512);
+        assert_eq!(evaluate("-7 / 2", &mut environment).unwrap(), -3);
+        assert_eq!(evaluate("7 % 3 == 1 && !0", &mut environment).unwrap(), 1);
+        assert_eq!(
+            evaluate("0x1f + 010 + 2#101", &mut environment).unwrap(),
+            44
+        );
+        assert_eq!(evaluate("1 ? 2 : 3", &mut environment).unwrap(), 2);
+        assert_eq!(evaluate("", &mut environment).unwrap(), 0);
+        assert!(evaluate("1 / 0", &mut environment).is_err());
+        assert!(evaluate("1 +", &mut environment).is_err());
+
+        assert_eq!(evaluate("i = 5, i += 2, i", &mut environment).unwrap(), 7);
+        assert_eq!(evaluate("i++", &mut environment).unwrap(), 7);
+        assert_eq!(evaluate("++i", &mut environment).unwrap(), 9);
+        assert_eq!(evaluate("$i * 2", &mut environment).unwrap(), 18);
+        assert_eq!(environment.get("i").unwrap(), "9");
+
+        environment.insert("expr", "i + 1").unwrap();
+        assert_eq!(evaluate("expr * 2", &mut environment).unwrap(), 20);
+        assert_eq!(evaluate("0 && (j = 1)", &mut environment).unwrap(), 0);
+        assert!(environment.get("j").is_none());
+    }
+}
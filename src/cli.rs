@@ -2,8 +2,9 @@ use {
     crate::{
         global_state::GlobalState,
         inner_utils::InnerUtils,
+        interpreter::Interpreter,
         listener::Listener,
-        parser::{Parser, program_builder::program::Program},
+        parser::{Parser, command_builder::command::Command},
         program_output::ProgramOutput,
     },
     std::io::Write,
@@ -20,48 +21,62 @@ pub struct CLI {
 impl CLI {
     pub fn start(&mut self) {
         loop {
-            print!("{} ", self.global_state.settings.get_invitation_input());
+            if self.parser.is_waiting() {
+                print!("{} ", self.global_state.settings.get_continuation_input());
+            } else {
+                print!("{} ", self.global_state.settings.get_invitation_input());
+            }
             std::io::stdout().flush().unwrap();
 
-            let _: Vec<()> = self
-                .parse(self.listener.listen())
-                .into_iter()
-                .map(|program| {
-                    Self::print_output(program.execute(&mut self.global_state, &self.inner_utils))
-                })
-                .collect();
+            let commands = self.parse(self.listener.listen());
+            let mut sink = Self::print_output;
+            let mut interpreter =
+                Interpreter::new(&mut self.global_state, &self.inner_utils, &mut sink);
+            for command in commands {
+                interpreter.run(command);
+            }
         }
     }
 
-    fn parse(&mut self, input: String) -> Vec<Program> {
+    fn parse(&mut self, input: String) -> Vec<Command> {
+        self.parser.set_aliases(self.global_state.aliases.clone());
         let mut result = Vec::new();
-        if input.len() > 1 {
+        if input.len() > 1 || self.parser.is_waiting() {
             for byte in input.as_bytes() {
                 match self.parser.apply(*byte) {
-                    Ok(Some(program)) => result.push(program),
-                    Ok(None) => {}
-                    Err(e) => eprintln!("Parser error: {}", e),
+                    Ok(commands) => result.extend(commands),
+                    Err(e) => {
+                        eprintln!("Parser error: {}", e);
+                        return Vec::new();
+                    }
                 }
             }
-            match self.parser.finish() {
-                Ok(Some(program)) => result.push(program),
-                Ok(None) => {}
-                Err(e) => eprintln!("Parser error: {}", e),
+            if !self.parser.is_waiting() {
+                match self.parser.finish() {
+                    Ok(commands) => result.extend(commands),
+                    Err(e) => eprintln!("Parser error: {}", e),
+                }
             }
         }
         result
     }
 
-    fn print_output(output: anyhow::Result<ProgramOutput>) {
+    fn print_output(output: anyhow::Result<ProgramOutput>, checked: bool) {
         match output {
-            Ok(program_output) => match program_output.code {
-                0 => print!("{}", String::from_utf8_lossy(&program_output.stdout)),
-                _ => eprintln!(
-                    "Program exited with code {}. Error: {}",
-                    program_output.code,
-                    String::from_utf8_lossy(&program_output.stderr)
-                ),
-            },
+            Ok(program_output) if checked => {
+                print!("{}", String::from_utf8_lossy(&program_output.stdout));
+                eprint!("{}", String::from_utf8_lossy(&program_output.stderr));
+            }
+            Ok(program_output) => {
+                print!("{}", String::from_utf8_lossy(&program_output.stdout));
+                if program_output.code != 0 {
+                    eprintln!(
+                        "Program exited with code {}. Error: {}",
+                        program_output.code,
+                        String::from_utf8_lossy(&program_output.stderr)
+                    );
+                }
+            }
             Err(e) => eprintln!("Executing error: {}", e),
         }
     }
@@ -69,7 +84,10 @@ impl CLI {
 
 #[cfg(test)]
 mod test {
-    use crate::{cli::CLI, program_output::ProgramOutput};
+    use {
+        crate::{cli::CLI, interpreter::Interpreter, program_output::ProgramOutput},
+        std::os::unix::fs::PermissionsExt,
+    };
 
     #[test]
     fn check_var_setter() {
@@ -78,8 +96,8 @@ mod test {
         let output: Vec<ProgramOutput> = cli
             .parse("  qwe=1278\n".to_string())
             .into_iter()
-            .map(|program| {
-                program
+            .map(|command| {
+                command
                     .execute(&mut cli.global_state, &cli.inner_utils)
                     .unwrap()
             })
@@ -92,8 +110,8 @@ mod test {
         let output: Vec<ProgramOutput> = cli
             .parse("  qwe==10\n".to_string())
             .into_iter()
-            .map(|program| {
-                program
+            .map(|command| {
+                command
                     .execute(&mut cli.global_state, &cli.inner_utils)
                     .unwrap()
             })
@@ -106,8 +124,8 @@ mod test {
         let output: Vec<ProgramOutput> = cli
             .parse("  qwe=qwe\n".to_string())
             .into_iter()
-            .map(|program| {
-                program
+            .map(|command| {
+                command
                     .execute(&mut cli.global_state, &cli.inner_utils)
                     .unwrap()
             })
@@ -120,8 +138,8 @@ mod test {
         let output: Vec<ProgramOutput> = cli
             .parse("  qwe=\n".to_string())
             .into_iter()
-            .map(|program| {
-                program
+            .map(|command| {
+                command
                     .execute(&mut cli.global_state, &cli.inner_utils)
                     .unwrap()
             })
@@ -134,8 +152,8 @@ mod test {
         let output: Vec<ProgramOutput> = cli
             .parse("  qwe='10$10'\n".to_string())
             .into_iter()
-            .map(|program| {
-                program
+            .map(|command| {
+                command
                     .execute(&mut cli.global_state, &cli.inner_utils)
                     .unwrap()
             })
@@ -148,8 +166,8 @@ mod test {
         let output: Vec<ProgramOutput> = cli
             .parse("x=$PWD\n".to_string())
             .into_iter()
-            .map(|program| {
-                program
+            .map(|command| {
+                command
                     .execute(&mut cli.global_state, &cli.inner_utils)
                     .unwrap()
             })
@@ -164,8 +182,8 @@ mod test {
         let output: Vec<ProgramOutput> = cli
             .parse("x=$PWD:9\n".to_string())
             .into_iter()
-            .map(|program| {
-                program
+            .map(|command| {
+                command
                     .execute(&mut cli.global_state, &cli.inner_utils)
                     .unwrap()
             })
@@ -187,8 +205,8 @@ mod test {
         let output: Vec<ProgramOutput> = cli
             .parse(" echo $PWD\n".to_string())
             .into_iter()
-            .map(|program| {
-                program
+            .map(|command| {
+                command
                     .execute(&mut cli.global_state, &cli.inner_utils)
                     .unwrap()
             })
@@ -201,8 +219,8 @@ mod test {
         let output: Vec<ProgramOutput> = cli
             .parse(" echo $PWD $PWD\n".to_string())
             .into_iter()
-            .map(|program| {
-                program
+            .map(|command| {
+                command
                     .execute(&mut cli.global_state, &cli.inner_utils)
                     .unwrap()
             })
@@ -211,14 +229,16 @@ mod test {
         cli.global_state.environment.get_var(&mut var);
         var.push(b' ');
         var.append(&mut var.clone());
-        var.last_mut().map(|byte| *byte = b'\n');
+        if let Some(byte) = var.last_mut() {
+            *byte = b'\n';
+        }
         assert_eq!(output, vec![ProgramOutput::new(0, var, vec![])]);
 
         let output: Vec<ProgramOutput> = cli
             .parse(" echo $PWD$PWD\n".to_string())
             .into_iter()
-            .map(|program| {
-                program
+            .map(|command| {
+                command
                     .execute(&mut cli.global_state, &cli.inner_utils)
                     .unwrap()
             })
@@ -232,8 +252,8 @@ mod test {
         let output: Vec<ProgramOutput> = cli
             .parse(" echo $PWDPWD\n".to_string())
             .into_iter()
-            .map(|program| {
-                program
+            .map(|command| {
+                command
                     .execute(&mut cli.global_state, &cli.inner_utils)
                     .unwrap()
             })
@@ -244,6 +264,778 @@ mod test {
         );
     }
 
+    #[test]
+    fn check_command_assignments() {
+        let mut cli: CLI = CLI::default();
+
+        let output: Vec<ProgramOutput> = cli
+            .parse("y=5 printenv y; echo y=$y a=b\n".to_string())
+            .into_iter()
+            .map(|command| {
+                command
+                    .execute(&mut cli.global_state, &cli.inner_utils)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            output,
+            vec![
+                ProgramOutput::new(0, "5\n".as_bytes().to_vec(), vec![]),
+                ProgramOutput::new(0, "y= a=b\n".as_bytes().to_vec(), vec![]),
+            ]
+        );
+        assert!(cli.global_state.environment.get("y").is_none());
+
+        let output: Vec<ProgramOutput> = cli
+            .parse("a=1 b=$a; echo $a $b\n".to_string())
+            .into_iter()
+            .map(|command| {
+                command
+                    .execute(&mut cli.global_state, &cli.inner_utils)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            output,
+            vec![
+                ProgramOutput::new(0, vec![], vec![]),
+                ProgramOutput::new(0, "1 1\n".as_bytes().to_vec(), vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_word_splitting() {
+        let mut cli: CLI = CLI::default();
+
+        let output: Vec<ProgramOutput> = cli
+            .parse("x='a  b'; printf '[%s]' $x \"$x\"\n".to_string())
+            .into_iter()
+            .map(|command| {
+                command
+                    .execute(&mut cli.global_state, &cli.inner_utils)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            output,
+            vec![
+                ProgramOutput::new(0, vec![], vec![]),
+                ProgramOutput::new(0, "[a][b][a  b]".as_bytes().to_vec(), vec![])
+            ]
+        );
+
+        let output: Vec<ProgramOutput> = cli
+            .parse("set -- '1 2' 3; printf '[%s]' \"$@\" $*\n".to_string())
+            .into_iter()
+            .map(|command| {
+                command
+                    .execute(&mut cli.global_state, &cli.inner_utils)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            output,
+            vec![
+                ProgramOutput::new(0, vec![], vec![]),
+                ProgramOutput::new(0, "[1 2][3][1][2][3]".as_bytes().to_vec(), vec![])
+            ]
+        );
+
+        let output: Vec<ProgramOutput> = cli
+            .parse("set -- 'a b' '' c; printf '[%s]' \"$@\" \"x$@y\"\n".to_string())
+            .into_iter()
+            .map(|command| {
+                command
+                    .execute(&mut cli.global_state, &cli.inner_utils)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            output,
+            vec![
+                ProgramOutput::new(0, vec![], vec![]),
+                ProgramOutput::new(0, "[a b][][c][xa b][][cy]".as_bytes().to_vec(), vec![])
+            ]
+        );
+
+        let output: Vec<ProgramOutput> = cli
+            .parse("set --; for x in \"$@\"; do echo \"it[$x]\"; done\n".to_string())
+            .into_iter()
+            .map(|command| {
+                command
+                    .execute(&mut cli.global_state, &cli.inner_utils)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            output,
+            vec![
+                ProgramOutput::new(0, vec![], vec![]),
+                ProgramOutput::new(0, vec![], vec![])
+            ]
+        );
+    }
+
+    #[test]
+    fn check_here_doc() {
+        let mut cli: CLI = CLI::default();
+
+        let mut commands = cli.parse("x=1; cat <<EOF; cat <<'EOF'\n".to_string());
+        assert!(cli.parser.is_waiting());
+        commands.extend(cli.parse("\t$x $x\n".to_string()));
+        commands.extend(cli.parse("EOF\n".to_string()));
+        assert!(cli.parser.is_waiting());
+        commands.extend(cli.parse("$x\n".to_string()));
+        commands.extend(cli.parse("EOF\n".to_string()));
+        assert!(!cli.parser.is_waiting());
+        commands.extend(cli.parse("cat <<-EOF <<< \"$x  $x\"\n\t$x\n\tEOF\n".to_string()));
+
+        let output: Vec<ProgramOutput> = commands
+            .into_iter()
+            .map(|command| {
+                command
+                    .execute(&mut cli.global_state, &cli.inner_utils)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            output,
+            vec![
+                ProgramOutput::new(0, vec![], vec![]),
+                ProgramOutput::new(0, "\t1 1\n".as_bytes().to_vec(), vec![]),
+                ProgramOutput::new(0, "$x\n".as_bytes().to_vec(), vec![]),
+                ProgramOutput::new(0, "1  1\n".as_bytes().to_vec(), vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_variable_attributes() {
+        let mut cli: CLI = CLI::default();
+
+        let output: Vec<ProgramOutput> = cli
+            .parse("a=1; export b=2; printenv a b; unset b; printenv b\n".to_string())
+            .into_iter()
+            .map(|command| {
+                command
+                    .execute(&mut cli.global_state, &cli.inner_utils)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            output,
+            vec![
+                ProgramOutput::new(0, vec![], vec![]),
+                ProgramOutput::new(0, vec![], vec![]),
+                ProgramOutput::new(1, "2\n".as_bytes().to_vec(), vec![]),
+                ProgramOutput::new(0, vec![], vec![]),
+                ProgramOutput::new(1, vec![], vec![]),
+            ]
+        );
+
+        let output: Vec<ProgramOutput> = cli
+            .parse("declare -i n=2*3; n+=1; x='a  b'; readonly r=$x; declare -p n r\n".to_string())
+            .into_iter()
+            .map(|command| {
+                command
+                    .execute(&mut cli.global_state, &cli.inner_utils)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            output.last(),
+            Some(&ProgramOutput::new(
+                0,
+                "declare -i n=\"7\"\ndeclare -r r=\"a  b\"\n"
+                    .as_bytes()
+                    .to_vec(),
+                vec![]
+            ))
+        );
+
+        let output: Vec<anyhow::Result<ProgramOutput>> = cli
+            .parse("r=1; unset r; echo $r\n".to_string())
+            .into_iter()
+            .map(|command| command.execute(&mut cli.global_state, &cli.inner_utils))
+            .collect();
+        assert!(output[0].is_err());
+        assert_eq!(
+            output[1].as_ref().unwrap(),
+            &ProgramOutput::new(1, vec![], b"unset: r: readonly variable\n".to_vec())
+        );
+        assert_eq!(
+            output[2].as_ref().unwrap(),
+            &ProgramOutput::new(0, "a b\n".as_bytes().to_vec(), vec![])
+        );
+    }
+
+    #[test]
+    fn check_arrays() {
+        let mut cli: CLI = CLI::default();
+
+        let output: Vec<ProgramOutput> =
+            cli.parse("hosts=(a 'b c'\n".to_string())
+                .into_iter()
+                .chain(cli.parse(
+                    "d); hosts[5]=e; printf '[%s]' \"${hosts[@]}\" ${#hosts[@]}\n".to_string(),
+                ))
+                .map(|command| {
+                    command
+                        .execute(&mut cli.global_state, &cli.inner_utils)
+                        .unwrap()
+                })
+                .collect();
+        assert_eq!(
+            output.last(),
+            Some(&ProgramOutput::new(
+                0,
+                "[a][b c][d][e][4]".as_bytes().to_vec(),
+                vec![]
+            ))
+        );
+
+        let output: Vec<ProgramOutput> = cli
+            .parse("declare -A m=([x]=1); m[y]=2; echo ${!m[@]} ${m[y]} ${hosts[-1]}\n".to_string())
+            .into_iter()
+            .map(|command| {
+                command
+                    .execute(&mut cli.global_state, &cli.inner_utils)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            output.last(),
+            Some(&ProgramOutput::new(
+                0,
+                "x y 2 e\n".as_bytes().to_vec(),
+                vec![]
+            ))
+        );
+    }
+
+    #[test]
+    fn check_control_flow() {
+        let mut cli: CLI = CLI::default();
+
+        let output: Vec<ProgramOutput> = cli
+            .parse("for x in a 'b c' d; do\n".to_string())
+            .into_iter()
+            .chain(
+                cli.parse("if [ \"$x\" = d ]; then break; fi; printf '[%s]' \"$x\"\n".to_string()),
+            )
+            .chain(cli.parse("done\n".to_string()))
+            .map(|command| {
+                command
+                    .execute(&mut cli.global_state, &cli.inner_utils)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            output,
+            vec![ProgramOutput::new(
+                0,
+                "[a][b c]".as_bytes().to_vec(),
+                vec![]
+            )]
+        );
+
+        let output: Vec<ProgramOutput> = cli
+            .parse(
+                "i=0; while ((i < 5)); do ((i++)); case $i in 2|4) continue;; *) printf $i;; esac; done\n"
+                    .to_string(),
+            )
+            .into_iter()
+            .map(|command| {
+                command
+                    .execute(&mut cli.global_state, &cli.inner_utils)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            output.last(),
+            Some(&ProgramOutput::new(0, "135".as_bytes().to_vec(), vec![]))
+        );
+
+        let output: Vec<ProgramOutput> = cli
+            .parse(
+                "false && echo no || { echo $?; for ((j = 0; j < 2; j++)); do echo $j; done; }\n"
+                    .to_string(),
+            )
+            .into_iter()
+            .map(|command| {
+                command
+                    .execute(&mut cli.global_state, &cli.inner_utils)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            output,
+            vec![ProgramOutput::new(
+                0,
+                "1\n0\n1\n".as_bytes().to_vec(),
+                vec![]
+            )]
+        );
+
+        let output: Vec<ProgramOutput> = cli
+            .parse(
+                "k=0; while :; do ((++k > 2)) && break; done; if ! false; then echo $k; fi\n"
+                    .to_string(),
+            )
+            .into_iter()
+            .chain(cli.parse("! { true; } || echo negated; ! ! true && echo twice\n".to_string()))
+            .map(|command| {
+                command
+                    .execute(&mut cli.global_state, &cli.inner_utils)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            output
+                .into_iter()
+                .flat_map(|output| output.stdout)
+                .collect::<Vec<u8>>(),
+            "3\nnegated\ntwice\n".as_bytes().to_vec()
+        );
+
+        assert!(cli.parse("if true; then fi\n".to_string()).is_empty());
+        assert!(cli.parse("done\n".to_string()).is_empty());
+    }
+
+    #[test]
+    fn check_functions() {
+        let mut cli: CLI = CLI::default();
+
+        let output: Vec<ProgramOutput> = cli
+            .parse("x=global; show() { printf '%s:%s;' \"$1\" \"$x\"; }\n".to_string())
+            .into_iter()
+            .chain(cli.parse("function f {\n".to_string()))
+            .chain(cli.parse("local x=local; show $# \"$2\"; return 3; show never\n".to_string()))
+            .chain(cli.parse("}; f a b; show $?\n".to_string()))
+            .map(|command| {
+                command
+                    .execute(&mut cli.global_state, &cli.inner_utils)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            output
+                .iter()
+                .map(|output| output.code)
+                .collect::<Vec<i32>>(),
+            vec![0, 0, 0, 3, 0]
+        );
+        assert_eq!(
+            output
+                .into_iter()
+                .flat_map(|output| output.stdout)
+                .collect::<Vec<u8>>(),
+            "2:local;3:global;".as_bytes().to_vec()
+        );
+
+        let output: Vec<ProgramOutput> = cli
+            .parse("local y=1; return\n".to_string())
+            .into_iter()
+            .map(|command| {
+                command
+                    .execute(&mut cli.global_state, &cli.inner_utils)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            output
+                .iter()
+                .map(|output| output.code)
+                .collect::<Vec<i32>>(),
+            vec![1, 2]
+        );
+
+        let output: Vec<ProgramOutput> = cli
+            .parse("h() { cat; echo end; }; h <<< in\n".to_string())
+            .into_iter()
+            .map(|command| {
+                command
+                    .execute(&mut cli.global_state, &cli.inner_utils)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            output.last(),
+            Some(&ProgramOutput::new(
+                0,
+                "in\nend\n".as_bytes().to_vec(),
+                vec![]
+            ))
+        );
+
+        let commands = cli.parse(
+            "f() { nosuchcmd; echo after; }; f; g() { return 4; }; g; h() { false; }; h\n"
+                .to_string(),
+        );
+        let mut reported = Vec::new();
+        let mut sink = |output: anyhow::Result<ProgramOutput>, _checked: bool| {
+            reported.push(
+                output
+                    .map(|output| (output.code, output.stdout))
+                    .map_err(|e| e.to_string()),
+            );
+        };
+        let mut interpreter = Interpreter::new(&mut cli.global_state, &cli.inner_utils, &mut sink);
+        let codes: Vec<i32> = commands
+            .into_iter()
+            .map(|command| interpreter.run(command))
+            .collect();
+        assert_eq!(codes, vec![0, 0, 0, 4, 0, 1]);
+        assert_eq!(
+            reported,
+            vec![
+                Err("command not found: 'nosuchcmd'".to_string()),
+                Ok((0, "after\n".as_bytes().to_vec())),
+                Ok((4, vec![])),
+                Ok((1, vec![]))
+            ]
+        );
+
+        let output: Vec<ProgramOutput> = cli
+            .parse(
+                "l() { local v=$1 arr=(x \"$1\"); local -A m=([k]=v); echo \"[$v][${arr[1]}][${m[k]}]\"; }; l 'a  b'\n"
+                    .to_string(),
+            )
+            .into_iter()
+            .map(|command| {
+                command
+                    .execute(&mut cli.global_state, &cli.inner_utils)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            output.last(),
+            Some(&ProgramOutput::new(
+                0,
+                "[a  b][a  b][v]\n".as_bytes().to_vec(),
+                vec![]
+            ))
+        );
+        assert!(cli.parse("g() echo\n".to_string()).is_empty());
+    }
+
+    #[test]
+    fn check_subshells() {
+        let mut cli: CLI = CLI::default();
+
+        let output: Vec<ProgramOutput> = cli
+            .parse("x=1; (x=2; f() { :; }; exit 3; echo never) || printf \"$? $x\"\n".to_string())
+            .into_iter()
+            .map(|command| {
+                command
+                    .execute(&mut cli.global_state, &cli.inner_utils)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            output.last(),
+            Some(&ProgramOutput::new(0, "3 1".as_bytes().to_vec(), vec![]))
+        );
+        assert!(!cli.global_state.functions.contains_key("f"));
+
+        let output: Vec<ProgramOutput> = cli
+            .parse("case b in (a) echo a;; (b) (echo b);; esac\n".to_string())
+            .into_iter()
+            .map(|command| {
+                command
+                    .execute(&mut cli.global_state, &cli.inner_utils)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            output,
+            vec![ProgramOutput::new(0, "b\n".as_bytes().to_vec(), vec![])]
+        );
+        assert!(cli.parse("(echo a\n".to_string()).is_empty());
+        assert!(cli.parse("))\n".to_string()).is_empty());
+    }
+
+    #[test]
+    fn check_conditionals() {
+        let mut cli: CLI = CLI::default();
+
+        let output: Vec<ProgramOutput> = cli
+            .parse(
+                "v=; [ -d src -a ! -f src ] && [ -z \"$v\" ] && test 2 -lt 10 && printf ok\n"
+                    .to_string(),
+            )
+            .into_iter()
+            .map(|command| {
+                command
+                    .execute(&mut cli.global_state, &cli.inner_utils)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            output.last(),
+            Some(&ProgramOutput::new(0, "ok".as_bytes().to_vec(), vec![]))
+        );
+
+        let output: Vec<ProgramOutput> = cli
+            .parse("[ 1 -eq x ]\n".to_string())
+            .into_iter()
+            .map(|command| {
+                command
+                    .execute(&mut cli.global_state, &cli.inner_utils)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            output,
+            vec![ProgramOutput::new(
+                2,
+                vec![],
+                "[: x: integer expression expected\n".as_bytes().to_vec()
+            )]
+        );
+
+        let output: Vec<ProgramOutput> = cli
+            .parse(
+                "[ ! = a ]; a=$?; [ \"(\" -n \")\" ]; b=$?; [ ! \"\" -o a ]; printf \"$a$b$?\"\n"
+                    .to_string(),
+            )
+            .into_iter()
+            .map(|command| {
+                command
+                    .execute(&mut cli.global_state, &cli.inner_utils)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            output.last(),
+            Some(&ProgramOutput::new(0, "101".as_bytes().to_vec(), vec![]))
+        );
+
+        let output: Vec<ProgramOutput> = cli
+            .parse(
+                "[[ v1.20 =~ ^v([0-9]+)\".\"([0-9]+)$ && a.c == *\".c\" ]]&&printf \"${BASH_REMATCH[1]} ${BASH_REMATCH[2]}\"\n"
+                    .to_string(),
+            )
+            .into_iter()
+            .map(|command| {
+                command
+                    .execute(&mut cli.global_state, &cli.inner_utils)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            output,
+            vec![ProgramOutput::new(0, "1 20".as_bytes().to_vec(), vec![])]
+        );
+        assert!(cli.parse("[[ a == a\n".to_string()).is_empty());
+    }
+
+    #[test]
+    fn check_aliases() {
+        let mut cli: CLI = CLI::default();
+
+        cli.parse("alias p=\"printf '%s-'\" r='p ' printf=\"printf '%s.'\"\n".to_string())
+            .into_iter()
+            .for_each(|command| {
+                command
+                    .execute(&mut cli.global_state, &cli.inner_utils)
+                    .unwrap();
+            });
+        let output: Vec<ProgramOutput> = cli
+            .parse("r r; printf a; 'p' x\n".to_string())
+            .into_iter()
+            .map(|command| command.execute(&mut cli.global_state, &cli.inner_utils))
+            .filter_map(|output| output.ok())
+            .collect();
+        assert_eq!(
+            output,
+            vec![
+                ProgramOutput::new(0, "%s-.p.".as_bytes().to_vec(), vec![]),
+                ProgramOutput::new(0, "a.".as_bytes().to_vec(), vec![])
+            ]
+        );
+
+        cli.parse("unalias r; r\n".to_string())
+            .into_iter()
+            .for_each(|command| {
+                let _ = command.execute(&mut cli.global_state, &cli.inner_utils);
+            });
+        assert!(!cli.global_state.aliases.contains_key("r"));
+        assert!(cli.global_state.aliases.contains_key("p"));
+    }
+
+    #[test]
+    fn check_command_lookup() {
+        let mut cli: CLI = CLI::default();
+
+        let output: Vec<ProgramOutput> = cli
+            .parse(
+                "printf() { :; }; command printf x; type -t printf cd if; command -v nope\n"
+                    .to_string(),
+            )
+            .into_iter()
+            .map(|command| {
+                command
+                    .execute(&mut cli.global_state, &cli.inner_utils)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            output[1..],
+            vec![
+                ProgramOutput::new(0, "x".as_bytes().to_vec(), vec![]),
+                ProgramOutput::new(
+                    0,
+                    "function\nbuiltin\nkeyword\n".as_bytes().to_vec(),
+                    vec![]
+                ),
+                ProgramOutput::new(1, vec![], vec![])
+            ]
+        );
+
+        let output: Vec<ProgramOutput> = cli
+            .parse("unset -f printf; uname; hash -t uname\n".to_string())
+            .into_iter()
+            .map(|command| {
+                command
+                    .execute(&mut cli.global_state, &cli.inner_utils)
+                    .unwrap()
+            })
+            .collect();
+        assert!(output[2].stdout.ends_with(b"/uname\n"));
+        assert!(
+            cli.parse("hash -r\n".to_string())
+                .into_iter()
+                .all(|command| {
+                    command
+                        .execute(&mut cli.global_state, &cli.inner_utils)
+                        .is_ok_and(|output| output.code == 0)
+                })
+        );
+        assert_eq!(
+            cli.global_state
+                .commands
+                .entries(&cli.global_state.search_path())
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn check_utils_dir() {
+        let mut cli: CLI = CLI::default();
+        let dir = std::env::temp_dir().join(format!("pseudobash-utils-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let util = dir.join("greet");
+        std::fs::write(&util, "#!/bin/sh\nprintf hi\n").unwrap();
+        std::fs::set_permissions(&util, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let relay = dir.join("relay");
+        std::fs::write(&relay, "#!/bin/sh\nexec greet\n").unwrap();
+        std::fs::set_permissions(&relay, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let path = cli.global_state.environment.get("PATH").cloned();
+        let output: Vec<anyhow::Result<ProgramOutput>> = cli
+            .parse(format!(
+                "greet; PSEUDOBASH_UTILS={}; greet; type -p greet; relay\n",
+                dir.display()
+            ))
+            .into_iter()
+            .map(|command| command.execute(&mut cli.global_state, &cli.inner_utils))
+            .collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(output[0].is_err());
+        assert_eq!(
+            output[2].as_ref().unwrap(),
+            &ProgramOutput::new(0, "hi".as_bytes().to_vec(), vec![])
+        );
+        assert_eq!(
+            output[3].as_ref().unwrap().stdout,
+            format!("{}\n", util.display()).into_bytes()
+        );
+        assert_eq!(
+            output[4].as_ref().unwrap(),
+            &ProgramOutput::new(0, "hi".as_bytes().to_vec(), vec![])
+        );
+        assert_eq!(cli.global_state.environment.get("PATH").cloned(), path);
+    }
+
+    #[test]
+    fn check_exec_failures() {
+        let mut cli: CLI = CLI::default();
+
+        let commands = cli.parse("./src; ./Cargo.toml; ./nosuch\n".to_string());
+        let mut sink = |_output: anyhow::Result<ProgramOutput>, _checked: bool| {};
+        let mut interpreter = Interpreter::new(&mut cli.global_state, &cli.inner_utils, &mut sink);
+        let codes: Vec<i32> = commands
+            .into_iter()
+            .map(|command| interpreter.run(command))
+            .collect();
+        assert_eq!(codes, vec![126, 126, 127]);
+    }
+
+    #[test]
+    fn check_printf() {
+        let mut cli: CLI = CLI::default();
+
+        let output: Vec<ProgramOutput> = cli
+            .parse(
+                "printf '%-4s|%03d\\n' ab 7 c; printf -v out '%x-%q' 255 'a b'; printf '%d' x\n"
+                    .to_string(),
+            )
+            .into_iter()
+            .map(|command| {
+                command
+                    .execute(&mut cli.global_state, &cli.inner_utils)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            output,
+            vec![
+                ProgramOutput::new(0, "ab  |007\nc   |000\n".as_bytes().to_vec(), vec![]),
+                ProgramOutput::new(0, vec![], vec![]),
+                ProgramOutput::new(
+                    1,
+                    "0".as_bytes().to_vec(),
+                    "printf: x: invalid number\n".as_bytes().to_vec()
+                ),
+            ]
+        );
+        assert_eq!(
+            cli.global_state.environment.get("out").cloned(),
+            Some("ff-a\\ b".to_string())
+        );
+    }
+
+    #[test]
+    fn check_pwd() {
+        let mut cli: CLI = CLI::default();
+        let current = std::env::current_dir().unwrap();
+        let link = std::env::temp_dir().join(format!("pseudobash-pwd-{}", std::process::id()));
+        std::os::unix::fs::symlink(&current, &link).unwrap();
+
+        let output: Vec<ProgramOutput> = cli
+            .parse(format!(
+                "PWD={}; pwd; pwd -P; PWD=/nonexistent; pwd -L\n",
+                link.display()
+            ))
+            .into_iter()
+            .map(|command| {
+                command
+                    .execute(&mut cli.global_state, &cli.inner_utils)
+                    .unwrap()
+            })
+            .collect();
+        std::fs::remove_file(&link).unwrap();
+
+        let line = |path: &std::path::Path| format!("{}\n", path.display()).into_bytes();
+        assert_eq!(output[1].stdout, line(&link));
+        assert_eq!(output[2].stdout, line(&current));
+        assert_eq!(output[4].stdout, line(&current));
+    }
+
     #[test]
     fn check_error() {
         let mut cli: CLI = CLI::default();
@@ -251,7 +1043,7 @@ mod test {
         let output: Vec<anyhow::Result<ProgramOutput>> = cli
             .parse("  '1'\n".to_string())
             .into_iter()
-            .map(|program| program.execute(&mut cli.global_state, &cli.inner_utils))
+            .map(|command| command.execute(&mut cli.global_state, &cli.inner_utils))
             .collect();
         assert!(output.iter().all(|res| res.is_err()));
     }
@@ -0,0 +1,375 @@
+use {
+    crate::{
+        arithmetic,
+        expansion::Word,
+        global_state::{
+            GlobalState,
+            environment::{split_subscript, value::quote},
+        },
+        regex::Regex,
+    },
+    std::{
+        fs::Metadata,
+        os::unix::fs::{FileTypeExt, MetadataExt},
+    },
+};
+
+const UNARY_OPERATORS: [&str; 17] = [
+    "-a", "-b", "-c", "-d", "-e", "-f", "-h", "-L", "-n", "-p", "-r", "-s", "-S", "-v", "-w", "-x",
+    "-z",
+];
+
+const BINARY_OPERATORS: [&str; 15] = [
+    "=", "==", "!=", "=~", "<", ">", "-eq", "-ne", "-lt", "-le", "-gt", "-ge", "-nt", "-ot", "-ef",
+];
+
+#[derive(Default)]
+pub struct Operand {
+    text: String,
+    operator: bool,
+    word: Option<Word>,
+}
+
+impl Operand {
+    pub fn new(word: Word, operator: bool) -> Self {
+        Self {
+            text: String::from_utf8_lossy(word.as_bytes()).to_string(),
+            operator,
+            word: Some(word),
+        }
+    }
+
+    fn is(&self, operator: &str) -> bool {
+        self.operator && self.text == operator
+    }
+}
+
+struct Expression<'a> {
+    operands: Vec<Operand>,
+    idx: usize,
+    extended: bool,
+    skipping: bool,
+    gs: &'a mut GlobalState,
+}
+
+pub fn test(args: Vec<String>, gs: &mut GlobalState) -> anyhow::Result<bool> {
+    let operands = args
+        .into_iter()
+        .map(|text| Operand {
+            text,
+            operator: true,
+            word: None,
+        })
+        .collect();
+    Expression::new(operands, false, gs).run()
+}
+
+pub fn evaluate(operands: Vec<Operand>, gs: &mut GlobalState) -> anyhow::Result<bool> {
+    Expression::new(operands, true, gs).run()
+}
+
+impl<'a> Expression<'a> {
+    fn new(operands: Vec<Operand>, extended: bool, gs: &'a mut GlobalState) -> Self {
+        Self {
+            operands,
+            idx: 0,
+            extended,
+            skipping: false,
+            gs,
+        }
+    }
+
+    fn run(mut self) -> anyhow::Result<bool> {
+        if self.operands.is_empty() {
+            if self.extended {
+                anyhow::bail!("syntax error: conditional expression expected")
+            }
+            return Ok(false);
+        }
+        if !self.extended
+            && let Some(result) = self.by_count(self.operands.len())
+        {
+            return result;
+        }
+        self.idx = 0;
+        let result = self.or()?;
+        match self.operands.get(self.idx) {
+            None => Ok(result),
+            Some(_) if !self.extended => anyhow::bail!("too many arguments"),
+            Some(operand) => anyhow::bail!(
+                "syntax error in conditional expression near `{}'",
+                operand.text
+            ),
+        }
+    }
+
+    // `test` with up to four arguments is decided by their number as POSIX
+    // specifies, so that `[ ! = a ]` compares strings. Other forms are left
+    // to the parser.
+    fn by_count(&mut self, count: usize) -> Option<anyhow::Result<bool>> {
+        let is = |offset: usize, operator: &str| self.operands[self.idx + offset].is(operator);
+        let negated = |result: anyhow::Result<bool>| result.map(|result| !result);
+        match count {
+            1 => Some(Ok(!self.take().text.is_empty())),
+            2 if is(0, "!") => {
+                self.idx += 1;
+                self.by_count(1).map(negated)
+            }
+            2 if UNARY_OPERATORS.contains(&self.operands[self.idx].text.as_str()) => {
+                Some(self.unary())
+            }
+            3 if self.is_binary(self.idx + 1) => Some(self.binary()),
+            3 if is(1, "-a") || is(1, "-o") => {
+                let left = !self.take().text.is_empty();
+                let and = self.take().text == "-a";
+                let right = !self.take().text.is_empty();
+                Some(Ok(if and { left && right } else { left || right }))
+            }
+            3 if is(0, "!") => {
+                self.idx += 1;
+                self.by_count(2).map(negated)
+            }
+            3 | 4 if is(0, "(") && is(count - 1, ")") => {
+                self.idx += 1;
+                let result = self.by_count(count - 2);
+                self.idx += 1;
+                result
+            }
+            4 if is(0, "!") => {
+                self.idx += 1;
+                self.by_count(3).map(negated)
+            }
+            _ => None,
+        }
+    }
+
+    fn or(&mut self) -> anyhow::Result<bool> {
+        let operator = if self.extended { "||" } else { "-o" };
+        let mut result = self.and()?;
+        while self.accept(operator) {
+            let skipping = self.skipping;
+            self.skipping |= result;
+            let right = self.and()?;
+            self.skipping = skipping;
+            result = result || right;
+        }
+        Ok(result)
+    }
+
+    fn and(&mut self) -> anyhow::Result<bool> {
+        let operator = if self.extended { "&&" } else { "-a" };
+        let mut result = self.not()?;
+        while self.accept(operator) {
+            let skipping = self.skipping;
+            self.skipping |= !result;
+            let right = self.not()?;
+            self.skipping = skipping;
+            result = result && right;
+        }
+        Ok(result)
+    }
+
+    fn not(&mut self) -> anyhow::Result<bool> {
+        if self.idx + 1 < self.operands.len() && self.accept("!") {
+            return Ok(!self.not()?);
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> anyhow::Result<bool> {
+        let Some(operand) = self.operands.get(self.idx) else {
+            anyhow::bail!("argument expected")
+        };
+        if self.is_binary(self.idx + 1) && (self.extended || self.idx + 2 < self.operands.len()) {
+            return self.binary();
+        }
+        if operand.is("(") {
+            self.idx += 1;
+            let result = self.or()?;
+            if !self.accept(")") {
+                anyhow::bail!("`)' expected")
+            }
+            return Ok(result);
+        }
+        if operand.operator && UNARY_OPERATORS.contains(&operand.text.as_str()) {
+            if self.idx + 1 < self.operands.len() {
+                return self.unary();
+            }
+            if self.extended {
+                anyhow::bail!("unexpected argument to conditional unary operator")
+            }
+        }
+        if self.extended && ["(", ")", "&&", "||"].iter().any(|text| operand.is(text)) {
+            anyhow::bail!("syntax error near `{}'", operand.text)
+        }
+        self.idx += 1;
+        Ok(!operand.text.is_empty())
+    }
+
+    fn unary(&mut self) -> anyhow::Result<bool> {
+        let operator = self.take().text;
+        let operand = self.take().text;
+        if self.skipping {
+            return Ok(false);
+        }
+        Ok(match operator.as_str() {
+            "-z" => operand.is_empty(),
+            "-n" => !operand.is_empty(),
+            "-v" => self.is_set(&operand),
+            "-h" | "-L" => std::fs::symlink_metadata(&operand)
+                .is_ok_and(|metadata| metadata.file_type().is_symlink()),
+            _ => {
+                let Ok(metadata) = std::fs::metadata(&operand) else {
+                    return Ok(false);
+                };
+                let file_type = metadata.file_type();
+                match operator.as_str() {
+                    "-f" => file_type.is_file(),
+                    "-d" => file_type.is_dir(),
+                    "-b" => file_type.is_block_device(),
+                    "-c" => file_type.is_char_device(),
+                    "-p" => file_type.is_fifo(),
+                    "-S" => file_type.is_socket(),
+                    "-s" => metadata.len() > 0,
+                    "-r" => is_accessible(&metadata, 0o4),
+                    "-w" => is_accessible(&metadata, 0o2),
+                    "-x" => is_accessible(&metadata, 0o1),
+                    _ => true,
+                }
+            }
+        })
+    }
+
+    fn binary(&mut self) -> anyhow::Result<bool> {
+        let left = self.take().text;
+        let operator = self.take().text;
+        if self.idx >= self.operands.len() {
+            anyhow::bail!("argument expected after `{}'", operator)
+        }
+        let right = self.take();
+        if self.skipping {
+            return Ok(false);
+        }
+        Ok(match operator.as_str() {
+            "=" | "==" | "!=" => {
+                let matched = match right.word.as_ref() {
+                    Some(word) => word.to_pattern().matches(&left),
+                    None => left == right.text,
+                };
+                matched == (operator != "!=")
+            }
+            "=~" => self.matches_regex(&left, &right)?,
+            "<" => left < right.text,
+            ">" => left > right.text,
+            "-nt" | "-ot" => {
+                let modified = |path: &str| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+                let (left, right) = match operator.as_str() {
+                    "-nt" => (modified(&left), modified(&right.text)),
+                    _ => (modified(&right.text), modified(&left)),
+                };
+                match (left, right) {
+                    (Some(left), Some(right)) => left > right,
+                    (left, right) => left.is_some() && right.is_none(),
+                }
+            }
+            "-ef" => match (std::fs::metadata(&left), std::fs::metadata(&right.text)) {
+                (Ok(left), Ok(right)) => left.dev() == right.dev() && left.ino() == right.ino(),
+                _ => false,
+            },
+            _ => {
+                let (left, right) = (self.integer(&left)?, self.integer(&right.text)?);
+                match operator.as_str() {
+                    "-eq" => left == right,
+                    "-ne" => left != right,
+                    "-lt" => left < right,
+                    "-le" => left <= right,
+                    "-gt" => left > right,
+                    _ => left >= right,
+                }
+            }
+        })
+    }
+
+    fn matches_regex(&mut self, text: &str, pattern: &Operand) -> anyhow::Result<bool> {
+        let source = match pattern.word.as_ref() {
+            Some(word) => word.to_regex(),
+            None => pattern.text.clone(),
+        };
+        let groups = Regex::new(&source)?.captures(text);
+        let values: Vec<String> = groups
+            .iter()
+            .flatten()
+            .map(|group| quote(group.map_or("", |(from, to)| &text[from..to])))
+            .collect();
+        self.gs
+            .environment
+            .insert_compound("BASH_REMATCH", &format!("({})", values.join(" ")))?;
+        Ok(groups.is_some())
+    }
+
+    fn integer(&mut self, text: &str) -> anyhow::Result<i64> {
+        if self.extended {
+            return arithmetic::evaluate(text, &mut self.gs.environment);
+        }
+        text.trim()
+            .parse()
+            .map_err(|_| anyhow::anyhow!("{}: integer expression expected", text))
+    }
+
+    fn is_set(&mut self, name: &str) -> bool {
+        match split_subscript(name) {
+            (name, Some(subscript)) => self
+                .gs
+                .environment
+                .element(name, subscript)
+                .is_ok_and(|value| value.is_some()),
+            (name, None) => self
+                .gs
+                .environment
+                .variable(name)
+                .is_some_and(|variable| variable.value.is_some()),
+        }
+    }
+
+    fn is_binary(&self, idx: usize) -> bool {
+        self.operands.get(idx).is_some_and(|operand| {
+            operand.operator
+                && BINARY_OPERATORS.contains(&operand.text.as_str())
+                && (self.extended || operand.text != "=~")
+        })
+    }
+
+    fn accept(&mut self, operator: &str) -> bool {
+        let accepted = self
+            .operands
+            .get(self.idx)
+            .is_some_and(|operand| operand.is(operator));
+        if accepted {
+            self.idx += 1;
+        }
+        accepted
+    }
+
+    fn take(&mut self) -> Operand {
+        self.idx += 1;
+        std::mem::take(&mut self.operands[self.idx - 1])
+    }
+}
+
+fn is_accessible(metadata: &Metadata, bits: u32) -> bool {
+    let mode = metadata.mode();
+    let Ok(process) = std::fs::metadata("/proc/self") else {
+        return mode & (bits * 0o111) != 0;
+    };
+    if process.uid() == 0 {
+        return bits != 0o1 || metadata.is_dir() || mode & 0o111 != 0;
+    }
+    let shift = if metadata.uid() == process.uid() {
+        6
+    } else if metadata.gid() == process.gid() {
+        3
+    } else {
+        0
+    };
+    (mode >> shift) & bits != 0
+}
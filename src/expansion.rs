@@ -0,0 +1,82 @@
+pub mod brace;
+pub mod glob;
+pub mod parameter;
+pub mod split;
+pub mod tilde;
+
+use pseudobash_regex::glob::Pattern;
+
+#[derive(Default, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Origin {
+    #[default]
+    Literal,
+
+    Quoted,
+    Expanded,
+}
+
+#[derive(Default, Debug, PartialEq, Eq)]
+pub struct Word {
+    data: Vec<u8>,
+    origin: Vec<Origin>,
+    // Field boundaries from "$@" and "${a[@]}", and whether the field ending
+    // there is kept when empty, which is the case for quoted expansions.
+    breaks: Vec<(usize, bool)>,
+    quoted: bool,
+    // A quoted "$@" without elements removes the whole word.
+    vanished: bool,
+}
+
+impl Word {
+    pub fn push(&mut self, bytes: Vec<u8>, origin: Origin) {
+        self.quoted |= origin == Origin::Quoted;
+        self.origin.extend(std::iter::repeat_n(origin, bytes.len()));
+        self.data.extend(bytes);
+    }
+
+    pub fn push_break(&mut self, keep_empty: bool) {
+        self.breaks.push((self.data.len(), keep_empty));
+    }
+
+    pub fn set_vanished(&mut self) {
+        self.vanished = true;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn is_null(&self) -> bool {
+        self.is_empty() && (!self.quoted || self.vanished)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn into_string(self) -> String {
+        String::from_utf8_lossy(&self.data).to_string()
+    }
+
+    pub fn to_pattern(&self) -> Pattern {
+        glob::pattern(&self.data, &self.quoted())
+    }
+
+    pub fn to_regex(&self) -> String {
+        let mut source = Vec::new();
+        for (byte, origin) in self.data.iter().zip(&self.origin) {
+            if *origin == Origin::Quoted && b"\\.[]()*+?{}|^$".contains(byte) {
+                source.push(b'\\');
+            }
+            source.push(*byte);
+        }
+        String::from_utf8_lossy(&source).to_string()
+    }
+
+    fn quoted(&self) -> Vec<bool> {
+        self.origin
+            .iter()
+            .map(|origin| *origin == Origin::Quoted)
+            .collect()
+    }
+}
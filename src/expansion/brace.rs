@@ -0,0 +1,216 @@
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Piece<T> {
+    Byte(u8),
+    Opaque(T),
+}
+
+pub fn expand<T: Clone>(pieces: Vec<Piece<T>>) -> Vec<Vec<Piece<T>>> {
+    let Some((open, close, alternatives)) = find_group(&pieces) else {
+        return vec![pieces];
+    };
+
+    let prefix = &pieces[..open];
+    let suffixes = expand(pieces[close + 1..].to_vec());
+    let mut output = Vec::new();
+    for alternative in alternatives {
+        for alternative in expand(alternative) {
+            for suffix in &suffixes {
+                let mut result = prefix.to_vec();
+                result.extend(alternative.iter().cloned());
+                result.extend(suffix.iter().cloned());
+                output.push(result);
+            }
+        }
+    }
+    output
+}
+
+type Group<T> = (usize, usize, Vec<Vec<Piece<T>>>);
+
+fn find_group<T: Clone>(pieces: &[Piece<T>]) -> Option<Group<T>> {
+    for open in 0..pieces.len() {
+        if !matches!(pieces[open], Piece::Byte(b'{')) {
+            continue;
+        }
+        let Some(close) = find_close(pieces, open) else {
+            continue;
+        };
+        let inner = &pieces[open + 1..close];
+        if let Some(alternatives) = split_alternatives(inner).or_else(|| sequence(inner)) {
+            return Some((open, close, alternatives));
+        }
+    }
+    None
+}
+
+fn find_close<T>(pieces: &[Piece<T>], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (idx, piece) in pieces.iter().enumerate().skip(open) {
+        match piece {
+            Piece::Byte(b'{') => depth += 1,
+            Piece::Byte(b'}') => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(idx);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn split_alternatives<T: Clone>(inner: &[Piece<T>]) -> Option<Vec<Vec<Piece<T>>>> {
+    let mut alternatives = vec![vec![]];
+    let mut depth = 0;
+    for piece in inner {
+        match piece {
+            Piece::Byte(b'{') => depth += 1,
+            Piece::Byte(b'}') => depth -= 1,
+            Piece::Byte(b',') if depth == 0 => {
+                alternatives.push(vec![]);
+                continue;
+            }
+            _ => {}
+        }
+        if let Some(alternative) = alternatives.last_mut() {
+            alternative.push(piece.clone());
+        }
+    }
+    if alternatives.len() > 1 {
+        Some(alternatives)
+    } else {
+        None
+    }
+}
+
+fn sequence<T>(inner: &[Piece<T>]) -> Option<Vec<Vec<Piece<T>>>> {
+    let bytes = inner
+        .iter()
+        .map(|piece| match piece {
+            Piece::Byte(byte) => Some(*byte),
+            Piece::Opaque(_) => None,
+        })
+        .collect::<Option<Vec<u8>>>()?;
+    let text = String::from_utf8(bytes).ok()?;
+    let parts: Vec<&str> = text.split("..").collect();
+    let (start, end, step) = match parts.as_slice() {
+        [start, end] => (*start, *end, None),
+        [start, end, step] => (*start, *end, Some(step.parse::<i64>().ok()?)),
+        _ => return None,
+    };
+    let step = step.map(|step| step.unsigned_abs().max(1)).unwrap_or(1);
+
+    let items = match (start.parse::<i64>(), end.parse::<i64>()) {
+        (Ok(first), Ok(last)) => {
+            let width = if has_leading_zero(start) || has_leading_zero(end) {
+                start.len().max(end.len())
+            } else {
+                0
+            };
+            range(first, last, step)
+                .into_iter()
+                .map(|value| {
+                    if value < 0 {
+                        format!(
+                            "-{:0width$}",
+                            value.unsigned_abs(),
+                            width = width.saturating_sub(1)
+                        )
+                    } else {
+                        format!("{:0width$}", value)
+                    }
+                })
+                .collect::<Vec<String>>()
+        }
+        _ => {
+            let (first, last) = (single_char(start)?, single_char(end)?);
+            range(first as i64, last as i64, step)
+                .into_iter()
+                .map(|value| (value as u8 as char).to_string())
+                .collect()
+        }
+    };
+
+    Some(
+        items
+            .into_iter()
+            .map(|item| item.bytes().map(Piece::Byte).collect())
+            .collect(),
+    )
+}
+
+fn range(first: i64, last: i64, step: u64) -> Vec<i64> {
+    let step = step as usize;
+    if first <= last {
+        (first..=last).step_by(step).collect()
+    } else {
+        (last..=first).rev().step_by(step).collect()
+    }
+}
+
+fn has_leading_zero(number: &str) -> bool {
+    let digits = number.trim_start_matches('-');
+    digits.len() > 1 && digits.starts_with('0')
+}
+
+fn single_char(text: &str) -> Option<u8> {
+    match text.as_bytes() {
+        [byte] if byte.is_ascii_alphabetic() => Some(*byte),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::expansion::brace::{Piece, expand};
+
+    fn expand_str(text: &str) -> Vec<String> {
+        expand::<()>(text.bytes().map(Piece::Byte).collect())
+            .into_iter()
+            .map(|pieces| {
+                pieces
+                    .into_iter()
+                    .map(|piece| match piece {
+                        Piece::Byte(byte) => byte as char,
+                        Piece::Opaque(_) => '$',
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn check_expand() {
+        assert_eq!(expand_str("dir/{a,b,c}"), vec!["dir/a", "dir/b", "dir/c"]);
+        assert_eq!(expand_str("{a,b}{1,2}"), vec!["a1", "a2", "b1", "b2"]);
+        assert_eq!(expand_str("x{a,b{c,d}}y"), vec!["xay", "xbcy", "xbdy"]);
+        assert_eq!(expand_str("{a,}b"), vec!["ab", "b"]);
+        assert_eq!(expand_str("{1..5}"), vec!["1", "2", "3", "4", "5"]);
+        assert_eq!(expand_str("{5..1..2}"), vec!["5", "3", "1"]);
+        assert_eq!(expand_str("{-1..1}"), vec!["-1", "0", "1"]);
+        assert_eq!(expand_str("{08..10}"), vec!["08", "09", "10"]);
+        assert_eq!(
+            expand_str("{-9223372036854775808..-9223372036854775807}"),
+            vec!["-9223372036854775808", "-9223372036854775807"]
+        );
+        assert_eq!(expand_str("{-05..-4}"), vec!["-05", "-04"]);
+        assert_eq!(expand_str("{a..e..2}"), vec!["a", "c", "e"]);
+        assert_eq!(expand_str("{a}"), vec!["{a}"]);
+        assert_eq!(expand_str("{a}{b,c}"), vec!["{a}b", "{a}c"]);
+        assert_eq!(expand_str("{a..1}"), vec!["{a..1}"]);
+        assert_eq!(expand_str("{a,b"), vec!["{a,b"]);
+
+        let pieces = vec![
+            Piece::Byte(b'{'),
+            Piece::Opaque(()),
+            Piece::Byte(b','),
+            Piece::Byte(b'x'),
+            Piece::Byte(b'}'),
+        ];
+        assert_eq!(
+            expand(pieces),
+            vec![vec![Piece::Opaque(())], vec![Piece::Byte(b'x')]]
+        );
+    }
+}
@@ -0,0 +1,294 @@
+use {
+    crate::{expansion::Word, global_state::settings::GlobOptions},
+    pseudobash_regex::glob::Pattern,
+    std::{
+        ffi::OsStr,
+        os::unix::ffi::OsStrExt,
+        path::{Path, PathBuf},
+    },
+};
+
+// Wildcards match characters, so the bytes are decoded, and a character is
+// quoted when its first byte is.
+pub fn pattern(data: &[u8], quoted: &[bool]) -> Pattern {
+    let (mut chars, mut mask) = (Vec::new(), Vec::new());
+    let mut offset = 0;
+    for chunk in data.utf8_chunks() {
+        for (idx, char) in chunk.valid().char_indices() {
+            chars.push(char);
+            mask.push(quoted[offset + idx]);
+        }
+        offset += chunk.valid().len();
+        if !chunk.invalid().is_empty() {
+            chars.push(char::REPLACEMENT_CHARACTER);
+            mask.push(quoted[offset]);
+            offset += chunk.invalid().len();
+        }
+    }
+    Pattern::new(&chars, &mask, false)
+}
+
+pub fn expand(word: Word, options: &GlobOptions, cwd: &Path) -> Vec<String> {
+    let components = split_components(&word);
+    if components
+        .iter()
+        .all(|(data, quoted)| pattern(data, quoted).is_literal())
+    {
+        return vec![word.into_string()];
+    }
+
+    let absolute = word.as_bytes().first() == Some(&b'/');
+    let mut candidates: Vec<Vec<u8>> = vec![if absolute { b"/".to_vec() } else { vec![] }];
+    let last_idx = components.len() - 1;
+    for (idx, (data, quoted)) in components.iter().enumerate() {
+        let is_last = idx == last_idx;
+        if data.is_empty() {
+            if is_last {
+                candidates.retain(|candidate| resolve(cwd, candidate).is_dir());
+                candidates
+                    .iter_mut()
+                    .for_each(|candidate| candidate.push(b'/'));
+            }
+            continue;
+        }
+
+        let pattern = pattern(data, quoted);
+        candidates = if pattern.is_literal() {
+            candidates
+                .into_iter()
+                .map(|candidate| join(&candidate, data))
+                .filter(|candidate| resolve(cwd, candidate).symlink_metadata().is_ok())
+                .collect()
+        } else if options.globstar && data == b"**" {
+            candidates
+                .into_iter()
+                .flat_map(|candidate| {
+                    let mut output = if is_last {
+                        vec![]
+                    } else {
+                        vec![candidate.clone()]
+                    };
+                    walk(cwd, &candidate, options, !is_last, &mut output);
+                    output
+                })
+                .collect()
+        } else {
+            let dot_allowed = options.dotglob || data.first() == Some(&b'.');
+            candidates
+                .into_iter()
+                .flat_map(|candidate| {
+                    read_names(cwd, &candidate)
+                        .into_iter()
+                        .filter(|name| dot_allowed || name.first() != Some(&b'.'))
+                        .filter(|name| pattern.matches(&String::from_utf8_lossy(name)))
+                        .map(|name| join(&candidate, &name))
+                        .filter(|path| is_last || resolve(cwd, path).is_dir())
+                        .collect::<Vec<Vec<u8>>>()
+                })
+                .collect()
+        };
+    }
+
+    candidates.sort();
+    candidates.dedup();
+    if candidates.is_empty() {
+        if options.nullglob {
+            vec![]
+        } else {
+            vec![word.into_string()]
+        }
+    } else {
+        candidates
+            .into_iter()
+            .map(|path| String::from_utf8_lossy(&path).to_string())
+            .collect()
+    }
+}
+
+fn split_components(word: &Word) -> Vec<(Vec<u8>, Vec<bool>)> {
+    let mut components = vec![(vec![], vec![])];
+    for (byte, quoted) in word.data.iter().zip(word.quoted()) {
+        if *byte == b'/' {
+            components.push((vec![], vec![]));
+        } else if let Some((data, mask)) = components.last_mut() {
+            data.push(*byte);
+            mask.push(quoted);
+        }
+    }
+    if word.data.first() == Some(&b'/') {
+        components.remove(0);
+    }
+    components
+}
+
+fn walk(cwd: &Path, dir: &[u8], options: &GlobOptions, only_dirs: bool, output: &mut Vec<Vec<u8>>) {
+    for name in read_names(cwd, dir) {
+        if !options.dotglob && name.first() == Some(&b'.') {
+            continue;
+        }
+        let path = join(dir, &name);
+        let is_dir = resolve(cwd, &path)
+            .symlink_metadata()
+            .is_ok_and(|meta| meta.is_dir());
+        if is_dir || !only_dirs {
+            output.push(path.clone());
+        }
+        if is_dir {
+            walk(cwd, &path, options, only_dirs, output);
+        }
+    }
+}
+
+fn read_names(cwd: &Path, dir: &[u8]) -> Vec<Vec<u8>> {
+    let path = if dir.is_empty() {
+        cwd.to_path_buf()
+    } else {
+        resolve(cwd, dir)
+    };
+    let mut names: Vec<Vec<u8>> = std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name().as_bytes().to_vec())
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+fn join(dir: &[u8], name: &[u8]) -> Vec<u8> {
+    let mut path = dir.to_vec();
+    if !path.is_empty() && path.last() != Some(&b'/') {
+        path.push(b'/');
+    }
+    path.extend_from_slice(name);
+    path
+}
+
+fn resolve(cwd: &Path, path: &[u8]) -> PathBuf {
+    cwd.join(OsStr::from_bytes(path))
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        crate::{
+            expansion::{
+                Origin, Word,
+                glob::{expand, pattern},
+            },
+            global_state::settings::GlobOptions,
+        },
+        std::path::PathBuf,
+    };
+
+    fn new_word(data: &str, quoted: bool) -> Word {
+        let mut word = Word::default();
+        let origin = if quoted {
+            Origin::Quoted
+        } else {
+            Origin::Literal
+        };
+        word.push(data.as_bytes().to_vec(), origin);
+        word
+    }
+
+    fn new_tree(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("pseudobash-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        for dir in ["src/parser", "src/.hidden", "docs"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in [
+            "a.txt",
+            "b.txt",
+            ".c.txt",
+            "src/main.rs",
+            "src/lib.rs",
+            "src/parser/token.rs",
+            "src/.hidden/x.rs",
+        ] {
+            std::fs::write(root.join(file), b"").unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn check_pattern() {
+        let unquoted = |data: &[u8]| pattern(data, &vec![false; data.len()]);
+        assert!(unquoted(b"*.txt").matches("a.txt"));
+        assert!(unquoted("?[é]".as_bytes()).matches("aé"));
+        assert!(unquoted(b"a?\xff").matches("ab\u{fffd}"));
+        assert!(!pattern(b"*.txt", &[true, false, false, false, false]).matches("a.txt"));
+        assert!(pattern(b"*.txt", &[true, false, false, false, false]).matches("*.txt"));
+        assert!(pattern("é*".as_bytes(), &[false, false, true]).is_literal());
+    }
+
+    #[test]
+    fn check_expand() {
+        let root = new_tree("glob");
+        let options = GlobOptions::default();
+
+        assert_eq!(
+            expand(new_word("*.txt", false), &options, &root),
+            vec!["a.txt", "b.txt"]
+        );
+        assert_eq!(
+            expand(new_word("*.txt", true), &options, &root),
+            vec!["*.txt"]
+        );
+        assert_eq!(
+            expand(new_word("src/*.rs", false), &options, &root),
+            vec!["src/lib.rs", "src/main.rs"]
+        );
+        assert_eq!(
+            expand(new_word("*/", false), &options, &root),
+            vec!["docs/", "src/"]
+        );
+        assert_eq!(
+            expand(new_word("*.md", false), &options, &root),
+            vec!["*.md"]
+        );
+        assert_eq!(
+            expand(new_word("**/*.rs", false), &options, &root),
+            vec!["src/lib.rs", "src/main.rs"]
+        );
+        assert_eq!(
+            expand(
+                new_word(&format!("{}/?.txt", root.display()), false),
+                &options,
+                &root
+            ),
+            vec![
+                format!("{}/a.txt", root.display()),
+                format!("{}/b.txt", root.display())
+            ]
+        );
+
+        let options = GlobOptions {
+            nullglob: true,
+            dotglob: true,
+            globstar: true,
+        };
+        assert_eq!(
+            expand(new_word("*.md", false), &options, &root),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            expand(new_word("*.txt", false), &options, &root),
+            vec![".c.txt", "a.txt", "b.txt"]
+        );
+        assert_eq!(
+            expand(new_word("**/*.rs", false), &options, &root),
+            vec![
+                "src/.hidden/x.rs",
+                "src/lib.rs",
+                "src/main.rs",
+                "src/parser/token.rs"
+            ]
+        );
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
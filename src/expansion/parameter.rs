@@ -0,0 +1,142 @@
+use crate::global_state::{
+    GlobalState,
+    environment::{is_name, split_subscript},
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Parameter<'a> {
+    prefix: Option<char>,
+    name: &'a str,
+    subscript: Option<&'a str>,
+}
+
+impl<'a> Parameter<'a> {
+    pub fn parse(text: &'a str) -> Option<Self> {
+        if is_special(text) {
+            return Some(Self {
+                prefix: None,
+                name: text,
+                subscript: None,
+            });
+        }
+
+        let (prefix, rest) = match text.chars().next() {
+            Some(prefix @ ('#' | '!')) => (Some(prefix), &text[1..]),
+            _ => (None, text),
+        };
+        let (name, subscript) = split_subscript(rest);
+        let valid = match (prefix, subscript) {
+            (_, None) if is_special(name) => prefix != Some('!'),
+            (Some('!'), Some(subscript)) => is_name(name) && matches!(subscript, "@" | "*"),
+            (Some('!'), None) => false,
+            _ => is_name(name),
+        };
+        valid.then_some(Self {
+            prefix,
+            name,
+            subscript,
+        })
+    }
+
+    pub fn expand(&self, quoted: bool, gs: &mut GlobalState) -> Vec<Vec<u8>> {
+        let environment = &mut gs.environment;
+        let values = match (self.name, self.subscript) {
+            ("@" | "*", None) => Some(environment.positional().to_vec()),
+            (name, Some("@" | "*")) if self.prefix == Some('!') => Some(environment.keys(name)),
+            (name, Some("@" | "*")) => Some(environment.values(name)),
+            _ => None,
+        };
+        let joined = quoted && (self.name == "*" || self.subscript == Some("*"));
+
+        match (self.prefix, values) {
+            (Some('#'), Some(values)) => vec![values.len().to_string().into_bytes()],
+            (_, Some(values)) if joined => {
+                let separator = environment.ifs().first().map(|byte| vec![*byte]);
+                vec![
+                    values
+                        .into_iter()
+                        .map(String::into_bytes)
+                        .collect::<Vec<Vec<u8>>>()
+                        .join(separator.as_deref().unwrap_or_default()),
+                ]
+            }
+            (_, Some(values)) => values.into_iter().map(String::into_bytes).collect(),
+            (prefix, None) => {
+                let value = match self.subscript {
+                    Some(subscript) => environment
+                        .element(self.name, subscript)
+                        .ok()
+                        .flatten()
+                        .unwrap_or_default()
+                        .into_bytes(),
+                    None => {
+                        let mut value = self.name.as_bytes().to_vec();
+                        environment.get_var(&mut value);
+                        value
+                    }
+                };
+                match prefix {
+                    Some('#') => vec![
+                        String::from_utf8_lossy(&value)
+                            .chars()
+                            .count()
+                            .to_string()
+                            .into_bytes(),
+                    ],
+                    _ => vec![value],
+                }
+            }
+        }
+    }
+}
+
+fn is_special(name: &str) -> bool {
+    matches!(name, "@" | "*" | "#" | "?")
+        || (!name.is_empty() && name.bytes().all(|byte| byte.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{expansion::parameter::Parameter, global_state::GlobalState};
+
+    #[test]
+    fn check_parameter() {
+        assert!(Parameter::parse("x").is_some());
+        assert!(Parameter::parse("#x").is_some());
+        assert!(Parameter::parse("#").is_some());
+        assert!(Parameter::parse("#@").is_some());
+        assert!(Parameter::parse("arr[1+1]").is_some());
+        assert!(Parameter::parse("!arr[@]").is_some());
+        assert!(Parameter::parse("!arr").is_none());
+        assert!(Parameter::parse("").is_none());
+        assert!(Parameter::parse("a b").is_none());
+        assert!(Parameter::parse("arr[]").is_none());
+
+        let mut gs = GlobalState::default();
+        gs.environment
+            .insert_compound("arr", "(a 'b c' [5]=d)")
+            .unwrap();
+        gs.environment
+            .set_positional(vec!["p".to_string(), "q".to_string()]);
+        let expand = |text: &str, quoted: bool, gs: &mut GlobalState| {
+            Parameter::parse(text)
+                .unwrap()
+                .expand(quoted, gs)
+                .into_iter()
+                .map(|field| String::from_utf8(field).unwrap())
+                .collect::<Vec<String>>()
+        };
+
+        assert_eq!(expand("arr[@]", true, &mut gs), vec!["a", "b c", "d"]);
+        assert_eq!(expand("arr[*]", true, &mut gs), vec!["a b c d"]);
+        assert_eq!(expand("!arr[@]", false, &mut gs), vec!["0", "1", "5"]);
+        assert_eq!(expand("#arr[@]", false, &mut gs), vec!["3"]);
+        assert_eq!(expand("arr[2*2+1]", false, &mut gs), vec!["d"]);
+        assert_eq!(expand("arr[-1]", false, &mut gs), vec!["d"]);
+        assert_eq!(expand("#arr[1]", false, &mut gs), vec!["3"]);
+        assert_eq!(expand("arr", false, &mut gs), vec!["a"]);
+        assert_eq!(expand("#", false, &mut gs), vec!["2"]);
+        assert_eq!(expand("#@", false, &mut gs), vec!["2"]);
+        assert_eq!(expand("2", false, &mut gs), vec!["q"]);
+    }
+}
@@ -0,0 +1,139 @@
+use crate::expansion::{Origin, Word};
+
+pub const DEFAULT_IFS: &[u8] = b" \t\n";
+
+pub fn split(word: Word, ifs: &[u8]) -> Vec<Word> {
+    if word.is_empty() && word.breaks.is_empty() {
+        return vec![word];
+    }
+    let is_delimiter =
+        |idx: usize| word.origin[idx] == Origin::Expanded && ifs.contains(&word.data[idx]);
+    let is_whitespace = |idx: usize| is_delimiter(idx) && word.data[idx].is_ascii_whitespace();
+
+    let mut fields = Vec::new();
+    let mut current: Option<Word> = None;
+    let mut breaks = word.breaks.iter().peekable();
+    let mut idx = 0;
+    while idx < word.data.len() {
+        if let Some((_, keep_empty)) = breaks.next_if(|(position, _)| *position == idx) {
+            end_field(&mut fields, current.take(), *keep_empty);
+            continue;
+        }
+
+        if is_delimiter(idx) {
+            while idx < word.data.len() && is_whitespace(idx) {
+                idx += 1;
+            }
+            let mut hard = false;
+            if idx < word.data.len() && is_delimiter(idx) && !is_whitespace(idx) {
+                hard = true;
+                idx += 1;
+                while idx < word.data.len() && is_whitespace(idx) {
+                    idx += 1;
+                }
+            }
+            if current.is_some() || hard {
+                fields.push(current.take().unwrap_or_default());
+            }
+            continue;
+        }
+
+        current
+            .get_or_insert_default()
+            .push(vec![word.data[idx]], word.origin[idx]);
+        idx += 1;
+    }
+    for (_, keep_empty) in breaks {
+        end_field(&mut fields, current.take(), *keep_empty);
+    }
+    // The last field belongs to the same expansion as the last break.
+    let keep_empty = word
+        .breaks
+        .last()
+        .is_some_and(|(_, keep_empty)| *keep_empty);
+    end_field(&mut fields, current, keep_empty);
+    fields
+}
+
+fn end_field(fields: &mut Vec<Word>, field: Option<Word>, keep_empty: bool) {
+    match keep_empty {
+        true => fields.push(field.unwrap_or_default()),
+        false => fields.extend(field),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::expansion::{
+        Origin, Word,
+        split::{DEFAULT_IFS, split},
+    };
+
+    fn new_word(parts: &[(&str, Origin)]) -> Word {
+        let mut word = Word::default();
+        for (data, origin) in parts {
+            word.push(data.as_bytes().to_vec(), *origin);
+        }
+        word
+    }
+
+    fn split_str(word: Word, ifs: &[u8]) -> Vec<String> {
+        split(word, ifs)
+            .into_iter()
+            .map(|word| word.into_string())
+            .collect()
+    }
+
+    #[test]
+    fn check_split() {
+        let word = new_word(&[("  a  b\tc ", Origin::Expanded)]);
+        assert_eq!(split_str(word, DEFAULT_IFS), vec!["a", "b", "c"]);
+
+        let word = new_word(&[("x", Origin::Literal), ("a b", Origin::Expanded)]);
+        assert_eq!(split_str(word, DEFAULT_IFS), vec!["xa", "b"]);
+
+        let word = new_word(&[("a b", Origin::Quoted)]);
+        assert_eq!(split_str(word, DEFAULT_IFS), vec!["a b"]);
+
+        let word = new_word(&[("a b", Origin::Literal)]);
+        assert_eq!(split_str(word, DEFAULT_IFS), vec!["a b"]);
+
+        let word = new_word(&[(":a::b : c:", Origin::Expanded)]);
+        assert_eq!(split_str(word, b" :"), vec!["", "a", "", "b", "c"]);
+
+        let word = new_word(&[("a b", Origin::Expanded)]);
+        assert_eq!(split_str(word, b""), vec!["a b"]);
+
+        let word = new_word(&[("   ", Origin::Expanded)]);
+        assert_eq!(split_str(word, DEFAULT_IFS), Vec::<String>::new());
+
+        let mut word = new_word(&[("a b", Origin::Quoted)]);
+        word.push_break(true);
+        word.push(b"c".to_vec(), Origin::Quoted);
+        assert_eq!(split_str(word, DEFAULT_IFS), vec!["a b", "c"]);
+
+        let mut word = new_word(&[("a b", Origin::Quoted)]);
+        word.push_break(true);
+        word.push_break(true);
+        word.push(b"c".to_vec(), Origin::Quoted);
+        assert_eq!(split_str(word, DEFAULT_IFS), vec!["a b", "", "c"]);
+
+        let mut word = new_word(&[("a", Origin::Quoted)]);
+        word.push_break(true);
+        assert_eq!(split_str(word, DEFAULT_IFS), vec!["a", ""]);
+
+        let mut word = new_word(&[("a", Origin::Expanded)]);
+        word.push_break(false);
+        word.push(b"b".to_vec(), Origin::Expanded);
+        word.push_break(false);
+        assert_eq!(split_str(word, DEFAULT_IFS), vec!["a", "b"]);
+
+        let mut word = Word::default();
+        word.push_break(true);
+        assert_eq!(split_str(word, DEFAULT_IFS), vec!["", ""]);
+
+        let mut word = new_word(&[("", Origin::Quoted)]);
+        word.set_vanished();
+        assert!(word.is_null());
+    }
+}
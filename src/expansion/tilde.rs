@@ -0,0 +1,92 @@
+use crate::global_state::environment::Environment;
+
+pub fn expand(data: &[u8], whole: bool, assignment: bool, environment: &Environment) -> Vec<u8> {
+    let mut starts = Vec::new();
+    if assignment {
+        if let Some(eq_idx) = data.iter().position(|byte| *byte == b'=') {
+            starts.push(eq_idx + 1);
+            starts.extend(
+                data.iter()
+                    .enumerate()
+                    .skip(eq_idx + 1)
+                    .filter(|(_, byte)| **byte == b':')
+                    .map(|(idx, _)| idx + 1),
+            );
+        }
+    } else {
+        starts.push(0);
+    }
+
+    let mut output = Vec::new();
+    let mut copied = 0;
+    for start in starts {
+        if data.get(start) != Some(&b'~') {
+            continue;
+        }
+        let end = data[start..]
+            .iter()
+            .position(|byte| *byte == b'/' || assignment && *byte == b':')
+            .map(|offset| start + offset);
+        if end.is_none() && !whole {
+            continue;
+        }
+        let end = end.unwrap_or(data.len());
+        if let Some(value) = lookup(&data[start + 1..end], environment) {
+            output.extend_from_slice(&data[copied..start]);
+            output.extend(value.into_bytes());
+            copied = end;
+        }
+    }
+    output.extend_from_slice(&data[copied..]);
+    output
+}
+
+fn lookup(prefix: &[u8], environment: &Environment) -> Option<String> {
+    match prefix {
+        b"" => environment.get("HOME").cloned(),
+        b"+" => environment.get("PWD").cloned(),
+        b"-" => environment.get("OLDPWD").cloned(),
+        user => home_of(&String::from_utf8_lossy(user)),
+    }
+}
+
+fn home_of(user: &str) -> Option<String> {
+    std::fs::read_to_string("/etc/passwd")
+        .ok()?
+        .lines()
+        .map(|line| line.split(':').collect::<Vec<&str>>())
+        .find(|fields| fields.len() > 5 && fields[0] == user)
+        .map(|fields| fields[5].to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{expansion::tilde::expand, global_state::environment::Environment};
+
+    #[test]
+    fn check_expand() {
+        let mut environment = Environment::default();
+        environment.insert("HOME", "/home/user").unwrap();
+        environment.insert("PWD", "/work").unwrap();
+        environment.insert("OLDPWD", "/old").unwrap();
+
+        assert_eq!(expand(b"~", true, false, &environment), b"/home/user");
+        assert_eq!(
+            expand(b"~/projects", false, false, &environment),
+            b"/home/user/projects"
+        );
+        assert_eq!(expand(b"~+/x", true, false, &environment), b"/work/x");
+        assert_eq!(expand(b"~-", true, false, &environment), b"/old");
+        assert_eq!(expand(b"~", false, false, &environment), b"~");
+        assert_eq!(expand(b"a~", true, false, &environment), b"a~");
+        assert_eq!(expand(b"~root", true, false, &environment), b"/root");
+        assert_eq!(
+            expand(b"~no_such_user_here", true, false, &environment),
+            b"~no_such_user_here"
+        );
+        assert_eq!(
+            expand(b"x=~/a:~-:b~", true, true, &environment),
+            b"x=/home/user/a:/old:b~"
+        );
+    }
+}
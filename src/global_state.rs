@@ -1,10 +1,52 @@
-mod environment;
-mod settings;
+pub mod command_hash;
+pub mod environment;
+pub mod settings;
 
-use crate::global_state::{environment::Environment, settings::Settings};
+use {
+    crate::{
+        global_state::{command_hash::CommandHash, environment::Environment, settings::Settings},
+        parser::command_builder::command::Command,
+    },
+    std::{collections::HashMap, path::PathBuf},
+};
 
-#[derive(Default)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Control {
+    Break(usize),
+    Continue(usize),
+    Return,
+    Exit(i32),
+}
+
+#[derive(Default, Clone)]
 pub struct GlobalState {
     pub(crate) environment: Environment,
     pub(crate) settings: Settings,
+    pub(crate) functions: HashMap<String, Command>,
+    pub(crate) aliases: HashMap<String, String>,
+    pub(crate) commands: CommandHash,
+    pub(crate) control: Option<Control>,
+    pub(crate) loop_depth: usize,
+    pub(crate) subshell: bool,
+    // Here-doc or here-string input of the running function, taken by the
+    // first external command inside it.
+    pub(crate) input: Option<Vec<u8>>,
+}
+
+impl GlobalState {
+    pub fn utils_dir(&self) -> Option<PathBuf> {
+        self.environment
+            .get("PSEUDOBASH_UTILS")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| self.settings.utils_dir.clone())
+    }
+
+    pub fn search_path(&self) -> String {
+        let path = self.environment.get("PATH").cloned().unwrap_or_default();
+        match self.utils_dir() {
+            Some(dir) => format!("{}:{}", dir.display(), path),
+            None => path,
+        }
+    }
 }
@@ -0,0 +1,80 @@
+use std::{
+    collections::BTreeMap,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone)]
+pub struct Hashed {
+    pub(crate) path: PathBuf,
+    pub(crate) hits: usize,
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct CommandHash {
+    search_path: String,
+    entries: BTreeMap<String, Hashed>,
+}
+
+impl CommandHash {
+    pub fn resolve(&mut self, name: &str, search_path: &str) -> Option<&mut Hashed> {
+        self.sync(search_path);
+        if self
+            .entries
+            .get(name)
+            .is_some_and(|hashed| !is_executable(&hashed.path))
+        {
+            self.entries.remove(name);
+        }
+        if !self.entries.contains_key(name) {
+            let path = search(name, search_path).into_iter().next()?;
+            self.entries
+                .insert(name.to_string(), Hashed { path, hits: 0 });
+        }
+        self.entries.get_mut(name)
+    }
+
+    pub fn get(&mut self, name: &str, search_path: &str) -> Option<&Hashed> {
+        self.sync(search_path);
+        self.entries.get(name)
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.entries.remove(name).is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn entries(&mut self, search_path: &str) -> impl Iterator<Item = (&String, &Hashed)> {
+        self.sync(search_path);
+        self.entries.iter()
+    }
+
+    fn sync(&mut self, search_path: &str) {
+        if search_path != self.search_path {
+            self.entries.clear();
+            self.search_path = search_path.to_string();
+        }
+    }
+}
+
+pub fn search(name: &str, search_path: &str) -> Vec<PathBuf> {
+    if name.is_empty() || name.contains('/') {
+        return vec![];
+    }
+    search_path
+        .split(':')
+        .map(|dir| match dir {
+            "" => Path::new(".").join(name),
+            dir => Path::new(dir).join(name),
+        })
+        .filter(|path| is_executable(path))
+        .collect()
+}
+
+pub fn is_executable(path: &Path) -> bool {
+    path.metadata()
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
@@ -1,82 +1,477 @@
-use std::{
-    collections::{HashMap, hash_map::Iter},
-    env::{current_exe, var, vars},
+pub mod value;
+
+use {
+    crate::{
+        arithmetic,
+        expansion::split::DEFAULT_IFS,
+        global_state::environment::value::{Value, parse_compound},
+    },
+    std::{
+        collections::{BTreeMap, HashMap, hash_map::Iter},
+        env::vars,
+    },
 };
 
+#[derive(Default, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Attributes {
+    pub(crate) exported: bool,
+    pub(crate) readonly: bool,
+    pub(crate) integer: bool,
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct Variable {
+    pub(crate) value: Option<Value>,
+    pub(crate) attributes: Attributes,
+}
+
+impl Variable {
+    pub fn flags(&self) -> String {
+        let mut flags = String::new();
+        flags.extend(self.value.as_ref().and_then(|value| value.flag()));
+        if self.attributes.integer {
+            flags.push('i');
+        }
+        if self.attributes.readonly {
+            flags.push('r');
+        }
+        if self.attributes.exported {
+            flags.push('x');
+        }
+        if flags.is_empty() {
+            flags.push('-');
+        }
+        flags
+    }
+}
+
+enum Key {
+    Index(usize),
+    Name(String),
+}
+
 #[derive(Clone)]
 pub struct Environment {
-    map: HashMap<String, String>,
+    map: HashMap<String, Variable>,
+    positional: Vec<String>,
+    status: i32,
+    scopes: Vec<HashMap<String, Option<Variable>>>,
 }
 
 impl Environment {
     pub fn get_var(&self, name: &mut Vec<u8>) {
-        std::mem::swap(
-            name,
-            &mut self
-                .map
-                .get(&String::from_utf8_lossy(name).to_string())
-                .unwrap_or(&String::new())
-                .as_bytes()
-                .to_vec(),
-        );
-    }
-
-    pub fn set_var(&mut self, var: Vec<u8>) {
-        let mut is_name = true;
-        let mut name = Vec::new();
-        let mut value = Vec::new();
-        for byte in var {
-            if byte == b'=' && is_name {
-                is_name = false;
-                continue;
+        let name_str = String::from_utf8_lossy(name).to_string();
+        *name = match name_str.as_str() {
+            "#" => self.positional.len().to_string(),
+            "?" => self.status.to_string(),
+            "0" => "pseudobash".to_string(),
+            number if number.bytes().all(|byte| byte.is_ascii_digit()) => number
+                .parse::<usize>()
+                .ok()
+                .and_then(|idx| self.positional.get(idx.checked_sub(1)?))
+                .cloned()
+                .unwrap_or_default(),
+            name => self.get(name).cloned().unwrap_or_default(),
+        }
+        .into_bytes();
+    }
+
+    pub fn positional(&self) -> &[String] {
+        &self.positional
+    }
+
+    pub fn set_positional(&mut self, positional: Vec<String>) {
+        self.positional = positional;
+    }
+
+    pub fn status(&self) -> i32 {
+        self.status
+    }
+
+    pub fn set_status(&mut self, status: i32) {
+        self.status = status;
+    }
+
+    pub fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    pub fn pop_scope(&mut self) {
+        for (name, variable) in self.scopes.pop().unwrap_or_default() {
+            self.restore(&name, variable);
+        }
+    }
+
+    pub fn in_function(&self) -> bool {
+        !self.scopes.is_empty()
+    }
+
+    pub fn make_local(&mut self, name: &str) -> anyhow::Result<()> {
+        self.check_writable(name)?;
+        let Some(scope) = self.scopes.last_mut() else {
+            anyhow::bail!("can only be used in a function")
+        };
+        if !scope.contains_key(name) {
+            scope.insert(name.to_string(), self.map.remove(name));
+            self.map.insert(name.to_string(), Variable::default());
+        }
+        Ok(())
+    }
+
+    pub fn locals(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .scopes
+            .last()
+            .map(|scope| scope.keys().map(|name| name.as_str()).collect())
+            .unwrap_or_default();
+        names.sort();
+        names
+    }
+
+    pub fn ifs(&self) -> Vec<u8> {
+        self.get("IFS")
+            .map(|ifs| ifs.as_bytes().to_vec())
+            .unwrap_or(DEFAULT_IFS.to_vec())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&String> {
+        self.map.get(name)?.value.as_ref()?.scalar()
+    }
+
+    pub fn element(&mut self, name: &str, subscript: &str) -> anyhow::Result<Option<String>> {
+        let key = self.key(name, subscript)?;
+        Ok(
+            match (self.map.get(name).and_then(|var| var.value.as_ref()), key) {
+                (Some(Value::Scalar(value)), Key::Index(0)) => Some(value.clone()),
+                (Some(Value::Indexed(map)), Key::Index(index)) => map.get(&index).cloned(),
+                (Some(Value::Associative(map)), Key::Name(key)) => map.get(&key).cloned(),
+                _ => None,
+            },
+        )
+    }
+
+    pub fn values(&self, name: &str) -> Vec<String> {
+        self.map
+            .get(name)
+            .and_then(|variable| variable.value.as_ref())
+            .map(|value| value.values())
+            .unwrap_or_default()
+    }
+
+    pub fn keys(&self, name: &str) -> Vec<String> {
+        self.map
+            .get(name)
+            .and_then(|variable| variable.value.as_ref())
+            .map(|value| value.keys())
+            .unwrap_or_default()
+    }
+
+    pub fn variable(&self, name: &str) -> Option<&Variable> {
+        self.map.get(name)
+    }
+
+    pub fn check_writable(&self, name: &str) -> anyhow::Result<()> {
+        let (name, _) = split_subscript(name);
+        if !is_name(name) {
+            anyhow::bail!("`{}': not a valid identifier", name)
+        }
+        if self
+            .map
+            .get(name)
+            .is_some_and(|variable| variable.attributes.readonly)
+        {
+            anyhow::bail!("{}: readonly variable", name)
+        }
+        Ok(())
+    }
+
+    pub fn insert(&mut self, name: &str, value: &str) -> anyhow::Result<()> {
+        self.check_writable(name)?;
+        let (name, subscript) = split_subscript(name);
+        let value = self.integer_value(name, value)?;
+        let key = match subscript {
+            Some(subscript) => Some(self.key(name, subscript)?),
+            None => None,
+        };
+
+        let variable = self.map.entry(name.to_string()).or_default();
+        match (&mut variable.value, key) {
+            (Some(Value::Indexed(map)), None) => {
+                map.insert(0, value);
+            }
+            (Some(Value::Associative(map)), None) => {
+                map.insert("0".to_string(), value);
+            }
+            (Some(Value::Associative(map)), Some(Key::Name(key))) => {
+                map.insert(key, value);
             }
-            if is_name {
-                name.push(byte);
-            } else {
-                value.push(byte);
+            (old_value, Some(Key::Index(index))) => {
+                let mut map = match old_value.take() {
+                    Some(Value::Indexed(map)) => map,
+                    Some(Value::Scalar(old_value)) => BTreeMap::from([(0, old_value)]),
+                    _ => BTreeMap::new(),
+                };
+                map.insert(index, value);
+                *old_value = Some(Value::Indexed(map));
             }
+            (old_value, _) => *old_value = Some(Value::Scalar(value)),
         }
-        self.map.insert(
-            String::from_utf8_lossy(&name).to_string(),
-            String::from_utf8_lossy(&value).to_string(),
-        );
+        Ok(())
     }
 
-    pub fn vars(&self) -> Iter<String, String> {
-        self.map.iter()
+    pub fn insert_compound(&mut self, name: &str, compound: &str) -> anyhow::Result<()> {
+        let (name, append) = match name.strip_suffix('+') {
+            Some(name) => (name, true),
+            None => (name, false),
+        };
+        self.check_writable(name)?;
+        let old_value = self
+            .map
+            .get(name)
+            .and_then(|variable| variable.value.clone());
+        let mut value = match (old_value, append) {
+            (Some(Value::Associative(map)), true) => Value::Associative(map),
+            (Some(Value::Associative(_)), false) => Value::Associative(BTreeMap::new()),
+            (Some(Value::Indexed(map)), true) => Value::Indexed(map),
+            (Some(Value::Scalar(old_value)), true) => {
+                Value::Indexed(BTreeMap::from([(0, old_value)]))
+            }
+            _ => Value::Indexed(BTreeMap::new()),
+        };
+
+        let mut next_index = match &value {
+            Value::Indexed(_) => value.next_index(),
+            _ => 0,
+        };
+        for (key, item) in parse_compound(compound)? {
+            let item = self.integer_value(name, &item)?;
+            match (&mut value, key) {
+                (Value::Associative(map), Some(key)) => {
+                    map.insert(key, item);
+                }
+                (Value::Associative(_), None) => anyhow::bail!(
+                    "{}: {}: must use subscript when assigning associative array",
+                    name,
+                    item
+                ),
+                (Value::Indexed(map), key) => {
+                    let index = match key {
+                        Some(key) => {
+                            usize::try_from(arithmetic::evaluate(&key, self)?).map_err(|_| {
+                                anyhow::Error::msg(format!(
+                                    "{}[{}]: bad array subscript",
+                                    name, key
+                                ))
+                            })?
+                        }
+                        None => next_index,
+                    };
+                    map.insert(index, item);
+                    next_index = index + 1;
+                }
+                (Value::Scalar(_), _) => unreachable!(),
+            }
+        }
+
+        self.map.entry(name.to_string()).or_default().value = Some(value);
+        Ok(())
     }
-}
 
-impl Default for Environment {
-    fn default() -> Self {
-        let mut result = Self {
-            map: vars().collect(),
+    pub fn declare_array(&mut self, name: &str, associative: bool) -> anyhow::Result<()> {
+        self.check_writable(name)?;
+        let variable = self.map.entry(name.to_string()).or_default();
+        variable.value = match (variable.value.take(), associative) {
+            (None, false) => Some(Value::Indexed(BTreeMap::new())),
+            (None, true) => Some(Value::Associative(BTreeMap::new())),
+            (Some(Value::Scalar(value)), false) => {
+                Some(Value::Indexed(BTreeMap::from([(0, value)])))
+            }
+            (Some(Value::Scalar(value)), true) => Some(Value::Associative(BTreeMap::from([(
+                "0".to_string(),
+                value,
+            )]))),
+            (Some(Value::Indexed(map)), true) if map.is_empty() => {
+                Some(Value::Associative(BTreeMap::new()))
+            }
+            (Some(value @ Value::Indexed(_)), true) => {
+                variable.value = Some(value);
+                anyhow::bail!("{}: cannot convert indexed to associative array", name)
+            }
+            (Some(value @ Value::Associative(_)), false) => {
+                variable.value = Some(value);
+                anyhow::bail!("{}: cannot convert associative to indexed array", name)
+            }
+            (value, _) => value,
         };
+        Ok(())
+    }
 
-        match current_exe()
-            .map_err(|e| anyhow::Error::new(e))
-            .and_then(|path| {
-                path.parent()
-                    .ok_or(anyhow::Error::msg(""))
-                    .map(|path| path.to_owned())
-            })
-            .and_then(|path| {
-                path.join("../utils/release")
-                    .canonicalize()
-                    .map_err(|e| anyhow::Error::new(e))
-            })
-            .map(|path| path.to_string_lossy().to_string())
-            .map(|mut string| {
-                string.push(':');
-                string.push_str(&var("PATH").unwrap());
-                string
-            }) {
-            Ok(path_value) => {
-                result.map.insert("PATH".to_string(), path_value);
+    pub fn resolve_append(&mut self, name: &str, value: &str) -> (String, String) {
+        let Some(name) = name.strip_suffix('+') else {
+            return (name.to_string(), value.to_string());
+        };
+        let old_value = match split_subscript(name) {
+            (base, Some(subscript)) => self.element(base, subscript).ok().flatten(),
+            (base, None) => self.get(base).cloned(),
+        }
+        .unwrap_or_default();
+        let value = match self.variable(split_subscript(name).0) {
+            Some(variable) if variable.attributes.integer => {
+                format!("({}) + ({})", old_value, value)
             }
-            Err(e) => eprintln!("WARNING! Failed to modify $PATH: {}", e),
+            _ => old_value + value,
+        };
+        (name.to_string(), value)
+    }
+
+    pub fn remove(&mut self, name: &str) -> anyhow::Result<()> {
+        self.check_writable(name)?;
+        let (name, subscript) = split_subscript(name);
+        let Some(subscript) = subscript else {
+            self.map.remove(name);
+            return Ok(());
+        };
+
+        let key = self.key(name, subscript)?;
+        let Some(variable) = self.map.get_mut(name) else {
+            return Ok(());
+        };
+        match (&mut variable.value, key) {
+            (Some(Value::Indexed(map)), Key::Index(index)) => {
+                map.remove(&index);
+            }
+            (Some(Value::Associative(map)), Key::Name(key)) => {
+                map.remove(&key);
+            }
+            (Some(Value::Scalar(_)), Key::Index(0)) => {
+                self.map.remove(name);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    pub fn attributes_mut(&mut self, name: &str) -> anyhow::Result<&mut Attributes> {
+        if !is_name(name) {
+            anyhow::bail!("`{}': not a valid identifier", name)
+        }
+        Ok(&mut self.map.entry(name.to_string()).or_default().attributes)
+    }
+
+    pub fn restore(&mut self, name: &str, variable: Option<Variable>) {
+        match variable {
+            Some(variable) => self.map.insert(name.to_string(), variable),
+            None => self.map.remove(name),
         };
+    }
+
+    pub fn exported(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.map
+            .iter()
+            .filter_map(|(name, variable)| match &variable.value {
+                Some(Value::Scalar(value)) if variable.attributes.exported => Some((name, value)),
+                _ => None,
+            })
+    }
+
+    pub fn vars(&self) -> Iter<'_, String, Variable> {
+        self.map.iter()
+    }
+
+    fn integer_value(&mut self, name: &str, value: &str) -> anyhow::Result<String> {
+        if self
+            .map
+            .get(name)
+            .is_some_and(|variable| variable.attributes.integer)
+        {
+            Ok(arithmetic::evaluate(value, self)?.to_string())
+        } else {
+            Ok(value.to_string())
+        }
+    }
+
+    fn key(&mut self, name: &str, subscript: &str) -> anyhow::Result<Key> {
+        let subscript = self.expand_subscript(subscript);
+        let value = self
+            .map
+            .get(name)
+            .and_then(|variable| variable.value.as_ref());
+        if let Some(Value::Associative(_)) = value {
+            return Ok(Key::Name(subscript));
+        }
 
-        result
+        let next_index = value.map_or(0, |value| value.next_index()) as i64;
+        let mut index = arithmetic::evaluate(&subscript, self)?;
+        if index < 0 {
+            index += next_index;
+        }
+        usize::try_from(index).map(Key::Index).map_err(|_| {
+            anyhow::Error::msg(format!("{}[{}]: bad array subscript", name, subscript))
+        })
+    }
+
+    fn expand_subscript(&self, subscript: &str) -> String {
+        let mut output = String::new();
+        let mut rest = subscript;
+        while let Some(dollar) = rest.find('$') {
+            output.push_str(&rest[..dollar]);
+            rest = &rest[dollar + 1..];
+            let (name, tail) = match rest
+                .strip_prefix('{')
+                .and_then(|inner| inner.split_once('}'))
+            {
+                Some((name, tail)) => (name, tail),
+                None => {
+                    let end = rest
+                        .find(|char: char| !(char.is_ascii_alphanumeric() || char == '_'))
+                        .unwrap_or(rest.len());
+                    (&rest[..end], &rest[end..])
+                }
+            };
+            let mut value = name.as_bytes().to_vec();
+            self.get_var(&mut value);
+            output.push_str(&String::from_utf8_lossy(&value));
+            rest = tail;
+        }
+        output.push_str(rest);
+        output
+    }
+}
+
+pub fn is_name(name: &str) -> bool {
+    name.bytes()
+        .next()
+        .is_some_and(|byte| byte.is_ascii_alphabetic() || byte == b'_')
+        && name
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'_')
+}
+
+pub fn split_subscript(name: &str) -> (&str, Option<&str>) {
+    match name.strip_suffix(']').and_then(|name| name.split_once('[')) {
+        Some((name, subscript)) if !subscript.is_empty() => (name, Some(subscript)),
+        _ => (name, None),
+    }
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self {
+            map: vars()
+                .map(|(name, value)| {
+                    let variable = Variable {
+                        value: Some(Value::Scalar(value)),
+                        attributes: Attributes {
+                            exported: true,
+                            ..Default::default()
+                        },
+                    };
+                    (name, variable)
+                })
+                .collect(),
+            positional: Vec::new(),
+            status: 0,
+            scopes: Vec::new(),
+        }
     }
 }
@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Scalar(String),
+    Indexed(BTreeMap<usize, String>),
+    Associative(BTreeMap<String, String>),
+}
+
+impl Value {
+    pub fn scalar(&self) -> Option<&String> {
+        match self {
+            Value::Scalar(value) => Some(value),
+            Value::Indexed(map) => map.get(&0),
+            Value::Associative(map) => map.get("0"),
+        }
+    }
+
+    pub fn values(&self) -> Vec<String> {
+        match self {
+            Value::Scalar(value) => vec![value.clone()],
+            Value::Indexed(map) => map.values().cloned().collect(),
+            Value::Associative(map) => map.values().cloned().collect(),
+        }
+    }
+
+    pub fn keys(&self) -> Vec<String> {
+        match self {
+            Value::Scalar(_) => vec!["0".to_string()],
+            Value::Indexed(map) => map.keys().map(|key| key.to_string()).collect(),
+            Value::Associative(map) => map.keys().cloned().collect(),
+        }
+    }
+
+    pub fn next_index(&self) -> usize {
+        match self {
+            Value::Indexed(map) => map.keys().next_back().map_or(0, |key| key + 1),
+            _ => 1,
+        }
+    }
+
+    pub fn flag(&self) -> Option<char> {
+        match self {
+            Value::Scalar(_) => None,
+            Value::Indexed(_) => Some('a'),
+            Value::Associative(_) => Some('A'),
+        }
+    }
+
+    pub fn serialize(&self) -> String {
+        let items: Vec<String> = match self {
+            Value::Scalar(value) => return quote(value),
+            Value::Indexed(map) => map
+                .iter()
+                .map(|(key, value)| format!("[{}]={}", key, quote(value)))
+                .collect(),
+            Value::Associative(map) => map
+                .iter()
+                .map(|(key, value)| format!("[{}]={}", key, quote(value)))
+                .collect(),
+        };
+        format!("({})", items.join(" "))
+    }
+}
+
+pub fn quote(value: &str) -> String {
+    let mut output = String::from('"');
+    for char in value.chars() {
+        if matches!(char, '"' | '\\' | '$' | '`') {
+            output.push('\\');
+        }
+        output.push(char);
+    }
+    output.push('"');
+    output
+}
+
+pub fn is_compound(value: &str) -> bool {
+    value.starts_with('(') && value.ends_with(')')
+}
+
+pub fn parse_compound(text: &str) -> anyhow::Result<Vec<(Option<String>, String)>> {
+    let Some(inner) = text
+        .strip_prefix('(')
+        .and_then(|text| text.strip_suffix(')'))
+    else {
+        anyhow::bail!("{}: not a compound assignment", text)
+    };
+
+    let mut items = Vec::new();
+    let mut chars = inner.chars().peekable();
+    loop {
+        while chars.next_if(|char| char.is_whitespace()).is_some() {}
+        let Some(&first) = chars.peek() else {
+            break;
+        };
+
+        let mut key = None;
+        if first == '[' {
+            chars.next();
+            let mut subscript = String::new();
+            loop {
+                match chars.next() {
+                    Some(']') if chars.peek() == Some(&'=') => {
+                        chars.next();
+                        break;
+                    }
+                    Some(char) => subscript.push(char),
+                    None => anyhow::bail!("[{}: bad array subscript", subscript),
+                }
+            }
+            key = Some(subscript);
+        }
+
+        let mut value = String::new();
+        let mut quote = None;
+        while let Some(char) = chars.next() {
+            match (quote, char) {
+                (None, char) if char.is_whitespace() => break,
+                (None, '"' | '\'') => quote = Some(char),
+                (Some(open), char) if open == char => quote = None,
+                (None | Some('"'), '\\') => value.extend(chars.next()),
+                (_, char) => value.push(char),
+            }
+        }
+        if quote.is_some() {
+            anyhow::bail!("{}: unterminated quote in compound assignment", text)
+        }
+        items.push((key, value));
+    }
+    Ok(items)
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        crate::global_state::environment::value::{Value, parse_compound},
+        std::collections::BTreeMap,
+    };
+
+    #[test]
+    fn check_compound() {
+        assert_eq!(
+            parse_compound("(a 'b c' [5]=\"d\\\"e\" [k ey]=)").unwrap(),
+            vec![
+                (None, "a".to_string()),
+                (None, "b c".to_string()),
+                (Some("5".to_string()), "d\"e".to_string()),
+                (Some("k ey".to_string()), String::new()),
+            ]
+        );
+        assert_eq!(parse_compound("()").unwrap(), vec![]);
+        assert!(parse_compound("(\"a)").is_err());
+
+        let value = Value::Indexed(BTreeMap::from([
+            (0, "a b".to_string()),
+            (3, "$x".to_string()),
+        ]));
+        assert_eq!(value.serialize(), "([0]=\"a b\" [3]=\"\\$x\")");
+        assert_eq!(value.next_index(), 4);
+        assert_eq!(
+            parse_compound(&value.serialize()).unwrap(),
+            vec![
+                (Some("0".to_string()), "a b".to_string()),
+                (Some("3".to_string()), "$x".to_string()),
+            ]
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use std::{env::current_exe, path::PathBuf};
+
 #[derive(Clone, Copy, Default, Debug)]
 pub struct Mode {
     pub(crate) interactive: bool,
@@ -17,10 +19,38 @@ impl From<Mode> for String {
     }
 }
 
+#[derive(Clone, Copy, Default, Debug)]
+pub struct GlobOptions {
+    pub(crate) nullglob: bool,
+    pub(crate) dotglob: bool,
+    pub(crate) globstar: bool,
+}
+
+impl GlobOptions {
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut bool> {
+        match name {
+            "nullglob" => Some(&mut self.nullglob),
+            "dotglob" => Some(&mut self.dotglob),
+            "globstar" => Some(&mut self.globstar),
+            _ => None,
+        }
+    }
+
+    pub fn list(&self) -> Vec<(&'static str, bool)> {
+        vec![
+            ("dotglob", self.dotglob),
+            ("globstar", self.globstar),
+            ("nullglob", self.nullglob),
+        ]
+    }
+}
+
 #[derive(Clone)]
 pub struct Settings {
     invitation_input_symbol: String,
     pub(crate) mode: Mode,
+    pub(crate) glob: GlobOptions,
+    pub(crate) utils_dir: Option<PathBuf>,
 }
 
 impl Settings {
@@ -35,7 +65,7 @@ impl Settings {
     pub fn get_invitation_input(&self) -> String {
         let str_mode = String::from(self.mode);
         let mut output = String::new();
-        if str_mode.len() > 0 {
+        if !str_mode.is_empty() {
             output.push_str(format!("\x1b[0;1;31m({}) ", str_mode).as_str());
         }
         output.push_str(
@@ -49,6 +79,10 @@ impl Settings {
         );
         output
     }
+
+    pub fn get_continuation_input(&self) -> String {
+        format!("\x1b[0;1;37m{}\x1b[0m", ".".repeat(3))
+    }
 }
 
 impl Default for Settings {
@@ -56,6 +90,21 @@ impl Default for Settings {
         Self {
             invitation_input_symbol: ">".to_string(),
             mode: Default::default(),
+            glob: Default::default(),
+            utils_dir: default_utils_dir(),
         }
     }
 }
+
+fn default_utils_dir() -> Option<PathBuf> {
+    if let Some(dir) = option_env!("PSEUDOBASH_UTILS") {
+        return Some(PathBuf::from(dir));
+    }
+    current_exe()
+        .ok()?
+        .parent()?
+        .join("../utils/release")
+        .canonicalize()
+        .ok()
+        .filter(|dir| dir.is_dir())
+}
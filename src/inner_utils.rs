@@ -1,10 +1,28 @@
 use {
-    crate::{global_state::GlobalState, program_output::ProgramOutput},
-    std::collections::HashMap,
+    crate::{
+        conditional,
+        global_state::{
+            Control, GlobalState, command_hash,
+            environment::{
+                Attributes, split_subscript,
+                value::{self, Value},
+            },
+        },
+        parser::program_builder::program::is_keyword,
+        printf::format,
+        program_output::ProgramOutput,
+    },
+    std::{
+        collections::HashMap,
+        os::unix::fs::MetadataExt,
+        path::{Component, Path, PathBuf},
+    },
 };
 
+type InnerUtil = fn(args: Vec<String>, gs: &mut GlobalState) -> ProgramOutput;
+
 pub struct InnerUtils {
-    utils: HashMap<String, fn(args: Vec<String>, gs: &mut GlobalState) -> ProgramOutput>,
+    utils: HashMap<String, InnerUtil>,
 }
 
 impl Default for InnerUtils {
@@ -14,14 +32,30 @@ impl Default for InnerUtils {
         //     "mode".to_string(),
         //     mode as fn(Vec<String>, &mut GlobalState) -> ProgramOutput,
         // );
-        utils.insert(
-            "exit".to_string(),
-            exit as fn(Vec<String>, &mut GlobalState) -> ProgramOutput,
-        );
-        utils.insert(
-            "nop".to_string(),
-            nop as fn(Vec<String>, &mut GlobalState) -> ProgramOutput,
-        );
+        utils.insert(":".to_string(), nop as InnerUtil);
+        utils.insert("[".to_string(), test as InnerUtil);
+        utils.insert("alias".to_string(), alias as InnerUtil);
+        utils.insert("break".to_string(), loop_control as InnerUtil);
+        utils.insert("cd".to_string(), cd as InnerUtil);
+        utils.insert("command".to_string(), command as InnerUtil);
+        utils.insert("continue".to_string(), loop_control as InnerUtil);
+        utils.insert("declare".to_string(), declare as InnerUtil);
+        utils.insert("exit".to_string(), exit as InnerUtil);
+        utils.insert("export".to_string(), export as InnerUtil);
+        utils.insert("false".to_string(), fail as InnerUtil);
+        utils.insert("hash".to_string(), hash as InnerUtil);
+        utils.insert("local".to_string(), local as InnerUtil);
+        utils.insert("printf".to_string(), printf as InnerUtil);
+        utils.insert("pwd".to_string(), pwd as InnerUtil);
+        utils.insert("readonly".to_string(), readonly as InnerUtil);
+        utils.insert("return".to_string(), return_from as InnerUtil);
+        utils.insert("set".to_string(), set as InnerUtil);
+        utils.insert("shopt".to_string(), shopt as InnerUtil);
+        utils.insert("test".to_string(), test as InnerUtil);
+        utils.insert("true".to_string(), nop as InnerUtil);
+        utils.insert("type".to_string(), type_of as InnerUtil);
+        utils.insert("unalias".to_string(), unalias as InnerUtil);
+        utils.insert("unset".to_string(), unset as InnerUtil);
 
         Self { utils }
     }
@@ -75,10 +109,843 @@ impl InnerUtils {
 //     ProgramOutput::new(0, vec![], vec![])
 // }
 
-fn exit(_args: Vec<String>, _gs: &mut GlobalState) -> ProgramOutput {
-    std::process::exit(0)
+fn alias(args: Vec<String>, gs: &mut GlobalState) -> ProgramOutput {
+    let mut names: Vec<String> = args.into_iter().skip(1).filter(|arg| arg != "-p").collect();
+    if names.is_empty() {
+        names = gs.aliases.keys().cloned().collect();
+        names.sort();
+    }
+
+    let mut code = 0;
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    for arg in names {
+        if let Some((name, value)) = arg.split_once('=') {
+            if is_alias_name(name) {
+                gs.aliases.insert(name.to_string(), value.to_string());
+            } else {
+                code = 1;
+                stderr.extend(format!("alias: `{}': invalid alias name\n", name).bytes());
+            }
+            continue;
+        }
+        match gs.aliases.get(&arg) {
+            Some(value) => {
+                stdout.extend(format!("alias {}={}\n", arg, single_quote(value)).bytes())
+            }
+            None => {
+                code = 1;
+                stderr.extend(format!("alias: {}: not found\n", arg).bytes());
+            }
+        }
+    }
+    ProgramOutput::new(code, stdout, stderr)
+}
+
+fn unalias(args: Vec<String>, gs: &mut GlobalState) -> ProgramOutput {
+    if args.len() < 2 {
+        return ProgramOutput::new(
+            2,
+            vec![],
+            b"unalias: usage: unalias [-a] name [name ...]\n".to_vec(),
+        );
+    }
+    if args[1] == "-a" {
+        gs.aliases.clear();
+        return ProgramOutput::new(0, vec![], vec![]);
+    }
+
+    let mut code = 0;
+    let mut stderr = Vec::new();
+    for name in &args[1..] {
+        if gs.aliases.remove(name).is_none() {
+            code = 1;
+            stderr.extend(format!("unalias: {}: not found\n", name).bytes());
+        }
+    }
+    ProgramOutput::new(code, vec![], stderr)
+}
+
+fn is_alias_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name
+            .chars()
+            .any(|char| char.is_whitespace() || "/$`'\"\\=;&|()<>".contains(char))
+}
+
+fn single_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+enum Found {
+    Alias(String),
+    Keyword,
+    Function,
+    Builtin,
+    File(PathBuf, bool),
+}
+
+fn find(name: &str, gs: &mut GlobalState, all: bool, path_only: bool) -> Vec<Found> {
+    let mut found = Vec::new();
+    if !path_only {
+        found.extend(gs.aliases.get(name).cloned().map(Found::Alias));
+        if is_keyword(name) {
+            found.push(Found::Keyword);
+        }
+        if gs.functions.contains_key(name) {
+            found.push(Found::Function);
+        }
+        if InnerUtils::default().is_inner(&name.to_string()) {
+            found.push(Found::Builtin);
+        }
+    }
+    if name.contains('/') {
+        if command_hash::is_executable(Path::new(name)) {
+            found.push(Found::File(PathBuf::from(name), false));
+        }
+    } else if let Some(hashed) = gs.commands.get(name, &gs.search_path()) {
+        found.push(Found::File(hashed.path.clone(), true));
+    }
+    if all || !found.iter().any(|found| matches!(found, Found::File(..))) {
+        for path in command_hash::search(name, &gs.search_path()) {
+            if !found
+                .iter()
+                .any(|found| matches!(found, Found::File(known, _) if *known == path))
+            {
+                found.push(Found::File(path, false));
+            }
+        }
+    }
+    if !all {
+        found.truncate(1);
+    }
+    found
+}
+
+fn describe(name: &str, found: &Found) -> String {
+    match found {
+        Found::Alias(value) => format!("{} is aliased to `{}'\n", name, value),
+        Found::Keyword => format!("{} is a shell keyword\n", name),
+        Found::Function => format!("{} is a function\n", name),
+        Found::Builtin => format!("{} is a shell builtin\n", name),
+        Found::File(path, true) => format!("{} is hashed ({})\n", name, path.display()),
+        Found::File(path, false) => format!("{} is {}\n", name, path.display()),
+    }
+}
+
+fn type_of(args: Vec<String>, gs: &mut GlobalState) -> ProgramOutput {
+    let (mut all, mut kind_only, mut path_only, mut force_path) = (false, false, false, false);
+    let mut names = Vec::new();
+    for arg in &args[1..] {
+        match arg.as_str() {
+            "-a" => all = true,
+            "-t" => kind_only = true,
+            "-p" => path_only = true,
+            "-P" => force_path = true,
+            option if option.starts_with('-') && names.is_empty() => {
+                return ProgramOutput::new(
+                    2,
+                    vec![],
+                    format!("type: {}: invalid option\n", option).into_bytes(),
+                );
+            }
+            _ => names.push(arg.as_str()),
+        }
+    }
+
+    let mut code = 0;
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    for name in names {
+        let found = find(name, gs, all, force_path);
+        if found.is_empty() {
+            code = 1;
+            if !kind_only && !path_only && !force_path {
+                stderr.extend(format!("type: {}: not found\n", name).bytes());
+            }
+        }
+        for found in &found {
+            let line = match found {
+                Found::File(..) if kind_only => "file\n".to_string(),
+                Found::File(path, _) if path_only || force_path => {
+                    format!("{}\n", path.display())
+                }
+                _ if path_only => continue,
+                Found::Alias(_) if kind_only => "alias\n".to_string(),
+                Found::Keyword if kind_only => "keyword\n".to_string(),
+                Found::Function if kind_only => "function\n".to_string(),
+                Found::Builtin if kind_only => "builtin\n".to_string(),
+                found => describe(name, found),
+            };
+            stdout.extend(line.bytes());
+        }
+    }
+    ProgramOutput::new(code, stdout, stderr)
+}
+
+fn command(args: Vec<String>, gs: &mut GlobalState) -> ProgramOutput {
+    let verbose = match args.get(1).map(|arg| arg.as_str()) {
+        None => return ProgramOutput::new(0, vec![], vec![]),
+        Some("-v") => false,
+        Some("-V") => true,
+        Some(option) => {
+            return ProgramOutput::new(
+                2,
+                vec![],
+                format!("command: {}: invalid option\n", option).into_bytes(),
+            );
+        }
+    };
+
+    let mut code = 0;
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    for name in &args[2..] {
+        let Some(found) = find(name, gs, false, false).pop() else {
+            code = 1;
+            if verbose {
+                stderr.extend(format!("command: {}: not found\n", name).bytes());
+            }
+            continue;
+        };
+        let line = match found {
+            found if verbose => describe(name, &found),
+            Found::Alias(value) => format!("alias {}={}\n", name, single_quote(&value)),
+            Found::File(path, _) => format!("{}\n", path.display()),
+            _ => format!("{}\n", name),
+        };
+        stdout.extend(line.bytes());
+    }
+    ProgramOutput::new(code, stdout, stderr)
+}
+
+fn hash(args: Vec<String>, gs: &mut GlobalState) -> ProgramOutput {
+    let mut code = 0;
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    let (option, names) = match args.get(1).map(|arg| arg.as_str()) {
+        Some(option @ ("-r" | "-d" | "-t")) => (Some(option), &args[2..]),
+        Some(option) if option.starts_with('-') => {
+            return ProgramOutput::new(
+                2,
+                vec![],
+                format!("hash: {}: invalid option\n", option).into_bytes(),
+            );
+        }
+        _ => (None, &args[1..]),
+    };
+
+    if option == Some("-r") {
+        gs.commands.clear();
+    }
+    if names.is_empty() && option.is_none() {
+        let entries: Vec<String> = gs
+            .commands
+            .entries(&gs.search_path())
+            .map(|(_, hashed)| format!("{:4}\t{}\n", hashed.hits, hashed.path.display()))
+            .collect();
+        if entries.is_empty() {
+            return ProgramOutput::new(0, b"hash: hash table empty\n".to_vec(), vec![]);
+        }
+        stdout.extend(b"hits\tcommand\n");
+        stdout.extend(entries.concat().bytes());
+    }
+    for name in names {
+        let found = match option {
+            Some("-d") => gs.commands.remove(name),
+            Some("-t") => match gs.commands.get(name, &gs.search_path()) {
+                Some(hashed) => {
+                    stdout.extend(format!("{}\n", hashed.path.display()).bytes());
+                    true
+                }
+                None => false,
+            },
+            _ => name.contains('/') || gs.commands.resolve(name, &gs.search_path()).is_some(),
+        };
+        if !found {
+            code = 1;
+            stderr.extend(format!("hash: {}: not found\n", name).bytes());
+        }
+    }
+    ProgramOutput::new(code, stdout, stderr)
+}
+
+fn cd(args: Vec<String>, gs: &mut GlobalState) -> ProgramOutput {
+    let mut physical = false;
+    let mut operands = Vec::new();
+    for arg in &args[1..] {
+        match arg.as_str() {
+            "-P" => physical = true,
+            "-L" => physical = false,
+            _ => operands.push(arg),
+        }
+    }
+    if operands.len() > 1 {
+        return ProgramOutput::new(1, vec![], b"cd: too many arguments\n".to_vec());
+    }
+
+    let mut stdout = Vec::new();
+    let target = match operands.first().map(|arg| arg.as_str()) {
+        None => gs.environment.get("HOME").cloned(),
+        Some("-") => gs.environment.get("OLDPWD").cloned().inspect(|target| {
+            stdout.extend(format!("{}\n", target).bytes());
+        }),
+        Some(target) => Some(target.to_string()),
+    };
+    let Some(target) = target else {
+        let name = if operands.is_empty() {
+            "HOME"
+        } else {
+            "OLDPWD"
+        };
+        return ProgramOutput::new(1, vec![], format!("cd: {} not set\n", name).into_bytes());
+    };
+
+    let old_pwd = gs
+        .environment
+        .get("PWD")
+        .map(PathBuf::from)
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_default();
+    let mut new_pwd = normalize(&old_pwd.join(&target));
+    if physical {
+        new_pwd = match new_pwd.canonicalize() {
+            Ok(path) => path,
+            Err(e) => {
+                return ProgramOutput::new(
+                    1,
+                    vec![],
+                    format!("cd: {}: {}\n", target, e).into_bytes(),
+                );
+            }
+        };
+    }
+    if let Err(e) = std::env::set_current_dir(&new_pwd) {
+        return ProgramOutput::new(1, vec![], format!("cd: {}: {}\n", target, e).into_bytes());
+    }
+
+    if let Err(e) = gs
+        .environment
+        .insert("OLDPWD", &old_pwd.to_string_lossy())
+        .and_then(|_| gs.environment.insert("PWD", &new_pwd.to_string_lossy()))
+    {
+        return ProgramOutput::new(1, stdout, format!("cd: {}\n", e).into_bytes());
+    }
+    ProgramOutput::new(0, stdout, vec![])
+}
+
+fn pwd(args: Vec<String>, gs: &mut GlobalState) -> ProgramOutput {
+    let mut physical = false;
+    for arg in &args[1..] {
+        match arg.as_str() {
+            "-P" => physical = true,
+            "-L" => physical = false,
+            option if option.starts_with('-') && option != "-" => {
+                return ProgramOutput::new(
+                    2,
+                    vec![],
+                    format!("pwd: {}: invalid option\n", option).into_bytes(),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    let logical = gs
+        .environment
+        .get("PWD")
+        .map(PathBuf::from)
+        .filter(|path| !physical && is_current_dir(path));
+    match logical.map(Ok).unwrap_or_else(std::env::current_dir) {
+        Ok(path) => ProgramOutput::new(0, format!("{}\n", path.display()).into_bytes(), vec![]),
+        Err(e) => ProgramOutput::new(
+            1,
+            vec![],
+            format!("pwd: error retrieving current directory: {}\n", e).into_bytes(),
+        ),
+    }
+}
+
+fn is_current_dir(path: &Path) -> bool {
+    if !path.is_absolute()
+        || path
+            .components()
+            .any(|component| matches!(component, Component::CurDir | Component::ParentDir))
+    {
+        return false;
+    }
+    match (std::fs::metadata(path), std::fs::metadata(".")) {
+        (Ok(path), Ok(current)) => path.dev() == current.dev() && path.ino() == current.ino(),
+        _ => false,
+    }
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    let mut output = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                output.pop();
+            }
+            component => output.push(component),
+        }
+    }
+    output
+}
+
+fn exit(args: Vec<String>, gs: &mut GlobalState) -> ProgramOutput {
+    let code = match args.get(1).map(|arg| arg.parse::<i64>()) {
+        None => gs.environment.status(),
+        Some(Ok(code)) => (code & 0xff) as i32,
+        Some(Err(_)) => {
+            return ProgramOutput::new(
+                2,
+                vec![],
+                format!("exit: {}: numeric argument required\n", args[1]).into_bytes(),
+            );
+        }
+    };
+    if !gs.subshell {
+        std::process::exit(code)
+    }
+    gs.control = Some(Control::Exit(code));
+    ProgramOutput::new(0, vec![], vec![])
+}
+
+fn loop_control(args: Vec<String>, gs: &mut GlobalState) -> ProgramOutput {
+    let count = match args.get(1).map(|arg| arg.parse::<usize>()) {
+        None => 1,
+        Some(Ok(count)) if count > 0 => count,
+        Some(_) => {
+            return ProgramOutput::new(
+                1,
+                vec![],
+                format!("{}: {}: loop count out of range\n", args[0], args[1]).into_bytes(),
+            );
+        }
+    };
+    if gs.loop_depth > 0 {
+        let count = count.min(gs.loop_depth);
+        gs.control = Some(match args[0].as_str() {
+            "break" => Control::Break(count),
+            _ => Control::Continue(count),
+        });
+    }
+    ProgramOutput::new(0, vec![], vec![])
+}
+
+fn return_from(args: Vec<String>, gs: &mut GlobalState) -> ProgramOutput {
+    if !gs.environment.in_function() {
+        return ProgramOutput::new(
+            2,
+            vec![],
+            "return: can only `return' from a function\n"
+                .as_bytes()
+                .to_vec(),
+        );
+    }
+    let code = match args.get(1).map(|arg| arg.parse::<i64>()) {
+        None => gs.environment.status(),
+        Some(Ok(code)) => (code & 0xff) as i32,
+        Some(Err(_)) => {
+            return ProgramOutput::new(
+                2,
+                vec![],
+                format!("return: {}: numeric argument required\n", args[1]).into_bytes(),
+            );
+        }
+    };
+    gs.control = Some(Control::Return);
+    ProgramOutput::new(code, vec![], vec![])
+}
+
+fn test(mut args: Vec<String>, gs: &mut GlobalState) -> ProgramOutput {
+    if args[0] == "[" && args.pop_if(|arg| *arg == "]").is_none() {
+        return ProgramOutput::new(2, vec![], b"[: missing `]'\n".to_vec());
+    }
+    let util = args.remove(0);
+    match conditional::test(args, gs) {
+        Ok(result) => ProgramOutput::new(!result as i32, vec![], vec![]),
+        Err(e) => ProgramOutput::new(2, vec![], format!("{}: {}\n", util, e).into_bytes()),
+    }
+}
+
+fn printf(args: Vec<String>, gs: &mut GlobalState) -> ProgramOutput {
+    let mut args = &args[1..];
+    let mut variable = None;
+    if args.first().is_some_and(|arg| arg == "-v") {
+        match args.get(1) {
+            Some(name) => variable = Some(name.as_str()),
+            None => {
+                return ProgramOutput::new(
+                    2,
+                    vec![],
+                    b"printf: -v: option requires an argument\n".to_vec(),
+                );
+            }
+        }
+        args = &args[2..];
+    }
+    if args.first().is_some_and(|arg| arg == "--") {
+        args = &args[1..];
+    }
+    let Some((format_text, args)) = args.split_first() else {
+        return ProgramOutput::new(
+            2,
+            vec![],
+            b"printf: usage: printf [-v var] format [arguments]\n".to_vec(),
+        );
+    };
+
+    let formatted = format(format_text, args);
+    let mut code = !formatted.errors.is_empty() as i32;
+    let mut stderr: Vec<u8> = formatted
+        .errors
+        .iter()
+        .flat_map(|error| format!("printf: {}\n", error).into_bytes())
+        .collect();
+    let Some(name) = variable else {
+        return ProgramOutput::new(code, formatted.output, stderr);
+    };
+    let value = String::from_utf8_lossy(&formatted.output);
+    if let Err(e) = gs.environment.insert(name, &value) {
+        code = 1;
+        stderr.extend(format!("printf: {}\n", e).into_bytes());
+    }
+    ProgramOutput::new(code, vec![], stderr)
 }
 
 fn nop(_args: Vec<String>, _gs: &mut GlobalState) -> ProgramOutput {
     ProgramOutput::new(0, vec![], vec![])
 }
+
+fn fail(_args: Vec<String>, _gs: &mut GlobalState) -> ProgramOutput {
+    ProgramOutput::new(1, vec![], vec![])
+}
+
+fn set(args: Vec<String>, gs: &mut GlobalState) -> ProgramOutput {
+    match args.get(1).map(|arg| arg.as_str()) {
+        None => {
+            let mut vars: Vec<String> = gs
+                .environment
+                .vars()
+                .filter_map(|(name, variable)| {
+                    let value = match variable.value.as_ref()? {
+                        Value::Scalar(value) => value.clone(),
+                        value => value.serialize(),
+                    };
+                    Some(format!("{}={}\n", name, value))
+                })
+                .collect();
+            vars.sort();
+            ProgramOutput::new(0, vars.concat().into_bytes(), vec![])
+        }
+        Some("--") => {
+            gs.environment.set_positional(args[2..].to_vec());
+            ProgramOutput::new(0, vec![], vec![])
+        }
+        Some(option) if option.starts_with('-') || option.starts_with('+') => ProgramOutput::new(
+            2,
+            vec![],
+            format!("set: {}: invalid option\n", option).into_bytes(),
+        ),
+        Some(_) => {
+            gs.environment.set_positional(args[1..].to_vec());
+            ProgramOutput::new(0, vec![], vec![])
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+struct Declaration {
+    set: Attributes,
+    unset: Attributes,
+    array: Option<char>,
+    local: bool,
+}
+
+fn declare(args: Vec<String>, gs: &mut GlobalState) -> ProgramOutput {
+    declare_with(args, gs, Declaration::default())
+}
+
+fn local(args: Vec<String>, gs: &mut GlobalState) -> ProgramOutput {
+    if !gs.environment.in_function() {
+        return ProgramOutput::new(
+            1,
+            vec![],
+            "local: can only be used in a function\n"
+                .as_bytes()
+                .to_vec(),
+        );
+    }
+    if args.len() == 1 {
+        let names = gs.environment.locals();
+        return print_named_declarations(gs, "local", &names);
+    }
+    declare_with(
+        args,
+        gs,
+        Declaration {
+            local: true,
+            ..Default::default()
+        },
+    )
+}
+
+fn declare_with(
+    args: Vec<String>,
+    gs: &mut GlobalState,
+    mut declaration: Declaration,
+) -> ProgramOutput {
+    let util = args[0].as_str();
+    let mut print = false;
+    let mut operands = Vec::new();
+    for arg in &args[1..] {
+        let (attributes, flags) = match arg.as_bytes().first() {
+            Some(b'-') if operands.is_empty() && arg.len() > 1 => (&mut declaration.set, &arg[1..]),
+            Some(b'+') if operands.is_empty() && arg.len() > 1 => {
+                (&mut declaration.unset, &arg[1..])
+            }
+            _ => {
+                operands.push(arg.as_str());
+                continue;
+            }
+        };
+        for flag in flags.chars() {
+            match flag {
+                'p' => print = true,
+                'x' => attributes.exported = true,
+                'r' => attributes.readonly = true,
+                'i' => attributes.integer = true,
+                'a' | 'A' => declaration.array = Some(flag),
+                _ => {
+                    return ProgramOutput::new(
+                        2,
+                        vec![],
+                        format!("{}: -{}: invalid option\n", util, flag).into_bytes(),
+                    );
+                }
+            }
+        }
+    }
+
+    if operands.is_empty() {
+        return print_declarations(gs, declaration);
+    }
+    if print {
+        return print_named_declarations(gs, util, &operands);
+    }
+    apply_declarations(gs, util, &operands, declaration)
+}
+
+fn export(args: Vec<String>, gs: &mut GlobalState) -> ProgramOutput {
+    let (unexport, operands) = match args.get(1).map(|arg| arg.as_str()) {
+        Some("-n") => (true, &args[2..]),
+        Some("-p") => (false, &args[2..]),
+        _ => (false, &args[1..]),
+    };
+    let exported = Attributes {
+        exported: true,
+        ..Default::default()
+    };
+    let declaration = if unexport {
+        Declaration {
+            unset: exported,
+            ..Default::default()
+        }
+    } else {
+        Declaration {
+            set: exported,
+            ..Default::default()
+        }
+    };
+    if operands.is_empty() {
+        return print_declarations(gs, declaration);
+    }
+
+    let operands: Vec<&str> = operands.iter().map(|arg| arg.as_str()).collect();
+    apply_declarations(gs, "export", &operands, declaration)
+}
+
+fn readonly(args: Vec<String>, gs: &mut GlobalState) -> ProgramOutput {
+    let operands = match args.get(1).map(|arg| arg.as_str()) {
+        Some("-p") => &args[2..],
+        _ => &args[1..],
+    };
+    let declaration = Declaration {
+        set: Attributes {
+            readonly: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    if operands.is_empty() {
+        return print_declarations(gs, declaration);
+    }
+
+    let operands: Vec<&str> = operands.iter().map(|arg| arg.as_str()).collect();
+    apply_declarations(gs, "readonly", &operands, declaration)
+}
+
+fn unset(args: Vec<String>, gs: &mut GlobalState) -> ProgramOutput {
+    let names = match args.get(1).map(|arg| arg.as_str()) {
+        Some("-f") => {
+            for name in &args[2..] {
+                gs.functions.remove(name);
+            }
+            return ProgramOutput::new(0, vec![], vec![]);
+        }
+        Some("-v") => &args[2..],
+        _ => &args[1..],
+    };
+
+    let mut stderr = Vec::new();
+    for name in names {
+        if let Err(e) = gs.environment.remove(name) {
+            stderr.extend(format!("unset: {}\n", e).bytes());
+        }
+    }
+    ProgramOutput::new(!stderr.is_empty() as i32, vec![], stderr)
+}
+
+fn apply_declarations(
+    gs: &mut GlobalState,
+    util: &str,
+    operands: &[&str],
+    declaration: Declaration,
+) -> ProgramOutput {
+    let Declaration {
+        set,
+        unset,
+        array,
+        local,
+    } = declaration;
+    let mut stderr = Vec::new();
+    for operand in operands {
+        let (name, value) = match operand.split_once('=') {
+            Some((name, value)) if value::is_compound(value) => {
+                (name.to_string(), Some(value.to_string()))
+            }
+            Some((name, value)) => {
+                let (name, value) = gs.environment.resolve_append(name, value);
+                (name, Some(value))
+            }
+            None => (operand.to_string(), None),
+        };
+        let name = name.as_str();
+        let base = split_subscript(name.strip_suffix('+').unwrap_or(name)).0;
+        let result = match local {
+            true => gs.environment.make_local(base),
+            false => Ok(()),
+        };
+        let result = result.and_then(|_| gs.environment.attributes_mut(base));
+        let result = result.and_then(|attributes| {
+            if attributes.readonly && (unset.readonly || value.is_some()) {
+                anyhow::bail!("{}: readonly variable", base)
+            }
+            attributes.exported = (attributes.exported || set.exported) && !unset.exported;
+            attributes.integer = (attributes.integer || set.integer) && !unset.integer;
+            Ok(())
+        });
+        let result = result.and_then(|_| match array {
+            Some(flag) => gs.environment.declare_array(base, flag == 'A'),
+            None => Ok(()),
+        });
+        let result = result.and_then(|_| match value {
+            Some(value) if value::is_compound(&value) => {
+                gs.environment.insert_compound(name, &value)
+            }
+            Some(value) => gs.environment.insert(name, &value),
+            None => Ok(()),
+        });
+        let result = result.and_then(|_| gs.environment.attributes_mut(base));
+        match result {
+            Ok(attributes) => attributes.readonly |= set.readonly,
+            Err(e) => stderr.extend(format!("{}: {}\n", util, e).bytes()),
+        }
+    }
+    ProgramOutput::new(!stderr.is_empty() as i32, vec![], stderr)
+}
+
+fn print_declarations(gs: &GlobalState, filter: Declaration) -> ProgramOutput {
+    let Declaration { set, array, .. } = filter;
+    let mut lines: Vec<String> = gs
+        .environment
+        .vars()
+        .filter(|(_, variable)| {
+            (!set.exported || variable.attributes.exported)
+                && (!set.readonly || variable.attributes.readonly)
+                && (!set.integer || variable.attributes.integer)
+                && (array.is_none()
+                    || array == variable.value.as_ref().and_then(|value| value.flag()))
+        })
+        .map(|(name, _)| declaration(gs, name))
+        .collect();
+    lines.sort();
+    ProgramOutput::new(0, lines.concat().into_bytes(), vec![])
+}
+
+fn print_named_declarations(gs: &GlobalState, util: &str, names: &[&str]) -> ProgramOutput {
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    for name in names {
+        if gs.environment.variable(name).is_some() {
+            stdout.extend(declaration(gs, name).bytes());
+        } else {
+            stderr.extend(format!("{}: {}: not found\n", util, name).bytes());
+        }
+    }
+    ProgramOutput::new(!stderr.is_empty() as i32, stdout, stderr)
+}
+
+fn declaration(gs: &GlobalState, name: &str) -> String {
+    let Some(variable) = gs.environment.variable(name) else {
+        return String::new();
+    };
+    match &variable.value {
+        Some(value) => format!(
+            "declare -{} {}={}\n",
+            variable.flags(),
+            name,
+            value.serialize()
+        ),
+        None => format!("declare -{} {}\n", variable.flags(), name),
+    }
+}
+
+fn shopt(args: Vec<String>, gs: &mut GlobalState) -> ProgramOutput {
+    let (value, names) = match args.get(1).map(|arg| arg.as_str()) {
+        Some("-s") => (Some(true), &args[2..]),
+        Some("-u") => (Some(false), &args[2..]),
+        _ => (None, &args[1..]),
+    };
+
+    let mut code = 0;
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    let options = gs.settings.glob.list();
+    for (name, state) in options {
+        if names.is_empty() && value.is_none() {
+            stdout.extend(format!("{}\t{}\n", name, if state { "on" } else { "off" }).bytes());
+        }
+    }
+    for name in names {
+        match (gs.settings.glob.get_mut(name), value) {
+            (Some(option), Some(value)) => *option = value,
+            (Some(option), None) => {
+                stdout
+                    .extend(format!("{}\t{}\n", name, if *option { "on" } else { "off" }).bytes());
+                if !*option {
+                    code = 1;
+                }
+            }
+            (None, _) => {
+                code = 1;
+                stderr.extend(format!("shopt: {}: invalid shell option name\n", name).bytes());
+            }
+        }
+    }
+
+    ProgramOutput::new(code, stdout, stderr)
+}
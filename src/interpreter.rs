@@ -0,0 +1,324 @@
+use crate::{
+    arithmetic,
+    conditional::{self, Operand},
+    global_state::{Control, GlobalState},
+    inner_utils::InnerUtils,
+    parser::{
+        command_builder::command::{Command, Connector},
+        program_builder::program::Executed,
+    },
+    program_output::ProgramOutput,
+};
+
+pub type Sink<'a> = dyn FnMut(anyhow::Result<ProgramOutput>, bool) + 'a;
+
+pub struct Interpreter<'a> {
+    gs: &'a mut GlobalState,
+    iu: &'a InnerUtils,
+    sink: &'a mut Sink<'a>,
+}
+
+impl<'a> Interpreter<'a> {
+    pub fn new(gs: &'a mut GlobalState, iu: &'a InnerUtils, sink: &'a mut Sink<'a>) -> Self {
+        Self { gs, iu, sink }
+    }
+
+    pub fn run(&mut self, command: Command) -> i32 {
+        self.execute(command, false)
+    }
+
+    fn execute(&mut self, command: Command, checked: bool) -> i32 {
+        let status = match command {
+            Command::Simple(program) => match program.execute(self.gs, self.iu, self.sink) {
+                Ok(Executed::Call(status)) => status,
+                Ok(Executed::Output(output)) => {
+                    let status = output.code;
+                    (self.sink)(Ok(output), checked);
+                    status
+                }
+                Err(e) => {
+                    let status = error_status(&e);
+                    (self.sink)(Err(e), checked);
+                    status
+                }
+            },
+            Command::Arithmetic(text) => {
+                self.evaluate(&text).map_or(1, |value| (value == 0) as i32)
+            }
+            Command::Conditional(args) => {
+                let operands = args
+                    .into_iter()
+                    .map(|arg| {
+                        let operator = arg.literal().is_some();
+                        Operand::new(arg.into_word_with_executing(self.gs), operator)
+                    })
+                    .collect();
+                match conditional::evaluate(operands, self.gs) {
+                    Ok(result) => !result as i32,
+                    Err(e) => {
+                        (self.sink)(Err(e), checked);
+                        2
+                    }
+                }
+            }
+            Command::AndOr(first, rest) => {
+                let last = rest.len();
+                let mut status = self.execute(*first, checked || last > 0);
+                for (idx, (connector, command)) in rest.into_iter().enumerate() {
+                    if self.gs.control.is_some() {
+                        break;
+                    }
+                    let run = match connector {
+                        Connector::And => status == 0,
+                        Connector::Or => status != 0,
+                    };
+                    if run {
+                        status = self.execute(command, checked || idx + 1 < last);
+                    }
+                }
+                status
+            }
+            Command::Negated(command) => (self.execute(*command, true) == 0) as i32,
+            Command::Group(commands) => self.execute_list(commands, checked),
+            Command::Subshell(commands) => self.execute_subshell(commands, checked),
+            Command::If {
+                branches,
+                otherwise,
+            } => self.execute_if(branches, otherwise, checked),
+            Command::Loop {
+                until,
+                condition,
+                body,
+            } => {
+                self.gs.loop_depth += 1;
+                let mut status = 0;
+                loop {
+                    let passed = self.execute_list(condition.clone(), true) == 0;
+                    if self.take_control() || passed == until {
+                        break;
+                    }
+                    status = self.execute_list(body.clone(), checked);
+                    if self.take_control() {
+                        break;
+                    }
+                }
+                self.gs.loop_depth -= 1;
+                status
+            }
+            Command::For { name, words, body } => {
+                let values = match words {
+                    Some(words) => words
+                        .into_iter()
+                        .flat_map(|word| word.into_fields_with_executing(self.gs))
+                        .collect(),
+                    None => self.gs.environment.positional().to_vec(),
+                };
+                self.gs.loop_depth += 1;
+                let mut status = 0;
+                for value in values {
+                    if let Err(e) = self.gs.environment.insert(&name, &value) {
+                        (self.sink)(Err(e), checked);
+                        status = 1;
+                        break;
+                    }
+                    status = self.execute_list(body.clone(), checked);
+                    if self.take_control() {
+                        break;
+                    }
+                }
+                self.gs.loop_depth -= 1;
+                status
+            }
+            Command::ArithmeticFor {
+                init,
+                condition,
+                step,
+                body,
+            } => self.execute_arithmetic_for(&init, &condition, &step, body, checked),
+            Command::Case { word, items } => {
+                let word = word.into_word_with_executing(self.gs).into_string();
+                let body = items.into_iter().find_map(|(patterns, body)| {
+                    patterns
+                        .into_iter()
+                        .any(|pattern| {
+                            pattern
+                                .into_word_with_executing(self.gs)
+                                .to_pattern()
+                                .matches(&word)
+                        })
+                        .then_some(body)
+                });
+                body.map_or(0, |body| self.execute_list(body, checked))
+            }
+            Command::Function { name, body } => {
+                self.gs.functions.insert(name, *body);
+                0
+            }
+        };
+        self.gs.environment.set_status(status);
+        status
+    }
+
+    fn execute_list(&mut self, commands: Vec<Command>, checked: bool) -> i32 {
+        let mut status = 0;
+        for command in commands {
+            if self.gs.control.is_some() {
+                break;
+            }
+            status = self.execute(command, checked);
+        }
+        status
+    }
+
+    fn execute_subshell(&mut self, commands: Vec<Command>, checked: bool) -> i32 {
+        let mut state = self.gs.clone();
+        state.subshell = true;
+        let parent = std::mem::replace(self.gs, state);
+        let cwd = std::env::current_dir();
+
+        let mut status = self.execute_list(commands, checked);
+        if let Some(Control::Exit(code)) = self.gs.control {
+            status = code;
+        }
+
+        *self.gs = parent;
+        if let Ok(cwd) = cwd {
+            let _ = std::env::set_current_dir(cwd);
+        }
+        status
+    }
+
+    fn execute_if(
+        &mut self,
+        branches: Vec<(Vec<Command>, Vec<Command>)>,
+        otherwise: Option<Vec<Command>>,
+        checked: bool,
+    ) -> i32 {
+        for (condition, body) in branches {
+            let passed = self.execute_list(condition, true) == 0;
+            if self.gs.control.is_some() {
+                return 0;
+            }
+            if passed {
+                return self.execute_list(body, checked);
+            }
+        }
+        otherwise.map_or(0, |body| self.execute_list(body, checked))
+    }
+
+    fn execute_arithmetic_for(
+        &mut self,
+        init: &str,
+        condition: &str,
+        step: &str,
+        body: Vec<Command>,
+        checked: bool,
+    ) -> i32 {
+        if self.evaluate(init).is_none() {
+            return 1;
+        }
+        self.gs.loop_depth += 1;
+        let mut status = 0;
+        loop {
+            let passed = match condition.trim() {
+                "" => true,
+                condition => match self.evaluate(condition) {
+                    Some(value) => value != 0,
+                    None => {
+                        status = 1;
+                        break;
+                    }
+                },
+            };
+            if !passed {
+                break;
+            }
+            status = self.execute_list(body.clone(), checked);
+            if self.take_control() {
+                break;
+            }
+            if self.evaluate(step).is_none() {
+                status = 1;
+                break;
+            }
+        }
+        self.gs.loop_depth -= 1;
+        status
+    }
+
+    fn evaluate(&mut self, expression: &str) -> Option<i64> {
+        if expression.trim().is_empty() {
+            return Some(0);
+        }
+        match arithmetic::evaluate(expression, &mut self.gs.environment) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                (self.sink)(Err(e), false);
+                None
+            }
+        }
+    }
+
+    fn take_control(&mut self) -> bool {
+        match self.gs.control.take() {
+            None => false,
+            Some(Control::Break(count)) => {
+                if count > 1 {
+                    self.gs.control = Some(Control::Break(count - 1));
+                }
+                true
+            }
+            Some(Control::Continue(count)) => {
+                if count > 1 {
+                    self.gs.control = Some(Control::Continue(count - 1));
+                }
+                count > 1
+            }
+            Some(control @ (Control::Return | Control::Exit(_))) => {
+                self.gs.control = Some(control);
+                true
+            }
+        }
+    }
+}
+
+// Output of the commands inside the function goes to the caller's sink as
+// they run, so only the status is returned.
+pub fn call(
+    body: Command,
+    args: Vec<String>,
+    input: Option<Vec<u8>>,
+    gs: &mut GlobalState,
+    iu: &InnerUtils,
+    sink: &mut Sink<'_>,
+) -> i32 {
+    let positional = gs.environment.positional().to_vec();
+    let loop_depth = std::mem::take(&mut gs.loop_depth);
+    let outer_input = input.map(|input| gs.input.replace(input));
+    gs.environment
+        .set_positional(args.into_iter().skip(1).collect());
+    gs.environment.push_scope();
+
+    let code = Interpreter::new(gs, iu, sink).run(body);
+    if gs.control == Some(Control::Return) {
+        gs.control = None;
+    }
+
+    gs.environment.pop_scope();
+    gs.environment.set_positional(positional);
+    gs.loop_depth = loop_depth;
+    if let Some(outer_input) = outer_input {
+        gs.input = outer_input;
+    }
+    code
+}
+
+// As in bash, a command that is not found exits with 127 and one that is found
+// but cannot be executed with 126.
+fn error_status(error: &anyhow::Error) -> i32 {
+    match error.downcast_ref::<std::io::Error>().map(|e| e.kind()) {
+        Some(std::io::ErrorKind::NotFound) => 127,
+        Some(std::io::ErrorKind::PermissionDenied | std::io::ErrorKind::IsADirectory) => 126,
+        _ => 1,
+    }
+}
@@ -1,7 +1,13 @@
 pub mod cli;
 
+mod arithmetic;
+mod conditional;
+mod expansion;
 mod global_state;
 mod inner_utils;
+mod interpreter;
 mod listener;
 mod parser;
+mod printf;
 mod program_output;
+mod regex;
@@ -1,9 +1,15 @@
-use crate::parser::{
-    context::Context,
-    program_builder::{ProgramBuilder, program::Program},
+use {
+    crate::parser::{
+        arg_builder::ArgBuilderState,
+        command_builder::{CommandBuilder, command::Command},
+        context::Context,
+        program_builder::{ProgramBuilder, program::Program},
+    },
+    std::collections::HashMap,
 };
 
 pub mod arg_builder;
+pub mod command_builder;
 pub mod program_builder;
 
 mod context;
@@ -12,57 +18,199 @@ mod token;
 #[derive(Debug, Default, PartialEq)]
 pub struct Parser {
     program_builder: ProgramBuilder,
+    command_builder: CommandBuilder,
     context: Context,
+    held_programs: Vec<Program>,
+    here_doc_line: Option<Vec<u8>>,
+    aliases: HashMap<String, String>,
+    expanding: Vec<String>,
+    alias_follows: bool,
 }
 
 impl Parser {
-    pub fn apply(&mut self, byte: u8) -> anyhow::Result<Option<Program>> {
-        self.program_builder
-            .apply(byte, &mut self.context)
-            .map_err(|e| {
+    pub fn apply(&mut self, byte: u8) -> anyhow::Result<Vec<Command>> {
+        self.apply_byte(byte)
+            .and_then(|programs| self.push_programs(programs))
+            .inspect_err(|_| {
                 std::mem::take(self);
-                e
             })
     }
 
-    pub fn finish(&mut self) -> anyhow::Result<Option<Program>> {
-        self.program_builder.finish(&mut self.context).map_err(|e| {
+    pub fn finish(&mut self) -> anyhow::Result<Vec<Command>> {
+        self.finish_programs().inspect_err(|_| {
             std::mem::take(self);
-            e
         })
     }
+
+    pub fn set_aliases(&mut self, aliases: HashMap<String, String>) {
+        self.aliases = aliases;
+    }
+
+    pub fn is_waiting(&self) -> bool {
+        !self.held_programs.is_empty()
+            || self.program_builder.is_open()
+            || self.command_builder.is_open()
+    }
+
+    fn finish_programs(&mut self) -> anyhow::Result<Vec<Command>> {
+        if !self.held_programs.is_empty() {
+            anyhow::bail!("Syntax error: here-document delimited by end of input")
+        }
+        let program = self.program_builder.finish(&mut self.context)?;
+        let mut commands = self.push_programs(program.into_iter().collect())?;
+        commands.extend(self.command_builder.finish()?);
+        Ok(commands)
+    }
+
+    fn push_programs(&mut self, programs: Vec<Program>) -> anyhow::Result<Vec<Command>> {
+        let mut commands = Vec::new();
+        for program in programs {
+            commands.extend(self.command_builder.push(program)?);
+        }
+        Ok(commands)
+    }
+
+    fn apply_byte(&mut self, byte: u8) -> anyhow::Result<Vec<Program>> {
+        if let Some(line) = self.here_doc_line.as_mut() {
+            line.push(byte);
+            if byte == b'\n' {
+                return self.apply_here_doc_line();
+            }
+            return Ok(vec![]);
+        }
+
+        let line_end = byte == b'\n' && self.context.arg_builder_state == ArgBuilderState::Default;
+        let mut output = self.expand_alias(byte)?;
+        if let Some(mut program) = self.program_builder.apply(byte, &mut self.context)? {
+            if self.held_programs.is_empty() && program.pending_here_doc().is_none() {
+                output.push(program);
+            } else {
+                self.held_programs.push(program);
+            }
+        }
+        if line_end && !self.held_programs.is_empty() {
+            self.here_doc_line = Some(vec![]);
+        }
+        Ok(output)
+    }
+
+    fn expand_alias(&mut self, byte: u8) -> anyhow::Result<Vec<Program>> {
+        if self.context.arg_builder_state != ArgBuilderState::Default
+            || !matches!(
+                byte,
+                b' ' | b'\t' | b'\n' | b';' | b'&' | b'|' | b'(' | b')' | b'<' | b'>'
+            )
+        {
+            return Ok(vec![]);
+        }
+        let Some((word, command_position)) = self.program_builder.pending_word() else {
+            return Ok(vec![]);
+        };
+        let word = String::from_utf8_lossy(word).to_string();
+        let follows = std::mem::take(&mut self.alias_follows);
+        let value = match self.aliases.get(&word) {
+            Some(value) if (command_position || follows) && !self.expanding.contains(&word) => {
+                value.clone()
+            }
+            _ => return Ok(vec![]),
+        };
+
+        self.program_builder.discard_word(&mut self.context);
+        self.expanding.push(word);
+        let mut output = Vec::new();
+        for byte in value.bytes().chain([b' ']) {
+            output.extend(self.apply_byte(byte)?);
+        }
+        self.expanding.pop();
+        self.alias_follows = value.ends_with([' ', '\t']);
+        Ok(output)
+    }
+
+    fn apply_here_doc_line(&mut self) -> anyhow::Result<Vec<Program>> {
+        let line = self.here_doc_line.replace(vec![]).unwrap_or_default();
+        if let Some(redirection) = self
+            .held_programs
+            .iter_mut()
+            .find_map(|program| program.pending_here_doc())
+        {
+            redirection.apply_line(&line)?;
+        }
+        if self
+            .held_programs
+            .iter_mut()
+            .all(|program| program.pending_here_doc().is_none())
+        {
+            self.here_doc_line = None;
+            Ok(std::mem::take(&mut self.held_programs))
+        } else {
+            Ok(vec![])
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
     use crate::parser::{
-        Parser, arg_builder::arg::Arg, program_builder::program::Program, token::Token,
+        Parser,
+        arg_builder::arg::Arg,
+        command_builder::command::{Command, Connector},
+        program_builder::program::{Delimiter, Program},
+        token::Token,
     };
 
     #[test]
     fn check_program_builder_apply() {
         let mut parser = Parser::default();
 
-        let mut result: Vec<Program> = "echo 100"
+        let mut result: Vec<Command> = "echo 100"
             .as_bytes()
-            .into_iter()
-            .filter_map(|byte| parser.apply(*byte).unwrap())
+            .iter()
+            .flat_map(|byte| parser.apply(*byte).unwrap())
             .collect();
-        parser.finish().unwrap().map(|arg| result.push(arg));
+        result.extend(parser.finish().unwrap());
 
         assert_eq!(
             result,
-            vec![Program::new(vec![
+            vec![Command::Simple(Program::new(vec![
                 Arg::new_default(vec![Token::new_default("echo")]),
                 Arg::new_default(vec![Token::new_default("100")])
-            ]),]
+            ])),]
+        );
+        assert_eq!(parser, Parser::default());
+
+        let mut result: Vec<Command> = "true &&\nwhile x; do :; done"
+            .as_bytes()
+            .iter()
+            .flat_map(|byte| parser.apply(*byte).unwrap())
+            .collect();
+        assert!(parser.is_waiting());
+        result.extend(parser.finish().unwrap());
+
+        let mut first = Program::new(vec![Arg::new_default(vec![Token::new_default("true")])]);
+        first.set_delimiter(Delimiter::And);
+        let condition = Program::new(vec![Arg::new_default(vec![Token::new_default("x")])]);
+        assert_eq!(
+            result,
+            vec![Command::AndOr(
+                Box::new(Command::Simple(first)),
+                vec![(
+                    Connector::And,
+                    Command::Loop {
+                        until: false,
+                        condition: vec![Command::Simple(condition)],
+                        body: vec![Command::Simple(Program::new(vec![Arg::new_default(vec![
+                            Token::new_default(":")
+                        ])]))],
+                    }
+                )]
+            )]
         );
         assert_eq!(parser, Parser::default());
 
-        let _result: Vec<Program> = "echo '100"
+        let _result: Vec<Command> = "echo '100"
             .as_bytes()
-            .into_iter()
-            .filter_map(|byte| parser.apply(*byte).unwrap())
+            .iter()
+            .flat_map(|byte| parser.apply(*byte).unwrap())
             .collect();
         assert!(parser.finish().is_err());
         assert_eq!(parser, Parser::default());
@@ -15,75 +15,167 @@ pub enum ArgBuilderState {
 pub struct ArgBuilder {
     current_arg: Arg,
     current_token: Token,
+    compound: Option<(Arg, Vec<Arg>)>,
 }
 
 impl ArgBuilder {
     pub fn apply(&mut self, byte: u8, context: &mut Context) -> anyhow::Result<Option<Arg>> {
+        if self.current_token.is_open() {
+            if matches!(byte, b'\'' | b'"' | b' ' | b'\t' | b'\n' | b'\0') {
+                anyhow::bail!("Bad substitution")
+            }
+            return self.apply_to_token(byte, context);
+        }
+        if context.arg_builder_state == ArgBuilderState::Default {
+            match byte {
+                b'(' if self.compound.is_none()
+                    && self.current_arg.is_empty()
+                    && self.current_token.is_assignment_prefix()
+                    && self.current_token.ends_with(b'=') =>
+                {
+                    self.finish_token(context);
+                    self.compound = Some((std::mem::take(&mut self.current_arg), vec![]));
+                    return Ok(None);
+                }
+                b' ' | b'\t' | b'\n' | b'\0' if self.compound.is_some() => {
+                    self.finish_element(context);
+                    return Ok(None);
+                }
+                b')' if self.compound.is_some() => {
+                    self.finish_element(context);
+                    if let Some((mut arg, elements)) = self.compound.take() {
+                        arg.set_elements(elements);
+                        self.current_arg = arg;
+                    }
+                    return Ok(None);
+                }
+                _ => {}
+            }
+        }
+
         match byte {
             b'\'' => match context.arg_builder_state {
                 ArgBuilderState::Default => {
+                    self.finish_token(context);
                     context.arg_builder_state = ArgBuilderState::StrongSep;
                     return Ok(None);
                 }
                 ArgBuilderState::WeakSep => {}
                 ArgBuilderState::StrongSep => {
-                    context.arg_builder_state = ArgBuilderState::Default;
-                    self.current_token
-                        .finish(context)
-                        .map(|token| self.current_arg.push(token));
-                    return Ok(self.return_if_not_empty(context));
+                    self.finish_quote(context);
+                    return Ok(None);
                 }
             },
             b'"' => match context.arg_builder_state {
                 ArgBuilderState::Default => {
+                    self.finish_token(context);
                     context.arg_builder_state = ArgBuilderState::WeakSep;
                     return Ok(None);
                 }
                 ArgBuilderState::WeakSep => {
-                    context.arg_builder_state = ArgBuilderState::Default;
-                    self.current_token
-                        .finish(context)
-                        .map(|token| self.current_arg.push(token));
-                    return Ok(self.return_if_not_empty(context));
+                    self.finish_quote(context);
+                    return Ok(None);
                 }
                 ArgBuilderState::StrongSep => {}
             },
+            b' ' | b'\t' | b'\n' | b'\0' => match context.arg_builder_state {
+                ArgBuilderState::Default => {
+                    self.finish_token(context);
+                    return Ok(self.return_if_not_empty(context));
+                }
+                ArgBuilderState::WeakSep | ArgBuilderState::StrongSep => {}
+            },
             _ => {}
         }
 
-        match self.current_token.apply(byte, context)? {
-            Some(token) => {
-                self.current_arg.push(token);
-                if !context.token_in_process {
-                    Ok(self.return_if_not_empty(context))
-                } else {
-                    Ok(None)
-                }
-            }
-            None => Ok(None),
-        }
+        self.apply_to_token(byte, context)
     }
 
     pub fn finish(&mut self, context: &mut Context) -> anyhow::Result<Option<Arg>> {
+        if self.current_token.is_open() {
+            std::mem::take(self);
+            anyhow::bail!("Bad substitution")
+        }
+        if self.is_open() {
+            std::mem::take(self);
+            anyhow::bail!("Syntax error: unclosed compound assignment")
+        }
         match context.arg_builder_state {
             ArgBuilderState::Default => {
-                self.current_token
-                    .finish(context)
-                    .map(|token| self.current_arg.push(token));
+                self.finish_token(context);
                 Ok(self.return_if_not_empty(context))
             }
             ArgBuilderState::WeakSep | ArgBuilderState::StrongSep => anyhow::bail!("Syntax error"),
         }
     }
 
+    pub fn is_open(&self) -> bool {
+        self.compound.is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.current_arg.is_empty() && self.current_token == Token::default()
+    }
+
+    pub fn is_plain_word(&self) -> bool {
+        self.plain_word().is_some()
+    }
+
+    pub fn plain_word(&self) -> Option<&[u8]> {
+        if !self.current_arg.is_empty() {
+            return None;
+        }
+        self.current_token
+            .literal()
+            .filter(|word| !word.is_empty() && !word.contains(&b'='))
+    }
+
+    pub fn push_literal(&mut self, byte: u8, context: &mut Context) {
+        if self.current_token.literal().is_none() {
+            self.finish_token(context);
+        }
+        self.current_token.push_literal(byte, context);
+    }
+
+    fn apply_to_token(&mut self, byte: u8, context: &mut Context) -> anyhow::Result<Option<Arg>> {
+        if let Some(token) = self.current_token.apply(byte, context)? {
+            self.current_arg.push(token);
+        }
+        Ok(None)
+    }
+
+    fn finish_element(&mut self, context: &mut Context) {
+        self.finish_token(context);
+        let element = std::mem::take(&mut self.current_arg);
+        if let Some((_, elements)) = self.compound.as_mut()
+            && !element.is_empty()
+        {
+            elements.push(element);
+        }
+    }
+
+    fn finish_token(&mut self, context: &mut Context) {
+        if let Some(token) = self.current_token.finish(context) {
+            self.current_arg.push(token);
+        }
+    }
+
+    fn finish_quote(&mut self, context: &mut Context) {
+        if self.current_token == Token::default() {
+            self.current_arg.push(Token::raw(vec![]));
+        }
+        self.finish_token(context);
+        context.arg_builder_state = ArgBuilderState::Default;
+    }
+
     fn return_if_not_empty(&mut self, context: &mut Context) -> Option<Arg> {
         if self.current_arg.is_empty() {
             None
         } else {
-            if context.current_arg_is_setter {
+            if context.current_arg_is_setter && self.current_arg.has_assignment_prefix() {
                 self.current_arg.set_setter();
-                context.current_arg_is_setter = false;
             }
+            context.current_arg_is_setter = false;
             Some(std::mem::take(self).current_arg)
         }
     }
@@ -104,13 +196,10 @@ mod test {
 
         let mut result: Vec<Arg> = "echo 100"
             .as_bytes()
-            .into_iter()
+            .iter()
             .filter_map(|byte| arg_builder.apply(*byte, &mut context).unwrap())
             .collect();
-        arg_builder
-            .finish(&mut context)
-            .unwrap()
-            .map(|arg| result.push(arg));
+        result.extend(arg_builder.finish(&mut context).unwrap());
 
         assert_eq!(
             result,
@@ -124,13 +213,10 @@ mod test {
 
         let mut result: Vec<Arg> = "x=100 echo 100"
             .as_bytes()
-            .into_iter()
+            .iter()
             .filter_map(|byte| arg_builder.apply(*byte, &mut context).unwrap())
             .collect();
-        arg_builder
-            .finish(&mut context)
-            .unwrap()
-            .map(|arg| result.push(arg));
+        result.extend(arg_builder.finish(&mut context).unwrap());
 
         assert_eq!(
             result,
@@ -145,19 +231,16 @@ mod test {
 
         let mut result: Vec<Arg> = "\"x=100 $x\""
             .as_bytes()
-            .into_iter()
+            .iter()
             .filter_map(|byte| arg_builder.apply(*byte, &mut context).unwrap())
             .collect();
-        arg_builder
-            .finish(&mut context)
-            .unwrap()
-            .map(|arg| result.push(arg));
+        result.extend(arg_builder.finish(&mut context).unwrap());
 
         assert_eq!(
             result,
             vec![Arg::new_default(vec![
-                Token::new_default("x=100 "),
-                Token::new_var_getter("x")
+                Token::new_quoted("x=100 "),
+                Token::new_quoted_var_getter("x")
             ]),]
         );
         assert_eq!(arg_builder, ArgBuilder::default());
@@ -165,18 +248,15 @@ mod test {
 
         let mut result: Vec<Arg> = "'x=100\"' $x"
             .as_bytes()
-            .into_iter()
+            .iter()
             .filter_map(|byte| arg_builder.apply(*byte, &mut context).unwrap())
             .collect();
-        arg_builder
-            .finish(&mut context)
-            .unwrap()
-            .map(|arg| result.push(arg));
+        result.extend(arg_builder.finish(&mut context).unwrap());
 
         assert_eq!(
             result,
             vec![
-                Arg::new_default(vec![Token::new_default("x=100\"")]),
+                Arg::new_default(vec![Token::new_quoted("x=100\"")]),
                 Arg::new_default(vec![Token::new_var_getter("x")])
             ]
         );
@@ -185,7 +265,7 @@ mod test {
 
         let _result: Vec<Arg> = "echo 'x=100\" $x"
             .as_bytes()
-            .into_iter()
+            .iter()
             .filter_map(|byte| arg_builder.apply(*byte, &mut context).unwrap())
             .collect();
         assert!(arg_builder.finish(&mut context).is_err());
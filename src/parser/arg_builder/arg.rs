@@ -1,4 +1,11 @@
-use crate::{global_state::GlobalState, parser::token::Token};
+use crate::{
+    expansion::{Origin, Word, brace, glob, split},
+    global_state::{
+        GlobalState,
+        environment::{is_name, split_subscript, value::quote},
+    },
+    parser::token::Token,
+};
 
 #[derive(Default, Debug, PartialEq, Eq, Clone, Copy)]
 enum ArgType {
@@ -6,15 +13,33 @@ enum ArgType {
     Default,
 
     VarSetter,
+    Arithmetic,
 }
 
-#[derive(Default, Debug, PartialEq, Eq)]
+#[derive(Default, Debug, PartialEq, Eq, Clone)]
 pub struct Arg {
     data: Vec<Token>,
     kind: ArgType,
+    elements: Option<Vec<Arg>>,
 }
 
 impl Arg {
+    pub fn arithmetic(text: Vec<u8>) -> Self {
+        Self {
+            data: vec![Token::raw(text)],
+            kind: ArgType::Arithmetic,
+            elements: None,
+        }
+    }
+
+    pub fn operator(operator: Vec<u8>) -> Self {
+        Self {
+            data: vec![Token::word(operator)],
+            kind: ArgType::Default,
+            elements: None,
+        }
+    }
+
     pub fn push(&mut self, token: Token) {
         self.data.push(token);
     }
@@ -27,23 +52,200 @@ impl Arg {
         self.kind = ArgType::VarSetter
     }
 
-    pub fn into_string_with_executing(self, gs: &mut GlobalState) -> String {
-        let arg = &self
+    pub fn unset_setter(&mut self) {
+        self.kind = ArgType::Default
+    }
+
+    pub fn is_setter(&self) -> bool {
+        self.kind == ArgType::VarSetter
+    }
+
+    pub fn has_assignment_prefix(&self) -> bool {
+        let mut target = Vec::new();
+        for token in &self.data {
+            match token.literal() {
+                Some(literal) => match literal.iter().position(|byte| *byte == b'=') {
+                    Some(eq_idx) => {
+                        target.extend_from_slice(&literal[..eq_idx]);
+                        let target = String::from_utf8_lossy(&target);
+                        let target = target.strip_suffix('+').unwrap_or(&target);
+                        return is_name(split_subscript(target).0);
+                    }
+                    None => target.extend_from_slice(literal),
+                },
+                None if target.contains(&b'[') => target.push(b'0'),
+                None => return false,
+            }
+        }
+        false
+    }
+
+    pub fn set_elements(&mut self, elements: Vec<Arg>) {
+        self.elements = Some(elements);
+    }
+
+    pub fn arithmetic_text(&self) -> Option<String> {
+        match (self.kind, self.data.as_slice()) {
+            (ArgType::Arithmetic, [token]) => {
+                Some(String::from_utf8_lossy(&token.clone().into_raw()).to_string())
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_compound(&self) -> bool {
+        self.elements.is_some()
+    }
+
+    pub fn literal(&self) -> Option<&[u8]> {
+        match self.data.as_slice() {
+            [token] => token.literal(),
+            _ => None,
+        }
+    }
+
+    pub fn is_quoted(&self) -> bool {
+        self.data
+            .iter()
+            .any(|token| token.origin() == Origin::Quoted)
+    }
+
+    pub fn into_raw(self) -> Vec<u8> {
+        self.data
+            .into_iter()
+            .flat_map(|token| token.into_raw())
+            .collect()
+    }
+
+    pub fn into_bytes_with_executing(self, gs: &mut GlobalState) -> Vec<u8> {
+        self.data
+            .into_iter()
+            .flat_map(|token| token.expand(gs).join(b" ".as_slice()))
+            .collect()
+    }
+
+    pub fn expand_braces(self) -> Vec<Arg> {
+        if self.kind == ArgType::VarSetter || self.is_compound() {
+            return vec![self];
+        }
+        let pieces = self
             .data
             .into_iter()
-            .map(|mut token| {
-                token.to_default(gs);
-                token.downgrade()
+            .flat_map(|token| token.into_pieces())
+            .collect();
+        brace::expand(pieces)
+            .into_iter()
+            .map(|pieces| Self {
+                data: Token::from_pieces(pieces),
+                kind: self.kind,
+                elements: None,
             })
-            .flatten()
-            .collect::<Vec<u8>>();
-        if self.kind == ArgType::VarSetter {
-            gs.environment.set_var(arg.to_vec());
-            String::new()
-        } else {
-            String::from_utf8_lossy(arg).to_string()
+            .collect()
+    }
+
+    pub fn into_word_with_executing(mut self, gs: &mut GlobalState) -> Word {
+        let whole = self.data.len() == 1;
+        if let Some(token) = self.data.first_mut() {
+            token.expand_tilde(whole, false, &gs.environment);
+        }
+
+        let mut word = Word::default();
+        for token in self.data {
+            let origin = token.origin();
+            let fields = token.expand(gs);
+            if fields.is_empty() {
+                word.set_vanished();
+            }
+            for (idx, field) in fields.into_iter().enumerate() {
+                if idx > 0 {
+                    word.push_break(origin == Origin::Quoted);
+                }
+                word.push(field, origin);
+            }
         }
+        word
+    }
+
+    pub fn into_fields_with_executing(self, gs: &mut GlobalState) -> Vec<String> {
+        let words: Vec<Word> = self
+            .expand_braces()
+            .into_iter()
+            .map(|arg| arg.into_word_with_executing(gs))
+            .filter(|word| !word.is_null())
+            .collect();
+
+        let ifs = gs.environment.ifs();
+        let cwd = std::env::current_dir().unwrap_or_default();
+        words
+            .into_iter()
+            .flat_map(|word| split::split(word, &ifs))
+            .flat_map(|word| glob::expand(word, &gs.settings.glob, &cwd))
+            .collect()
     }
+
+    pub fn into_assignment_with_executing(mut self, gs: &mut GlobalState) -> (String, String) {
+        let elements = self.elements.take();
+        let whole = self.data.len() == 1;
+        if let Some(token) = self.data.first_mut() {
+            token.expand_tilde(whole, true, &gs.environment);
+        }
+
+        let assignment = String::from_utf8_lossy(&self.into_bytes_with_executing(gs)).to_string();
+        let Some((name, value)) = split_assignment(&assignment) else {
+            return (assignment, String::new());
+        };
+        match elements {
+            Some(elements) => (name.to_string(), Self::into_compound(elements, gs)),
+            None => gs.environment.resolve_append(name, value),
+        }
+    }
+
+    fn into_compound(elements: Vec<Arg>, gs: &mut GlobalState) -> String {
+        let mut items = Vec::new();
+        for element in elements {
+            if element.is_keyed() {
+                let item =
+                    String::from_utf8_lossy(&element.into_bytes_with_executing(gs)).to_string();
+                if let Some((key, value)) = item
+                    .strip_prefix('[')
+                    .and_then(|item| item.split_once("]="))
+                {
+                    items.push(format!("[{}]={}", key, quote(value)));
+                }
+                continue;
+            }
+            items.extend(
+                element
+                    .into_fields_with_executing(gs)
+                    .iter()
+                    .map(|field| quote(field)),
+            );
+        }
+        format!("({})", items.join(" "))
+    }
+
+    fn is_keyed(&self) -> bool {
+        self.data
+            .first()
+            .and_then(|token| token.literal())
+            .is_some_and(|literal| literal.starts_with(b"["))
+            && self.data.iter().any(|token| {
+                token
+                    .literal()
+                    .is_some_and(|literal| literal.windows(2).any(|pair| pair == b"]="))
+            })
+    }
+}
+
+fn split_assignment(assignment: &str) -> Option<(&str, &str)> {
+    let eq_idx = match (assignment.find('['), assignment.find('=')) {
+        (Some(open), Some(eq_idx)) if open < eq_idx => {
+            let close = open + assignment[open..].find(']')?;
+            close + 1 + assignment[close + 1..].find('=')?
+        }
+        (_, eq_idx) => eq_idx?,
+    };
+    Some((&assignment[..eq_idx], &assignment[eq_idx + 1..]))
 }
 
 #[cfg(test)]
@@ -58,6 +260,7 @@ mod test {
             Self {
                 data: tokens,
                 kind: ArgType::Default,
+                elements: None,
             }
         }
 
@@ -65,6 +268,7 @@ mod test {
             Self {
                 data: tokens,
                 kind: ArgType::VarSetter,
+                elements: None,
             }
         }
     }
@@ -0,0 +1,532 @@
+pub mod command;
+
+use crate::{
+    global_state::environment::is_name,
+    parser::{
+        arg_builder::arg::Arg,
+        command_builder::command::{Command, Connector},
+        program_builder::program::{Delimiter, Program},
+    },
+};
+
+#[derive(Default, Debug, PartialEq, Eq)]
+struct List {
+    commands: Vec<Command>,
+    chain: Option<Connector>,
+    negated: bool,
+}
+
+impl List {
+    fn push(&mut self, mut command: Command) {
+        if std::mem::take(&mut self.negated) {
+            command = Command::Negated(Box::new(command));
+        }
+        let Some(connector) = self.chain.take() else {
+            self.commands.push(command);
+            return;
+        };
+        match self.commands.pop() {
+            Some(Command::AndOr(first, mut rest)) => {
+                rest.push((connector, command));
+                self.commands.push(Command::AndOr(first, rest));
+            }
+            Some(previous) => self.commands.push(Command::AndOr(
+                Box::new(previous),
+                vec![(connector, command)],
+            )),
+            None => self.commands.push(command),
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        !self.commands.is_empty() && self.chain.is_none() && !self.negated
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum IfPart {
+    Condition,
+    Body(Vec<Command>),
+    Otherwise,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Header {
+    For {
+        name: String,
+        words: Option<Vec<Arg>>,
+    },
+    ArithmeticFor {
+        init: String,
+        condition: String,
+        step: String,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum FrameKind {
+    Group,
+    Subshell,
+    If {
+        branches: Vec<(Vec<Command>, Vec<Command>)>,
+        part: IfPart,
+    },
+    Loop {
+        until: bool,
+        condition: Option<Vec<Command>>,
+    },
+    For {
+        header: Header,
+        started: bool,
+    },
+    Case {
+        word: Arg,
+        items: Vec<(Vec<Arg>, Vec<Command>)>,
+        patterns: Vec<Arg>,
+        in_body: bool,
+    },
+    Function {
+        name: String,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Frame {
+    parent: List,
+    kind: FrameKind,
+}
+
+#[derive(Default, Debug, PartialEq, Eq)]
+pub struct CommandBuilder {
+    frames: Vec<Frame>,
+    list: List,
+}
+
+impl CommandBuilder {
+    pub fn push(&mut self, program: Program) -> anyhow::Result<Vec<Command>> {
+        let closed = program.delimiter() == Delimiter::Close;
+        self.apply(program).inspect_err(|_| {
+            std::mem::take(self);
+        })?;
+        if self.is_open() || closed {
+            Ok(vec![])
+        } else {
+            Ok(std::mem::take(&mut self.list.commands))
+        }
+    }
+
+    pub fn finish(&mut self) -> anyhow::Result<Vec<Command>> {
+        if self.is_open() {
+            std::mem::take(self);
+            anyhow::bail!("Syntax error: unexpected end of file")
+        }
+        Ok(std::mem::take(&mut self.list.commands))
+    }
+
+    pub fn is_open(&self) -> bool {
+        !self.frames.is_empty() || self.list.chain.is_some()
+    }
+
+    fn apply(&mut self, mut program: Program) -> anyhow::Result<()> {
+        let delimiter = program.delimiter();
+        if let Some(Frame {
+            kind: FrameKind::Case { in_body: false, .. },
+            ..
+        }) = self.frames.last()
+        {
+            return self.apply_pattern(program);
+        }
+        if let Some(Frame {
+            kind: FrameKind::For { started: false, .. },
+            ..
+        }) = self.frames.last()
+            && !program.is_empty()
+            && program.reserved_word() != Some("do")
+        {
+            anyhow::bail!("Syntax error: `do' expected")
+        }
+
+        if delimiter == Delimiter::FunctionHeader && program.reserved_word() != Some("function") {
+            let mut args = program.into_args();
+            let name = args.pop().and_then(|arg| Self::function_name(&arg));
+            match (name, args.is_empty()) {
+                (Some(name), true) => self.open(FrameKind::Function { name }),
+                _ => anyhow::bail!("Syntax error near unexpected token `('"),
+            }
+            return Ok(());
+        }
+
+        while let Some(word) = program.reserved_word() {
+            program.take_first();
+            match word {
+                "function" => {
+                    let Some(name) = program
+                        .take_first()
+                        .and_then(|arg| Self::function_name(&arg))
+                    else {
+                        anyhow::bail!("Syntax error: `function' expects a name")
+                    };
+                    self.open(FrameKind::Function { name });
+                }
+                "if" => self.open(FrameKind::If {
+                    branches: vec![],
+                    part: IfPart::Condition,
+                }),
+                "while" | "until" => self.open(FrameKind::Loop {
+                    until: word == "until",
+                    condition: None,
+                }),
+                "!" => self.list.negated = !self.list.negated,
+                "{" => self.open(FrameKind::Group),
+                "then" | "elif" | "else" | "do" => self.transition(word)?,
+                "for" => {
+                    let header = Self::for_header(program)?;
+                    self.open(FrameKind::For {
+                        header,
+                        started: false,
+                    });
+                    return Self::expect_sequence(word, delimiter);
+                }
+                "case" => {
+                    let mut args = program.into_args();
+                    if args.len() != 2 {
+                        anyhow::bail!("Syntax error near `case'")
+                    }
+                    args.pop();
+                    let word = args.pop().unwrap_or_default();
+                    self.open(FrameKind::Case {
+                        word,
+                        items: vec![],
+                        patterns: vec![],
+                        in_body: false,
+                    });
+                    return Ok(());
+                }
+                "fi" | "done" | "esac" | "}" => {
+                    if !program.is_empty() {
+                        anyhow::bail!("Syntax error near unexpected token after `{}'", word)
+                    }
+                    let command = self.close(word)?;
+                    return self.push_command(command, delimiter);
+                }
+                _ => anyhow::bail!("Syntax error near unexpected token `{}'", word),
+            }
+        }
+
+        if program.is_empty() {
+            if self.list.negated && delimiter != Delimiter::Open {
+                anyhow::bail!("Syntax error: `!' expects a command")
+            }
+            return match delimiter {
+                Delimiter::Sequence | Delimiter::FunctionHeader => Ok(()),
+                Delimiter::CaseEnd => self.end_case_item(),
+                Delimiter::Open => {
+                    self.open(FrameKind::Subshell);
+                    Ok(())
+                }
+                Delimiter::Close => self.close_subshell(),
+                Delimiter::And | Delimiter::Or if self.list.is_complete() => {
+                    self.push_delimiter(delimiter)
+                }
+                Delimiter::And | Delimiter::Or | Delimiter::Pipe => {
+                    anyhow::bail!("Syntax error: unexpected operator")
+                }
+            };
+        }
+        let command = match program.arithmetic() {
+            Some(text) => Command::Arithmetic(text),
+            None if program.is_conditional() => Command::Conditional(program.into_conditional()?),
+            None => Command::Simple(program),
+        };
+        self.push_command(command, delimiter)
+    }
+
+    fn apply_pattern(&mut self, program: Program) -> anyhow::Result<()> {
+        let delimiter = program.delimiter();
+        if program.reserved_word() == Some("esac") {
+            let command = self.close("esac")?;
+            return self.push_command(command, delimiter);
+        }
+        if program.is_empty() && matches!(delimiter, Delimiter::Sequence | Delimiter::Open) {
+            return Ok(());
+        }
+
+        let mut args = program.into_args();
+        let Some(Frame {
+            kind: FrameKind::Case {
+                patterns, in_body, ..
+            },
+            ..
+        }) = self.frames.last_mut()
+        else {
+            return Ok(());
+        };
+        match (args.pop(), args.is_empty(), delimiter) {
+            (Some(pattern), true, Delimiter::Pipe) => patterns.push(pattern),
+            (Some(pattern), true, Delimiter::Close) => {
+                patterns.push(pattern);
+                *in_body = true;
+            }
+            _ => anyhow::bail!("Syntax error near unexpected token in case pattern"),
+        }
+        Ok(())
+    }
+
+    fn push_command(&mut self, mut command: Command, delimiter: Delimiter) -> anyhow::Result<()> {
+        if let Some(Frame {
+            kind: FrameKind::Function { name },
+            ..
+        }) = self.frames.last_mut()
+        {
+            if !command.is_compound() {
+                anyhow::bail!(
+                    "Syntax error: `{}': function body must be a compound command",
+                    name
+                )
+            }
+            command = Command::Function {
+                name: std::mem::take(name),
+                body: Box::new(command),
+            };
+            if let Some(frame) = self.frames.pop() {
+                self.list = frame.parent;
+            }
+        }
+        self.list.push(command);
+        self.push_delimiter(delimiter)
+    }
+
+    fn push_delimiter(&mut self, delimiter: Delimiter) -> anyhow::Result<()> {
+        match delimiter {
+            Delimiter::Sequence => {}
+            Delimiter::And => self.list.chain = Some(Connector::And),
+            Delimiter::Or => self.list.chain = Some(Connector::Or),
+            Delimiter::CaseEnd => self.end_case_item()?,
+            Delimiter::Close => self.close_subshell()?,
+            Delimiter::Pipe => anyhow::bail!("Pipelines are not supported"),
+            Delimiter::Open | Delimiter::FunctionHeader => {
+                anyhow::bail!("Syntax error near unexpected token `('")
+            }
+        }
+        Ok(())
+    }
+
+    fn close_subshell(&mut self) -> anyhow::Result<()> {
+        match self.frames.last() {
+            Some(Frame {
+                kind: FrameKind::Subshell,
+                ..
+            }) if self.list.is_complete() => {}
+            _ => anyhow::bail!("Syntax error near unexpected token `)'"),
+        }
+        let commands = std::mem::take(&mut self.list).commands;
+        if let Some(frame) = self.frames.pop() {
+            self.list = frame.parent;
+        }
+        self.push_command(Command::Subshell(commands), Delimiter::Sequence)
+    }
+
+    fn open(&mut self, kind: FrameKind) {
+        let parent = std::mem::take(&mut self.list);
+        self.frames.push(Frame { parent, kind });
+    }
+
+    fn transition(&mut self, word: &str) -> anyhow::Result<()> {
+        let list = &mut self.list;
+        let Some(frame) = self.frames.last_mut() else {
+            anyhow::bail!("Syntax error near unexpected token `{}'", word)
+        };
+        match (&mut frame.kind, word) {
+            (FrameKind::If { part, .. }, "then") if *part == IfPart::Condition => {
+                *part = IfPart::Body(Self::take_list(list, word)?);
+            }
+            (FrameKind::If { branches, part }, "elif" | "else")
+                if matches!(part, IfPart::Body(_)) =>
+            {
+                let body = Self::take_list(list, word)?;
+                if let IfPart::Body(condition) = std::mem::replace(part, IfPart::Condition) {
+                    branches.push((condition, body));
+                }
+                if word == "else" {
+                    *part = IfPart::Otherwise;
+                }
+            }
+            (
+                FrameKind::Loop {
+                    condition: condition @ None,
+                    ..
+                },
+                "do",
+            ) => *condition = Some(Self::take_list(list, word)?),
+            (FrameKind::For { started, .. }, "do") if !*started => *started = true,
+            _ => anyhow::bail!("Syntax error near unexpected token `{}'", word),
+        }
+        Ok(())
+    }
+
+    fn close(&mut self, word: &str) -> anyhow::Result<Command> {
+        let unexpected = || anyhow::anyhow!("Syntax error near unexpected token `{}'", word);
+        let Some(frame) = self.frames.pop() else {
+            return Err(unexpected());
+        };
+        let list = std::mem::replace(&mut self.list, frame.parent);
+        let complete = list.is_complete();
+        let commands = list.commands;
+        let command = match (frame.kind, word) {
+            (FrameKind::Group, "}") if complete => Command::Group(commands),
+            (
+                FrameKind::If {
+                    mut branches,
+                    part: IfPart::Body(condition),
+                },
+                "fi",
+            ) if complete => {
+                branches.push((condition, commands));
+                Command::If {
+                    branches,
+                    otherwise: None,
+                }
+            }
+            (
+                FrameKind::If {
+                    branches,
+                    part: IfPart::Otherwise,
+                },
+                "fi",
+            ) if complete => Command::If {
+                branches,
+                otherwise: Some(commands),
+            },
+            (
+                FrameKind::Loop {
+                    until,
+                    condition: Some(condition),
+                },
+                "done",
+            ) if complete => Command::Loop {
+                until,
+                condition,
+                body: commands,
+            },
+            (
+                FrameKind::For {
+                    header: Header::For { name, words },
+                    started: true,
+                },
+                "done",
+            ) if complete => Command::For {
+                name,
+                words,
+                body: commands,
+            },
+            (
+                FrameKind::For {
+                    header:
+                        Header::ArithmeticFor {
+                            init,
+                            condition,
+                            step,
+                        },
+                    started: true,
+                },
+                "done",
+            ) if complete => Command::ArithmeticFor {
+                init,
+                condition,
+                step,
+                body: commands,
+            },
+            (
+                FrameKind::Case {
+                    word,
+                    mut items,
+                    patterns,
+                    in_body,
+                },
+                "esac",
+            ) if list.chain.is_none() => {
+                if in_body {
+                    items.push((patterns, commands));
+                }
+                Command::Case { word, items }
+            }
+            _ => return Err(unexpected()),
+        };
+        Ok(command)
+    }
+
+    fn end_case_item(&mut self) -> anyhow::Result<()> {
+        let list = &mut self.list;
+        match self.frames.last_mut() {
+            Some(Frame {
+                kind:
+                    FrameKind::Case {
+                        items,
+                        patterns,
+                        in_body: in_body @ true,
+                        ..
+                    },
+                ..
+            }) if list.chain.is_none() => {
+                items.push((std::mem::take(patterns), std::mem::take(&mut list.commands)));
+                *in_body = false;
+                Ok(())
+            }
+            _ => anyhow::bail!("Syntax error near unexpected token `;;'"),
+        }
+    }
+
+    fn take_list(list: &mut List, word: &str) -> anyhow::Result<Vec<Command>> {
+        if !list.is_complete() {
+            anyhow::bail!("Syntax error near unexpected token `{}'", word)
+        }
+        Ok(std::mem::take(list).commands)
+    }
+
+    fn for_header(program: Program) -> anyhow::Result<Header> {
+        if let Some(text) = program.arithmetic() {
+            let parts: Vec<&str> = text.split(';').collect();
+            let [init, condition, step] = parts.as_slice() else {
+                anyhow::bail!(
+                    "Syntax error: `for (({}))': expected three expressions",
+                    text
+                )
+            };
+            return Ok(Header::ArithmeticFor {
+                init: init.to_string(),
+                condition: condition.to_string(),
+                step: step.to_string(),
+            });
+        }
+
+        let mut args = program.into_args().into_iter();
+        let name = args
+            .next()
+            .and_then(|arg| {
+                arg.literal()
+                    .map(|name| String::from_utf8_lossy(name).to_string())
+            })
+            .filter(|name| is_name(name));
+        let Some(name) = name else {
+            anyhow::bail!("Syntax error: `for' expects a variable name")
+        };
+        let words = match args.next() {
+            None => None,
+            Some(arg) if arg.literal() == Some(b"in") => Some(args.collect()),
+            Some(_) => anyhow::bail!("Syntax error: `in' expected after `for {}'", name),
+        };
+        Ok(Header::For { name, words })
+    }
+
+    fn function_name(arg: &Arg) -> Option<String> {
+        arg.literal()
+            .map(|name| String::from_utf8_lossy(name).to_string())
+    }
+
+    fn expect_sequence(word: &str, delimiter: Delimiter) -> anyhow::Result<()> {
+        if delimiter != Delimiter::Sequence {
+            anyhow::bail!("Syntax error near unexpected token after `{}'", word)
+        }
+        Ok(())
+    }
+}
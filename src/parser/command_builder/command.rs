@@ -0,0 +1,115 @@
+use crate::parser::{arg_builder::arg::Arg, program_builder::program::Program};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Connector {
+    And,
+    Or,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Command {
+    Simple(Program),
+    Arithmetic(String),
+    Conditional(Vec<Arg>),
+    AndOr(Box<Command>, Vec<(Connector, Command)>),
+    Negated(Box<Command>),
+    Group(Vec<Command>),
+    Subshell(Vec<Command>),
+    If {
+        branches: Vec<(Vec<Command>, Vec<Command>)>,
+        otherwise: Option<Vec<Command>>,
+    },
+    Loop {
+        until: bool,
+        condition: Vec<Command>,
+        body: Vec<Command>,
+    },
+    For {
+        name: String,
+        words: Option<Vec<Arg>>,
+        body: Vec<Command>,
+    },
+    ArithmeticFor {
+        init: String,
+        condition: String,
+        step: String,
+        body: Vec<Command>,
+    },
+    Case {
+        word: Arg,
+        items: Vec<(Vec<Arg>, Vec<Command>)>,
+    },
+    Function {
+        name: String,
+        body: Box<Command>,
+    },
+}
+
+impl Command {
+    pub fn is_compound(&self) -> bool {
+        matches!(
+            self,
+            Command::Conditional(_)
+                | Command::Group(_)
+                | Command::Subshell(_)
+                | Command::If { .. }
+                | Command::Loop { .. }
+                | Command::For { .. }
+                | Command::ArithmeticFor { .. }
+                | Command::Case { .. }
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        global_state::GlobalState,
+        inner_utils::InnerUtils,
+        interpreter::Interpreter,
+        parser::{command_builder::command::Command, program_builder::program::Executed},
+        program_output::ProgramOutput,
+    };
+
+    fn collect(output: &mut ProgramOutput) -> impl FnMut(anyhow::Result<ProgramOutput>, bool) + '_ {
+        |result: anyhow::Result<ProgramOutput>, _checked: bool| match result {
+            Ok(result) => {
+                output.stdout.extend(result.stdout);
+                output.stderr.extend(result.stderr);
+            }
+            Err(e) => output.stderr.extend(format!("{}\n", e).bytes()),
+        }
+    }
+
+    impl Command {
+        pub fn execute(
+            self,
+            gs: &mut GlobalState,
+            iu: &InnerUtils,
+        ) -> anyhow::Result<ProgramOutput> {
+            if let Command::Simple(program) = self {
+                let mut streamed = ProgramOutput::new(0, vec![], vec![]);
+                let output = program.execute(gs, iu, &mut collect(&mut streamed));
+                let output = output.map(|executed| match executed {
+                    Executed::Output(output) => {
+                        streamed.stdout.extend(output.stdout);
+                        streamed.stderr.extend(output.stderr);
+                        ProgramOutput::new(output.code, streamed.stdout, streamed.stderr)
+                    }
+                    Executed::Call(code) => {
+                        ProgramOutput::new(code, streamed.stdout, streamed.stderr)
+                    }
+                });
+                if let Ok(output) = output.as_ref() {
+                    gs.environment.set_status(output.code);
+                }
+                return output;
+            }
+
+            let mut output = ProgramOutput::new(0, vec![], vec![]);
+            let code = Interpreter::new(gs, iu, &mut collect(&mut output)).run(self);
+            output.code = code;
+            Ok(output)
+        }
+    }
+}
@@ -1,50 +1,279 @@
 pub mod program;
+pub mod redirection;
 
 use crate::parser::{
-    arg_builder::{ArgBuilder, ArgBuilderState},
+    arg_builder::{ArgBuilder, ArgBuilderState, arg::Arg},
     context::Context,
-    program_builder::program::Program,
+    program_builder::{
+        program::{Delimiter, Program},
+        redirection::{Redirection, RedirectionKind},
+    },
 };
 
 #[derive(Default, Debug, PartialEq, Eq)]
 pub struct ProgramBuilder {
     current_program: Program,
     arg_builder: ArgBuilder,
+    operator: Vec<u8>,
+    redirection_kind: Option<RedirectionKind>,
+    arithmetic: Option<Vec<u8>>,
+    closed: bool,
 }
 
 impl ProgramBuilder {
     pub fn apply(&mut self, byte: u8, context: &mut Context) -> anyhow::Result<Option<Program>> {
-        match byte {
-            // b'|' => {
-            //     context.current_arg_index = 0;
-            //     self.data.delimeter = Delimeter::Pipe;
-            //     return Ok(Some(std::mem::take(self).data));
-            // }
-            b';' => match context.arg_builder_state {
-                ArgBuilderState::Default => return self.finish(context),
-                ArgBuilderState::WeakSep | ArgBuilderState::StrongSep => {}
-            },
+        if self.arithmetic.is_some() {
+            let arg = self.apply_arithmetic(byte);
+            self.push_arg(arg);
+            return Ok(None);
+        }
+        if self.arg_builder.is_open() {
+            let arg = self.arg_builder.apply(byte, context)?;
+            self.push_arg(arg);
+            return Ok(None);
+        }
+        if context.arg_builder_state == ArgBuilderState::Default
+            && (matches!(byte, b'&' | b'|' | b'(' | b')' | b'<' | b'>')
+                || !self.operator.is_empty())
+            && self.current_program.is_conditional_open()
+        {
+            return self.apply_conditional(byte, context);
+        }
+        match (self.operator.as_slice(), byte) {
+            (b";", b';') => {
+                self.operator.clear();
+                return self.finish_with(context, Delimiter::CaseEnd);
+            }
+            (b"&", b'&') => {
+                self.operator.clear();
+                return self.finish_with(context, Delimiter::And);
+            }
+            (b"|", b'|') => {
+                self.operator.clear();
+                return self.finish_with(context, Delimiter::Or);
+            }
+            (b"(", b'(') => {
+                self.operator.clear();
+                self.arithmetic = Some(vec![]);
+                return Ok(None);
+            }
+            (b"(", b')') => {
+                self.operator.clear();
+                return self.finish_with(context, Delimiter::FunctionHeader);
+            }
+            (b";", _) => self.operator.clear(),
+            (b"|", _) => {
+                self.operator.clear();
+                let program = self.finish_with(context, Delimiter::Pipe)?;
+                if self.apply(byte, context)?.is_some() {
+                    std::mem::take(self);
+                    anyhow::bail!("Syntax error near unexpected token `{}'", byte as char)
+                }
+                return Ok(program);
+            }
+            (b"&", _) => {
+                std::mem::take(self);
+                anyhow::bail!("Syntax error: background jobs are not supported")
+            }
+            (b"(", _) if self.current_program.is_reserved_prefix() => {
+                self.operator.clear();
+                let program = self.finish_with(context, Delimiter::Open)?;
+                if self.apply(byte, context)?.is_some() {
+                    std::mem::take(self);
+                    anyhow::bail!("Syntax error near unexpected token `{}'", byte as char)
+                }
+                return Ok(program);
+            }
+            (b"(", _) => {
+                std::mem::take(self);
+                anyhow::bail!("Syntax error near unexpected token `('")
+            }
             _ => {}
         }
+        if context.arg_builder_state == ArgBuilderState::Default {
+            match byte {
+                b'<' => {
+                    if self.operator.is_empty() {
+                        let arg = self.arg_builder.finish(context)?;
+                        self.push_arg(arg);
+                    }
+                    self.operator.push(byte);
+                    return Ok(None);
+                }
+                b'-' if self.operator == b"<<" => {
+                    self.operator.push(byte);
+                    return Ok(None);
+                }
+                _ => {}
+            }
+        }
+        if !self.operator.is_empty() {
+            self.redirection_kind = Some(RedirectionKind::from_operator(&self.operator)?);
+            self.operator.clear();
+        }
 
-        self.arg_builder.apply(byte, context)?.map(|arg| {
-            self.current_program.push(arg);
-        });
+        if context.arg_builder_state == ArgBuilderState::Default {
+            match byte {
+                b';' => {
+                    let program = self.finish_with(context, Delimiter::Sequence)?;
+                    self.operator.push(byte);
+                    return Ok(program);
+                }
+                b'\n' => return self.finish_with(context, Delimiter::Sequence),
+                b'&' | b'|' => {
+                    let arg = self.arg_builder.finish(context)?;
+                    self.push_arg(arg);
+                    self.operator.push(byte);
+                    return Ok(None);
+                }
+                b'(' if (self.arg_builder.is_empty() || self.arg_builder.is_plain_word())
+                    && self.redirection_kind.is_none() =>
+                {
+                    let arg = self.arg_builder.finish(context)?;
+                    self.push_arg(arg);
+                    self.operator.push(byte);
+                    return Ok(None);
+                }
+                b')' => return self.finish_with(context, Delimiter::Close),
+                _ => {}
+            }
+        }
+
+        let arg = self.arg_builder.apply(byte, context)?;
+        self.push_arg(arg);
+        if self.current_program.is_case_header() {
+            return self.finish_with(context, Delimiter::Sequence);
+        }
         Ok(None)
     }
 
     pub fn finish(&mut self, context: &mut Context) -> anyhow::Result<Option<Program>> {
-        self.arg_builder.finish(context)?.map(|arg| {
-            self.current_program.push(arg);
-        });
-        Ok(self.return_if_not_empty())
+        if self.arithmetic.is_some() {
+            std::mem::take(self);
+            anyhow::bail!("Syntax error: unclosed arithmetic command")
+        }
+        if matches!(self.operator.as_slice(), b";" | b"&" | b"|" | b"(")
+            && let Some(program) = self.apply(b'\n', context)?
+        {
+            return Ok(Some(program));
+        }
+        self.finish_with(context, Delimiter::Sequence)
     }
 
-    fn return_if_not_empty(&mut self) -> Option<Program> {
+    pub fn is_open(&self) -> bool {
+        self.arg_builder.is_open() || self.arithmetic.is_some()
+    }
+
+    pub fn pending_word(&self) -> Option<(&[u8], bool)> {
+        if self.arithmetic.is_some() || !self.operator.is_empty() || self.redirection_kind.is_some()
+        {
+            return None;
+        }
+        let word = self.arg_builder.plain_word()?;
+        Some((word, self.current_program.is_command_position()))
+    }
+
+    pub fn discard_word(&mut self, context: &mut Context) {
+        std::mem::take(&mut self.arg_builder);
+        context.token_in_process = false;
+    }
+
+    fn finish_with(
+        &mut self,
+        context: &mut Context,
+        delimiter: Delimiter,
+    ) -> anyhow::Result<Option<Program>> {
+        let arg = self.arg_builder.finish(context)?;
+        self.push_arg(arg);
+        if !self.operator.is_empty() || self.redirection_kind.is_some() {
+            std::mem::take(self);
+            anyhow::bail!("Syntax error: redirection target expected")
+        }
+        let closed = std::mem::replace(&mut self.closed, delimiter == Delimiter::Close);
         if self.current_program.is_empty() {
-            None
+            match delimiter {
+                Delimiter::Sequence => return Ok(None),
+                Delimiter::And | Delimiter::Or | Delimiter::Pipe if closed => {}
+                Delimiter::And | Delimiter::Or | Delimiter::Pipe | Delimiter::FunctionHeader => {
+                    std::mem::take(self);
+                    anyhow::bail!("Syntax error: command expected before operator")
+                }
+                Delimiter::CaseEnd | Delimiter::Open | Delimiter::Close => {}
+            }
+        }
+        let mut program = std::mem::take(&mut self.current_program);
+        program.set_delimiter(delimiter);
+        Ok(Some(program))
+    }
+
+    fn apply_conditional(
+        &mut self,
+        byte: u8,
+        context: &mut Context,
+    ) -> anyhow::Result<Option<Program>> {
+        if self.operator.is_empty()
+            && self.current_program.expects_regex()
+            && (matches!(byte, b'(' | b'|') || (byte == b')' && !self.arg_builder.is_empty()))
+        {
+            self.arg_builder.push_literal(byte, context);
+            return Ok(None);
+        }
+        let arg = self.arg_builder.finish(context)?;
+        self.push_arg(arg);
+        if !self.current_program.is_conditional_open() {
+            return self.apply(byte, context);
+        }
+        let operator = match (self.operator.as_slice(), byte) {
+            (b"&", b'&') | (b"|", b'|') => {
+                let mut operator = std::mem::take(&mut self.operator);
+                operator.push(byte);
+                operator
+            }
+            ([], b'&' | b'|') => {
+                self.operator.push(byte);
+                return Ok(None);
+            }
+            ([], _) => vec![byte],
+            (operator, _) => {
+                let operator = String::from_utf8_lossy(operator).to_string();
+                std::mem::take(self);
+                anyhow::bail!("Syntax error in conditional expression near `{}'", operator)
+            }
+        };
+        self.current_program.push(Arg::operator(operator));
+        Ok(None)
+    }
+
+    fn apply_arithmetic(&mut self, byte: u8) -> Option<Arg> {
+        let text = self.arithmetic.as_mut()?;
+        if self.operator == b")" {
+            self.operator.clear();
+            if byte == b')' {
+                return self.arithmetic.take().map(Arg::arithmetic);
+            }
+            text.push(b')');
+        }
+        let depth = text.iter().fold(0isize, |depth, byte| match byte {
+            b'(' => depth + 1,
+            b')' => depth - 1,
+            _ => depth,
+        });
+        if byte == b')' && depth == 0 {
+            self.operator.push(byte);
         } else {
-            Some(std::mem::take(&mut self.current_program))
+            text.push(byte);
+        }
+        None
+    }
+
+    fn push_arg(&mut self, arg: Option<Arg>) {
+        if let Some(arg) = arg {
+            match self.redirection_kind.take() {
+                Some(kind) => self
+                    .current_program
+                    .push_redirection(Redirection::new(kind, arg)),
+                None => self.current_program.push(arg),
+            }
         }
     }
 }
@@ -65,13 +294,10 @@ mod test {
 
         let mut result: Vec<Program> = "echo 100"
             .as_bytes()
-            .into_iter()
+            .iter()
             .filter_map(|byte| program_builder.apply(*byte, &mut context).unwrap())
             .collect();
-        program_builder
-            .finish(&mut context)
-            .unwrap()
-            .map(|arg| result.push(arg));
+        result.extend(program_builder.finish(&mut context).unwrap());
 
         assert_eq!(
             result,
@@ -85,13 +311,10 @@ mod test {
 
         let mut result: Vec<Program> = "x=100 echo 100"
             .as_bytes()
-            .into_iter()
+            .iter()
             .filter_map(|byte| program_builder.apply(*byte, &mut context).unwrap())
             .collect();
-        program_builder
-            .finish(&mut context)
-            .unwrap()
-            .map(|arg| result.push(arg));
+        result.extend(program_builder.finish(&mut context).unwrap());
 
         assert_eq!(
             result,
@@ -106,13 +329,10 @@ mod test {
 
         let mut result: Vec<Program> = "echo 100 200"
             .as_bytes()
-            .into_iter()
+            .iter()
             .filter_map(|byte| program_builder.apply(*byte, &mut context).unwrap())
             .collect();
-        program_builder
-            .finish(&mut context)
-            .unwrap()
-            .map(|arg| result.push(arg));
+        result.extend(program_builder.finish(&mut context).unwrap());
 
         assert_eq!(
             result,
@@ -127,13 +347,10 @@ mod test {
 
         let mut result: Vec<Program> = "echo 100 200;"
             .as_bytes()
-            .into_iter()
+            .iter()
             .filter_map(|byte| program_builder.apply(*byte, &mut context).unwrap())
             .collect();
-        program_builder
-            .finish(&mut context)
-            .unwrap()
-            .map(|arg| result.push(arg));
+        result.extend(program_builder.finish(&mut context).unwrap());
 
         assert_eq!(
             result,
@@ -148,13 +365,10 @@ mod test {
 
         let mut result: Vec<Program> = "echo 100 200; echo 100"
             .as_bytes()
-            .into_iter()
+            .iter()
             .filter_map(|byte| program_builder.apply(*byte, &mut context).unwrap())
             .collect();
-        program_builder
-            .finish(&mut context)
-            .unwrap()
-            .map(|arg| result.push(arg));
+        result.extend(program_builder.finish(&mut context).unwrap());
 
         assert_eq!(
             result,
@@ -175,19 +389,16 @@ mod test {
 
         let mut result: Vec<Program> = "echo '100 200; echo 100'"
             .as_bytes()
-            .into_iter()
+            .iter()
             .filter_map(|byte| program_builder.apply(*byte, &mut context).unwrap())
             .collect();
-        program_builder
-            .finish(&mut context)
-            .unwrap()
-            .map(|arg| result.push(arg));
+        result.extend(program_builder.finish(&mut context).unwrap());
 
         assert_eq!(
             result,
             vec![Program::new(vec![
                 Arg::new_default(vec![Token::new_default("echo")]),
-                Arg::new_default(vec![Token::new_default("100 200; echo 100")]),
+                Arg::new_default(vec![Token::new_quoted("100 200; echo 100")]),
             ]),]
         );
         assert_eq!(program_builder, ProgramBuilder::default());
@@ -195,13 +406,10 @@ mod test {
 
         let mut result: Vec<Program> = "echo 100 200; echo 100; echo 300"
             .as_bytes()
-            .into_iter()
+            .iter()
             .filter_map(|byte| program_builder.apply(*byte, &mut context).unwrap())
             .collect();
-        program_builder
-            .finish(&mut context)
-            .unwrap()
-            .map(|arg| result.push(arg));
+        result.extend(program_builder.finish(&mut context).unwrap());
 
         assert_eq!(
             result,
@@ -226,13 +434,10 @@ mod test {
 
         let mut result: Vec<Program> = "echo $x"
             .as_bytes()
-            .into_iter()
+            .iter()
             .filter_map(|byte| program_builder.apply(*byte, &mut context).unwrap())
             .collect();
-        program_builder
-            .finish(&mut context)
-            .unwrap()
-            .map(|arg| result.push(arg));
+        result.extend(program_builder.finish(&mut context).unwrap());
 
         assert_eq!(
             result,
@@ -246,13 +451,10 @@ mod test {
 
         let mut result: Vec<Program> = "x=100 x=100"
             .as_bytes()
-            .into_iter()
+            .iter()
             .filter_map(|byte| program_builder.apply(*byte, &mut context).unwrap())
             .collect();
-        program_builder
-            .finish(&mut context)
-            .unwrap()
-            .map(|arg| result.push(arg));
+        result.extend(program_builder.finish(&mut context).unwrap());
 
         assert_eq!(
             result,
@@ -263,5 +465,28 @@ mod test {
         );
         assert_eq!(program_builder, ProgramBuilder::default());
         assert_eq!(context, Context::default());
+
+        let mut result: Vec<Program> = "make CC=gcc; 1x=2 \"y\"=3"
+            .as_bytes()
+            .iter()
+            .filter_map(|byte| program_builder.apply(*byte, &mut context).unwrap())
+            .collect();
+        result.extend(program_builder.finish(&mut context).unwrap());
+
+        assert_eq!(
+            result,
+            vec![
+                Program::new(vec![
+                    Arg::new_default(vec![Token::new_default("make")]),
+                    Arg::new_default(vec![Token::new_default("CC=gcc")]),
+                ]),
+                Program::new(vec![
+                    Arg::new_default(vec![Token::new_default("1x=2")]),
+                    Arg::new_default(vec![Token::new_quoted("y"), Token::new_default("=3")]),
+                ])
+            ]
+        );
+        assert_eq!(program_builder, ProgramBuilder::default());
+        assert_eq!(context, Context::default());
     }
 }
@@ -1,24 +1,106 @@
-use crate::{
-    global_state::GlobalState, inner_utils::InnerUtils, parser::arg_builder::arg::Arg,
-    program_output::ProgramOutput,
+use {
+    crate::{
+        global_state::GlobalState,
+        inner_utils::InnerUtils,
+        interpreter::{self, Sink},
+        parser::{arg_builder::arg::Arg, program_builder::redirection::Redirection},
+        program_output::ProgramOutput,
+    },
+    std::{
+        io::{ErrorKind, Write},
+        os::unix::process::CommandExt,
+        path::PathBuf,
+        process::{Command, Stdio},
+    },
 };
 
-#[derive(Default, Debug, PartialEq, Eq)]
+type Assignment = (String, String);
+
+const DECLARATION_UTILS: [&[u8]; 4] = [b"declare", b"export", b"local", b"readonly"];
+
+const RESERVED_WORDS: [&str; 17] = [
+    "if", "then", "elif", "else", "fi", "while", "until", "do", "done", "for", "in", "case",
+    "esac", "{", "}", "function", "!",
+];
+
+#[derive(Default, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Delimiter {
+    #[default]
+    Sequence,
+
+    And,
+    Or,
+    Pipe,
+    CaseEnd,
+    Open,
+    Close,
+    FunctionHeader,
+}
+
+// The output of a function call has already gone to the sink while it ran, so
+// only its status is left.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Executed {
+    Output(ProgramOutput),
+    Call(i32),
+}
+
+#[derive(Default, Debug, PartialEq, Eq, Clone)]
 pub struct Program {
     args: Vec<Arg>,
+    redirections: Vec<Redirection>,
+    delimiter: Delimiter,
 }
 
 impl Program {
-    pub fn execute(self, gs: &mut GlobalState, iu: &InnerUtils) -> anyhow::Result<ProgramOutput> {
-        let prep_program = self.prepare(gs);
-        if prep_program.len() == 0 {
-            return Ok(ProgramOutput::new(0, vec![], vec![]));
+    pub fn execute(
+        mut self,
+        gs: &mut GlobalState,
+        iu: &InnerUtils,
+        sink: &mut Sink<'_>,
+    ) -> anyhow::Result<Executed> {
+        let input = std::mem::take(&mut self.redirections)
+            .into_iter()
+            .map(|redirection| redirection.into_input(gs))
+            .next_back();
+        let (assignments, mut prep_program) = self.prepare(gs)?;
+        let bypass_functions = Self::strip_command_prefix(&mut prep_program);
+        if prep_program.is_empty() {
+            return Ok(Executed::Output(ProgramOutput::new(0, vec![], vec![])));
         }
 
-        if prep_program.first().is_some_and(|name| iu.is_inner(name)) {
-            Ok(iu.execute(prep_program, gs))
+        if let Some(body) = gs
+            .functions
+            .get(&prep_program[0])
+            .filter(|_| !bypass_functions)
+            .cloned()
+        {
+            Self::with_assignments(gs, assignments, |gs| {
+                Executed::Call(interpreter::call(body, prep_program, input, gs, iu, sink))
+            })
+        } else if iu.is_inner(&prep_program[0]) {
+            Self::with_assignments(gs, assignments, |gs| {
+                Executed::Output(iu.execute(prep_program, gs))
+            })
         } else {
-            let mut command = std::process::Command::new(&prep_program[0]);
+            let path = if prep_program[0].contains('/') {
+                Some(PathBuf::from(&prep_program[0]))
+            } else {
+                gs.commands
+                    .resolve(&prep_program[0], &gs.search_path())
+                    .map(|hashed| {
+                        hashed.hits += 1;
+                        hashed.path.clone()
+                    })
+            };
+            let Some(path) = path else {
+                let e = std::io::Error::new(ErrorKind::NotFound, "command not found");
+                let message = format!("{}: '{}'", e, prep_program.join(" "));
+                return Err(anyhow::Error::new(e).context(message));
+            };
+
+            let mut command = Command::new(path);
+            command.arg0(&prep_program[0]);
             for (idx, arg) in prep_program.iter().enumerate() {
                 if idx > 0 {
                     command.arg(arg);
@@ -26,43 +108,269 @@ impl Program {
             }
 
             command.env_clear();
-            for (k, v) in gs.environment.vars() {
+            for (k, v) in gs.environment.exported() {
+                command.env(k, v);
+            }
+            // Bundled utilities that run other commands, like `find -exec`, look
+            // them up where the shell does.
+            if gs.utils_dir().is_some() {
+                command.env("PATH", gs.search_path());
+            }
+            for (k, v) in assignments {
                 command.env(k, v);
             }
 
-            Ok(command
-                .output()
-                .map_err(|e| {
-                    anyhow::Error::msg(format!("{}: '{}'", e.to_string(), prep_program.join(" ")))
-                })?
-                .into())
+            let input = input.or_else(|| gs.input.take());
+            let output = Self::run(command, input).map_err(|e| {
+                let message = format!("{}: '{}'", e, prep_program.join(" "));
+                anyhow::Error::new(e).context(message)
+            })?;
+            Ok(Executed::Output(output.into()))
         }
     }
 
-    pub fn push(&mut self, arg: Arg) {
+    pub fn push(&mut self, mut arg: Arg) {
+        if arg.is_setter()
+            && self
+                .args
+                .iter()
+                .skip_while(|arg| is_reserved(arg))
+                .any(|arg| !arg.is_setter())
+        {
+            arg.unset_setter();
+        }
         self.args.push(arg);
     }
 
+    pub fn push_redirection(&mut self, redirection: Redirection) {
+        self.redirections.push(redirection);
+    }
+
     pub fn is_empty(&self) -> bool {
-        self.args.is_empty()
+        self.args.is_empty() && self.redirections.is_empty()
     }
 
-    fn prepare(self, gs: &mut GlobalState) -> Vec<String> {
-        self.args
+    pub fn delimiter(&self) -> Delimiter {
+        self.delimiter
+    }
+
+    pub fn set_delimiter(&mut self, delimiter: Delimiter) {
+        self.delimiter = delimiter;
+    }
+
+    pub fn reserved_word(&self) -> Option<&'static str> {
+        let word = self.args.first()?.literal()?;
+        RESERVED_WORDS
             .into_iter()
-            .map(|arg| arg.into_string_with_executing(gs))
-            .filter(|arg| arg.len() > 0)
-            .collect()
+            .find(|reserved| reserved.as_bytes() == word)
+    }
+
+    pub fn is_reserved_prefix(&self) -> bool {
+        self.redirections.is_empty() && self.args.iter().all(is_reserved)
+    }
+
+    pub fn is_command_position(&self) -> bool {
+        self.args
+            .iter()
+            .all(|arg| arg.is_setter() || is_reserved(arg))
+            && !self
+                .args
+                .last()
+                .and_then(|arg| arg.literal())
+                .is_some_and(|word| {
+                    [b"for".as_slice(), b"case", b"in", b"function"].contains(&word)
+                })
+    }
+
+    pub fn is_case_header(&self) -> bool {
+        let words: Vec<Option<&[u8]>> = self.args.iter().map(|arg| arg.literal()).collect();
+        match words.as_slice() {
+            [prefix @ .., Some(b"case"), _, Some(b"in")] => prefix.iter().all(|word| {
+                word.is_some_and(|word| [b"then".as_slice(), b"else", b"do", b"{"].contains(&word))
+            }),
+            _ => false,
+        }
+    }
+
+    pub fn is_conditional_open(&self) -> bool {
+        let mut words = self
+            .args
+            .iter()
+            .skip_while(|arg| is_reserved(arg))
+            .map(|arg| arg.literal());
+        matches!(words.next(), Some(Some(b"[["))) && words.all(|word| !matches!(word, Some(b"]]")))
+    }
+
+    pub fn expects_regex(&self) -> bool {
+        matches!(self.args.last().and_then(|arg| arg.literal()), Some(b"=~"))
+    }
+
+    pub fn is_conditional(&self) -> bool {
+        matches!(self.args.first().and_then(|arg| arg.literal()), Some(b"[["))
+    }
+
+    pub fn into_conditional(mut self) -> anyhow::Result<Vec<Arg>> {
+        let last = self.args.pop();
+        if !self.redirections.is_empty()
+            || !matches!(last.as_ref().and_then(|arg| arg.literal()), Some(b"]]"))
+        {
+            anyhow::bail!("Syntax error: `]]' expected")
+        }
+        self.args.remove(0);
+        Ok(self.args)
+    }
+
+    pub fn take_first(&mut self) -> Option<Arg> {
+        if self.args.is_empty() {
+            None
+        } else {
+            Some(self.args.remove(0))
+        }
+    }
+
+    pub fn arithmetic(&self) -> Option<String> {
+        match self.args.as_slice() {
+            [arg] if self.redirections.is_empty() => arg.arithmetic_text(),
+            _ => None,
+        }
+    }
+
+    pub fn into_args(self) -> Vec<Arg> {
+        self.args
+    }
+
+    pub fn pending_here_doc(&mut self) -> Option<&mut Redirection> {
+        self.redirections
+            .iter_mut()
+            .find(|redirection| redirection.is_pending())
+    }
+
+    fn strip_command_prefix(prep_program: &mut Vec<String>) -> bool {
+        let operand = match prep_program.get(1).map(|arg| arg.as_str()) {
+            Some("--") => 2,
+            Some(arg) if !arg.starts_with('-') => 1,
+            _ => return false,
+        };
+        if prep_program[0] != "command" || prep_program.len() <= operand {
+            return false;
+        }
+        prep_program.drain(..operand);
+        true
+    }
+
+    fn with_assignments<T>(
+        gs: &mut GlobalState,
+        assignments: Vec<Assignment>,
+        execute: impl FnOnce(&mut GlobalState) -> T,
+    ) -> anyhow::Result<T> {
+        let mut saved = Vec::new();
+        for (name, value) in assignments {
+            saved.push((name.clone(), gs.environment.variable(&name).cloned()));
+            if let Err(e) = gs.environment.insert(&name, &value) {
+                for (name, variable) in saved.into_iter().rev() {
+                    gs.environment.restore(&name, variable);
+                }
+                return Err(e);
+            }
+            if let Ok(attributes) = gs.environment.attributes_mut(&name) {
+                attributes.exported = true;
+            }
+        }
+        let output = execute(gs);
+        for (name, variable) in saved.into_iter().rev() {
+            gs.environment.restore(&name, variable);
+        }
+        Ok(output)
+    }
+
+    fn run(mut command: Command, input: Option<Vec<u8>>) -> std::io::Result<std::process::Output> {
+        let Some(input) = input else {
+            return command.output();
+        };
+
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let mut stdin = child.stdin.take();
+        let writer = std::thread::spawn(move || {
+            if let Some(stdin) = stdin.as_mut() {
+                let _ = stdin.write_all(&input);
+            }
+        });
+        let output = child.wait_with_output();
+        let _ = writer.join();
+        output
+    }
+
+    fn prepare(self, gs: &mut GlobalState) -> anyhow::Result<(Vec<Assignment>, Vec<String>)> {
+        let (setters, args): (Vec<Arg>, Vec<Arg>) =
+            self.args.into_iter().partition(|arg| arg.is_setter());
+
+        let declaration = args
+            .first()
+            .and_then(|arg| arg.literal())
+            .is_some_and(|name| DECLARATION_UTILS.contains(&name));
+        let mut words = Vec::new();
+        for arg in args {
+            if declaration && arg.has_assignment_prefix() {
+                let (name, value) = arg.into_assignment_with_executing(gs);
+                words.push(format!("{}={}", name, value));
+                continue;
+            }
+            if arg.is_compound() {
+                anyhow::bail!("syntax error near unexpected token `('")
+            }
+            if arg.arithmetic_text().is_some() {
+                anyhow::bail!("syntax error near unexpected token `(('")
+            }
+            words.extend(arg.into_fields_with_executing(gs));
+        }
+
+        let mut assignments = Vec::new();
+        for arg in setters {
+            let compound = arg.is_compound();
+            let (name, value) = arg.into_assignment_with_executing(gs);
+            if compound {
+                gs.environment.insert_compound(&name, &value)?;
+            } else if words.is_empty() {
+                gs.environment.insert(&name, &value)?;
+            } else {
+                gs.environment.check_writable(&name)?;
+                assignments.push((name, value));
+            }
+        }
+        Ok((assignments, words))
     }
 }
 
+pub fn is_keyword(word: &str) -> bool {
+    RESERVED_WORDS.contains(&word) || ["[[", "]]"].contains(&word)
+}
+
+fn is_reserved(arg: &Arg) -> bool {
+    arg.literal().is_some_and(|word| {
+        RESERVED_WORDS
+            .iter()
+            .any(|reserved| reserved.as_bytes() == word)
+    })
+}
+
 #[cfg(test)]
 mod test {
-    use crate::parser::{arg_builder::arg::Arg, program_builder::program::Program};
+    use crate::parser::{
+        arg_builder::arg::Arg,
+        program_builder::program::{Delimiter, Program},
+    };
 
     impl Program {
         pub fn new(args: Vec<Arg>) -> Self {
-            Self { args }
+            Self {
+                args,
+                redirections: vec![],
+                delimiter: Delimiter::Sequence,
+            }
         }
     }
 }
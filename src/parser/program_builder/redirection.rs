@@ -0,0 +1,112 @@
+use crate::{
+    global_state::GlobalState,
+    parser::{
+        arg_builder::{ArgBuilderState, arg::Arg},
+        context::Context,
+        token::Token,
+    },
+};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum RedirectionKind {
+    HereDoc { strip_tabs: bool },
+    HereString,
+}
+
+impl RedirectionKind {
+    pub fn from_operator(operator: &[u8]) -> anyhow::Result<Self> {
+        match operator {
+            b"<<" => Ok(Self::HereDoc { strip_tabs: false }),
+            b"<<-" => Ok(Self::HereDoc { strip_tabs: true }),
+            b"<<<" => Ok(Self::HereString),
+            _ => anyhow::bail!(format!(
+                "Unsupported redirection: {:?}",
+                String::from_utf8_lossy(operator)
+            )),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Redirection {
+    HereDoc {
+        delimiter: Vec<u8>,
+        strip_tabs: bool,
+        expand: bool,
+        body: Arg,
+        closed: bool,
+    },
+    HereString(Arg),
+}
+
+impl Redirection {
+    pub fn new(kind: RedirectionKind, target: Arg) -> Self {
+        match kind {
+            RedirectionKind::HereDoc { strip_tabs } => Self::HereDoc {
+                expand: !target.is_quoted(),
+                delimiter: target.into_raw(),
+                strip_tabs,
+                body: Arg::default(),
+                closed: false,
+            },
+            RedirectionKind::HereString => Self::HereString(target),
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        matches!(self, Self::HereDoc { closed: false, .. })
+    }
+
+    pub fn apply_line(&mut self, mut line: &[u8]) -> anyhow::Result<bool> {
+        let Self::HereDoc {
+            delimiter,
+            strip_tabs,
+            expand,
+            body,
+            closed,
+        } = self
+        else {
+            anyhow::bail!("Here-document is not expected");
+        };
+
+        if *strip_tabs {
+            while line.first() == Some(&b'\t') {
+                line = &line[1..];
+            }
+        }
+        if line.strip_suffix(b"\n").unwrap_or(line) == delimiter.as_slice() {
+            *closed = true;
+            return Ok(true);
+        }
+
+        let mut context = Context {
+            arg_builder_state: if *expand {
+                ArgBuilderState::WeakSep
+            } else {
+                ArgBuilderState::StrongSep
+            },
+            ..Default::default()
+        };
+        let mut token = Token::default();
+        for byte in line {
+            if let Some(token) = token.apply(*byte, &mut context)? {
+                body.push(token);
+            }
+        }
+        if let Some(token) = token.finish(&mut context) {
+            body.push(token);
+        }
+        Ok(false)
+    }
+
+    pub fn into_input(self, gs: &mut GlobalState) -> Vec<u8> {
+        match self {
+            Self::HereDoc { body, .. } => body.into_bytes_with_executing(gs),
+            Self::HereString(arg) => {
+                let mut input = arg.into_bytes_with_executing(gs);
+                input.push(b'\n');
+                input
+            }
+        }
+    }
+}
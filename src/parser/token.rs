@@ -1,42 +1,102 @@
 use crate::{
-    global_state::GlobalState,
+    expansion::{Origin, brace::Piece, parameter::Parameter, tilde},
+    global_state::{GlobalState, environment::Environment},
     parser::{arg_builder::ArgBuilderState, context::Context},
 };
 
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
 enum TokenType {
     #[default]
     Default,
 
     VarGetter,
+    BracedGetter,
 }
 
-#[derive(Default, Debug, PartialEq, Eq)]
+#[derive(Default, Debug, PartialEq, Eq, Clone)]
 pub struct Token {
     buffer: Vec<u8>,
     kind: TokenType,
+    quoted: bool,
 }
 
 impl Token {
+    pub fn raw(buffer: Vec<u8>) -> Self {
+        Self {
+            buffer,
+            kind: TokenType::Default,
+            quoted: true,
+        }
+    }
+
+    pub fn word(buffer: Vec<u8>) -> Self {
+        Self {
+            buffer,
+            kind: TokenType::Default,
+            quoted: false,
+        }
+    }
+
+    pub fn push_literal(&mut self, byte: u8, context: &mut Context) {
+        self.push(byte, context);
+    }
+
     pub fn apply(&mut self, byte: u8, context: &mut Context) -> anyhow::Result<Option<Self>> {
+        if self.kind == TokenType::BracedGetter {
+            if byte != b'}' {
+                self.buffer.push(byte);
+                return Ok(None);
+            }
+            if Parameter::parse(&String::from_utf8_lossy(&self.buffer)).is_none() {
+                anyhow::bail!(
+                    "${{{}}}: bad substitution",
+                    String::from_utf8_lossy(&self.buffer)
+                )
+            }
+            return Ok(Some(std::mem::take(self)));
+        }
+
+        if self.kind == TokenType::VarGetter && self.buffer.is_empty() && byte == b'{' {
+            self.kind = TokenType::BracedGetter;
+            self.quoted = context.arg_builder_state != ArgBuilderState::Default;
+            return Ok(None);
+        }
+
+        if self.kind == TokenType::VarGetter
+            && self.buffer.is_empty()
+            && matches!(byte, b'@' | b'*' | b'#' | b'?' | b'0'..=b'9')
+        {
+            self.push(byte, context);
+            return Ok(self.return_if_not_empty());
+        }
+
+        if self.kind == TokenType::VarGetter && !(byte.is_ascii_alphanumeric() || byte == b'_') {
+            self.keep_lone_dollar(context);
+        }
+        if self.kind == TokenType::VarGetter
+            && !(byte.is_ascii_alphanumeric() || byte == b'_')
+            && let Some(token) = self.return_if_not_empty()
+        {
+            return self
+                .apply(byte, context)
+                .map(|output| output.or(Some(token)));
+        }
+
         match byte {
-            b' ' | b'\n' | b'\0' => {
-                if self.buffer.len() > 0 {
-                    match context.arg_builder_state {
-                        ArgBuilderState::Default => {
-                            context.token_in_process = false;
-                            Ok(Some(std::mem::take(self)))
-                        }
-                        ArgBuilderState::WeakSep | ArgBuilderState::StrongSep => {
-                            context.token_in_process = true;
-                            self.buffer.push(byte);
-                            Ok(None)
-                        }
+            b' ' | b'\t' | b'\n' | b'\0' => match context.arg_builder_state {
+                ArgBuilderState::Default => {
+                    if !self.buffer.is_empty() {
+                        context.token_in_process = false;
+                        Ok(Some(std::mem::take(self)))
+                    } else {
+                        Ok(None)
                     }
-                } else {
+                }
+                ArgBuilderState::WeakSep | ArgBuilderState::StrongSep => {
+                    self.push(byte, context);
                     Ok(None)
                 }
-            }
+            },
             b'a'..=b'z'
             | b'A'..=b'Z'
             | b'0'..=b'9'
@@ -47,28 +107,26 @@ impl Token {
             | b'.'
             | b'\''
             | b'"'
-            | b';' => {
-                context.token_in_process = true;
-                self.buffer.push(byte);
+            | b';'
+            | b'*'
+            | b'?'
+            | b'['
+            | b']'
+            | b'!'
+            | b'^'
+            | b'{'
+            | b'}'
+            | b','
+            | b'~'
+            | b':' => {
+                self.push(byte, context);
                 Ok(None)
             }
-            b':' => {
-                context.token_in_process = true;
-                if self.kind == TokenType::VarGetter {
-                    let output = self.return_if_not_empty();
-                    self.buffer.push(byte);
-                    Ok(output)
-                } else {
-                    self.buffer.push(byte);
-                    Ok(None)
-                }
-            }
             b'=' => {
-                context.token_in_process = true;
                 if context.arg_builder_state == ArgBuilderState::Default {
                     context.current_arg_is_setter = true;
                 }
-                self.buffer.push(byte);
+                self.push(byte, context);
                 Ok(None)
             }
             b'$' => {
@@ -80,20 +138,27 @@ impl Token {
                         Ok(output)
                     }
                     ArgBuilderState::StrongSep => {
-                        self.buffer.push(byte);
+                        self.push(byte, context);
                         Ok(None)
                     }
                 }
             }
-            _ => {
-                anyhow::bail!(format!("Unexpected symbol: {:?}", byte as char))
-            }
+            _ => match context.arg_builder_state {
+                ArgBuilderState::Default => {
+                    anyhow::bail!(format!("Unexpected symbol: {:?}", byte as char))
+                }
+                ArgBuilderState::WeakSep | ArgBuilderState::StrongSep => {
+                    self.push(byte, context);
+                    Ok(None)
+                }
+            },
         }
     }
 
     pub fn finish(&mut self, context: &mut Context) -> Option<Self> {
+        self.keep_lone_dollar(context);
         context.token_in_process = false;
-        if self.buffer.len() > 0 {
+        if !self.buffer.is_empty() {
             Some(std::mem::take(self))
         } else {
             std::mem::take(self);
@@ -101,22 +166,114 @@ impl Token {
         }
     }
 
-    pub fn downgrade(self) -> Vec<u8> {
-        self.buffer
+    pub fn origin(&self) -> Origin {
+        match (self.quoted, &self.kind) {
+            (true, _) => Origin::Quoted,
+            (false, TokenType::Default) => Origin::Literal,
+            (false, TokenType::VarGetter | TokenType::BracedGetter) => Origin::Expanded,
+        }
+    }
+
+    pub fn into_raw(self) -> Vec<u8> {
+        match self.kind {
+            TokenType::Default => self.buffer,
+            TokenType::VarGetter => [b"$".as_slice(), &self.buffer].concat(),
+            TokenType::BracedGetter => [b"${".as_slice(), &self.buffer, b"}"].concat(),
+        }
+    }
+
+    pub fn into_pieces(self) -> Vec<Piece<Token>> {
+        if self.is_literal() {
+            self.buffer.into_iter().map(Piece::Byte).collect()
+        } else {
+            vec![Piece::Opaque(self)]
+        }
+    }
+
+    pub fn from_pieces(pieces: Vec<Piece<Token>>) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        let mut literal = Token::default();
+        for piece in pieces {
+            match piece {
+                Piece::Byte(byte) => literal.buffer.push(byte),
+                Piece::Opaque(token) => {
+                    tokens.extend(literal.return_if_not_empty());
+                    tokens.push(token);
+                }
+            }
+        }
+        tokens.extend(literal.return_if_not_empty());
+        tokens
+    }
+
+    pub fn expand_tilde(&mut self, whole: bool, assignment: bool, environment: &Environment) {
+        if self.is_literal() {
+            self.buffer = tilde::expand(&self.buffer, whole, assignment, environment);
+        }
     }
 
-    pub fn to_default(&mut self, gs: &mut GlobalState) {
+    pub fn expand(self, gs: &mut GlobalState) -> Vec<Vec<u8>> {
         match self.kind {
-            TokenType::Default => {}
-            TokenType::VarGetter => {
-                gs.environment.get_var(&mut self.buffer);
-                self.kind = TokenType::Default
+            TokenType::Default => vec![self.buffer],
+            TokenType::VarGetter | TokenType::BracedGetter => {
+                match Parameter::parse(&String::from_utf8_lossy(&self.buffer)) {
+                    Some(parameter) => parameter.expand(self.quoted, gs),
+                    None => vec![vec![]],
+                }
             }
         }
     }
 
+    pub fn ends_with(&self, byte: u8) -> bool {
+        self.buffer.last() == Some(&byte)
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.kind == TokenType::BracedGetter
+    }
+
+    pub fn is_assignment_prefix(&self) -> bool {
+        self.is_literal()
+            && self
+                .buffer
+                .iter()
+                .position(|byte| *byte == b'=')
+                .map(|eq_idx| match self.buffer[..eq_idx].last() {
+                    Some(b'+') => eq_idx - 1,
+                    _ => eq_idx,
+                })
+                .is_some_and(|eq_idx| {
+                    eq_idx > 0
+                        && !self.buffer[0].is_ascii_digit()
+                        && self.buffer[..eq_idx]
+                            .iter()
+                            .all(|byte| byte.is_ascii_alphanumeric() || *byte == b'_')
+                })
+    }
+
+    pub fn literal(&self) -> Option<&[u8]> {
+        self.is_literal().then_some(self.buffer.as_slice())
+    }
+
+    fn is_literal(&self) -> bool {
+        self.kind == TokenType::Default && !self.quoted
+    }
+
+    fn push(&mut self, byte: u8, context: &mut Context) {
+        context.token_in_process = true;
+        self.quoted = context.arg_builder_state != ArgBuilderState::Default;
+        self.buffer.push(byte);
+    }
+
+    fn keep_lone_dollar(&mut self, context: &mut Context) {
+        if self.kind == TokenType::VarGetter && self.buffer.is_empty() {
+            self.kind = TokenType::Default;
+            self.push(b'$', context);
+        }
+    }
+
     fn return_if_not_empty(&mut self) -> Option<Token> {
-        if self.buffer.len() == 0 {
+        if self.buffer.is_empty() {
             None
         } else {
             Some(std::mem::take(self))
@@ -136,6 +293,7 @@ mod test {
             Self {
                 buffer: buffer.as_bytes().to_vec(),
                 kind: TokenType::Default,
+                quoted: false,
             }
         }
 
@@ -143,6 +301,23 @@ mod test {
             Self {
                 buffer: buffer.as_bytes().to_vec(),
                 kind: TokenType::VarGetter,
+                quoted: false,
+            }
+        }
+
+        pub fn new_quoted(buffer: &str) -> Self {
+            Self {
+                buffer: buffer.as_bytes().to_vec(),
+                kind: TokenType::Default,
+                quoted: true,
+            }
+        }
+
+        pub fn new_quoted_var_getter(buffer: &str) -> Self {
+            Self {
+                buffer: buffer.as_bytes().to_vec(),
+                kind: TokenType::VarGetter,
+                quoted: true,
             }
         }
     }
@@ -154,10 +329,10 @@ mod test {
 
         let mut result: Vec<Token> = "echo 100"
             .as_bytes()
-            .into_iter()
+            .iter()
             .filter_map(|byte| token.apply(*byte, &mut context).unwrap())
             .collect();
-        token.finish(&mut context).map(|token| result.push(token));
+        result.extend(token.finish(&mut context));
 
         assert_eq!(
             result,
@@ -168,10 +343,10 @@ mod test {
 
         let mut result: Vec<Token> = "x=100 echo 100"
             .as_bytes()
-            .into_iter()
+            .iter()
             .filter_map(|byte| token.apply(*byte, &mut context).unwrap())
             .collect();
-        token.finish(&mut context).map(|token| result.push(token));
+        result.extend(token.finish(&mut context));
 
         assert_eq!(
             result,
@@ -186,10 +361,10 @@ mod test {
 
         let mut result: Vec<Token> = "  echo 100   200  "
             .as_bytes()
-            .into_iter()
+            .iter()
             .filter_map(|byte| token.apply(*byte, &mut context).unwrap())
             .collect();
-        token.finish(&mut context).map(|token| result.push(token));
+        result.extend(token.finish(&mut context));
 
         assert_eq!(
             result,
@@ -204,10 +379,10 @@ mod test {
 
         let mut result: Vec<Token> = "x=100 $x"
             .as_bytes()
-            .into_iter()
+            .iter()
             .filter_map(|byte| token.apply(*byte, &mut context).unwrap())
             .collect();
-        token.finish(&mut context).map(|token| result.push(token));
+        result.extend(token.finish(&mut context));
 
         assert_eq!(
             result,
@@ -218,10 +393,10 @@ mod test {
 
         let mut result: Vec<Token> = "x=100 $x 100"
             .as_bytes()
-            .into_iter()
+            .iter()
             .filter_map(|byte| token.apply(*byte, &mut context).unwrap())
             .collect();
-        token.finish(&mut context).map(|token| result.push(token));
+        result.extend(token.finish(&mut context));
 
         assert_eq!(
             result,
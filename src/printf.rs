@@ -0,0 +1,603 @@
+const FLAGS: &[u8] = b"-+ #0";
+
+// Widths and precisions are built in memory, so larger ones are refused.
+const MAX_FIELD: usize = 1 << 20;
+
+// A double has no nonzero digits past this many decimal places, and the
+// formatter takes at most `u16::MAX`, so further digits are padded with zeros.
+const EXACT_DIGITS: usize = 1100;
+
+pub struct Formatted {
+    pub output: Vec<u8>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Default)]
+struct Spec {
+    left: bool,
+    plus: bool,
+    space: bool,
+    alternate: bool,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+}
+
+struct Printf<'a> {
+    args: &'a [String],
+    idx: usize,
+    output: Vec<u8>,
+    errors: Vec<String>,
+}
+
+enum Flow {
+    Continue,
+    Stop,
+}
+
+pub fn format(format: &str, args: &[String]) -> Formatted {
+    let mut printf = Printf {
+        args,
+        idx: 0,
+        output: vec![],
+        errors: vec![],
+    };
+    loop {
+        let start = printf.idx;
+        if let Flow::Stop = printf.pass(format.as_bytes()) {
+            break;
+        }
+        if printf.idx == start || printf.idx >= args.len() {
+            break;
+        }
+    }
+    Formatted {
+        output: printf.output,
+        errors: printf.errors,
+    }
+}
+
+impl<'a> Printf<'a> {
+    fn pass(&mut self, format: &[u8]) -> Flow {
+        let mut idx = 0;
+        while idx < format.len() {
+            match format[idx] {
+                b'\\' => {
+                    idx += 1;
+                    if let Flow::Stop = unescape(format, &mut idx, &mut self.output, false) {
+                        return Flow::Stop;
+                    }
+                }
+                b'%' if format.get(idx + 1) == Some(&b'%') => {
+                    self.output.push(b'%');
+                    idx += 2;
+                }
+                b'%' => {
+                    idx += 1;
+                    if let Flow::Stop = self.conversion(format, &mut idx) {
+                        return Flow::Stop;
+                    }
+                }
+                byte => {
+                    self.output.push(byte);
+                    idx += 1;
+                }
+            }
+        }
+        Flow::Continue
+    }
+
+    fn conversion(&mut self, format: &[u8], idx: &mut usize) -> Flow {
+        let mut spec = Spec::default();
+        while let Some(flag) = format.get(*idx).filter(|byte| FLAGS.contains(byte)) {
+            match flag {
+                b'-' => spec.left = true,
+                b'+' => spec.plus = true,
+                b' ' => spec.space = true,
+                b'#' => spec.alternate = true,
+                _ => spec.zero = true,
+            }
+            *idx += 1;
+        }
+        let start = *idx;
+        if format.get(*idx) == Some(&b'*') {
+            *idx += 1;
+            let width = self.next_integer();
+            spec.left |= width < 0;
+            spec.width = usize::try_from(width.unsigned_abs()).unwrap_or(usize::MAX);
+        } else {
+            spec.width = digits(format, idx).unwrap_or_default();
+        }
+        if spec.width > MAX_FIELD {
+            let text = self.field_text(format, start, *idx);
+            self.errors.push(format!("`{}': invalid field width", text));
+            return Flow::Stop;
+        }
+        if format.get(*idx) == Some(&b'.') {
+            *idx += 1;
+            let start = *idx;
+            if format.get(*idx) == Some(&b'*') {
+                *idx += 1;
+                spec.precision = usize::try_from(self.next_integer()).ok();
+            } else {
+                spec.precision = Some(digits(format, idx).unwrap_or_default());
+            }
+            if spec
+                .precision
+                .is_some_and(|precision| precision > MAX_FIELD)
+            {
+                let text = self.field_text(format, start, *idx);
+                self.errors.push(format!("`{}': invalid precision", text));
+                return Flow::Stop;
+            }
+        }
+
+        let Some(&conversion) = format.get(*idx) else {
+            self.errors
+                .push("`%': missing format character".to_string());
+            return Flow::Stop;
+        };
+        *idx += 1;
+        match conversion {
+            b's' => {
+                let arg = self.next_arg().as_bytes().to_vec();
+                self.write_text(&spec, arg);
+            }
+            b'b' => {
+                let arg = self.next_arg().as_bytes();
+                let mut text = vec![];
+                let mut arg_idx = 0;
+                let mut flow = Flow::Continue;
+                while arg_idx < arg.len() {
+                    arg_idx += 1;
+                    match arg[arg_idx - 1] {
+                        b'\\' => flow = unescape(arg, &mut arg_idx, &mut text, true),
+                        byte => text.push(byte),
+                    }
+                    if let Flow::Stop = flow {
+                        break;
+                    }
+                }
+                self.write_text(&spec, text);
+                return flow;
+            }
+            b'q' => {
+                let arg = quote(self.next_arg()).into_bytes();
+                self.write_text(&spec, arg);
+            }
+            b'c' => {
+                let arg = self.next_arg().chars().next().map(String::from);
+                self.write_text(&spec, arg.unwrap_or_default().into_bytes());
+            }
+            b'd' | b'i' => {
+                let value = self.next_integer();
+                let sign = sign(value < 0, &spec);
+                let mut digits = value.unsigned_abs().to_string();
+                if let Some(precision) = spec.precision {
+                    digits = pad_digits(digits, precision);
+                }
+                self.write_number(&spec, sign, digits, spec.precision.is_none());
+            }
+            b'u' | b'o' | b'x' | b'X' => {
+                let value = self.next_integer() as u64;
+                let (prefix, mut digits) = match conversion {
+                    b'u' => ("", value.to_string()),
+                    b'o' => ("", format!("{:o}", value)),
+                    b'x' => ("0x", format!("{:x}", value)),
+                    _ => ("0X", format!("{:X}", value)),
+                };
+                if let Some(precision) = spec.precision {
+                    digits = pad_digits(digits, precision);
+                }
+                let prefix = match spec.alternate {
+                    true if conversion == b'o' && !digits.starts_with('0') => {
+                        digits.insert(0, '0');
+                        ""
+                    }
+                    true if value != 0 && conversion != b'o' => prefix,
+                    _ => "",
+                };
+                self.write_number(&spec, prefix, digits, spec.precision.is_none());
+            }
+            b'f' | b'F' | b'e' | b'E' => {
+                let value = self.next_float();
+                let precision = spec.precision.unwrap_or(6);
+                let sign = sign(value.is_sign_negative() && value != 0.0, &spec);
+                let mut body = match conversion {
+                    _ if value.is_nan() => "nan".to_string(),
+                    _ if value.is_infinite() => "inf".to_string(),
+                    b'f' | b'F' => fixed(value.abs(), precision),
+                    _ => exponent(value.abs(), precision),
+                };
+                if spec.alternate
+                    && precision == 0
+                    && value.is_finite()
+                    && matches!(conversion, b'f' | b'F')
+                {
+                    body.push('.');
+                }
+                if conversion.is_ascii_uppercase() {
+                    body.make_ascii_uppercase();
+                }
+                match value.is_finite() {
+                    true => self.write_number(&spec, sign, body, true),
+                    false => self.write_text(&spec, format!("{}{}", sign, body).into_bytes()),
+                }
+            }
+            other => {
+                self.errors.push(format!(
+                    "`{}': invalid format character",
+                    String::from_utf8_lossy(&[other])
+                ));
+                return Flow::Stop;
+            }
+        }
+        Flow::Continue
+    }
+
+    // The text a width or precision came from: its digits, or the argument
+    // taken by `*`.
+    fn field_text(&self, format: &[u8], start: usize, end: usize) -> String {
+        match format[start] {
+            b'*' => self.args[self.idx - 1].clone(),
+            _ => String::from_utf8_lossy(&format[start..end]).into_owned(),
+        }
+    }
+
+    fn next_arg(&mut self) -> &'a str {
+        let arg = self.args.get(self.idx).map_or("", |arg| arg.as_str());
+        self.idx += 1;
+        arg
+    }
+
+    fn next_integer(&mut self) -> i64 {
+        let (value, error) = parse_integer(self.next_arg());
+        self.errors.extend(error);
+        value
+    }
+
+    fn next_float(&mut self) -> f64 {
+        let (value, error) = parse_float(self.next_arg());
+        self.errors.extend(error);
+        value
+    }
+
+    fn write_number(&mut self, spec: &Spec, prefix: &str, body: String, zero_fill: bool) {
+        let fill = spec.width.saturating_sub(prefix.len() + body.len());
+        if spec.left {
+            self.output.extend(prefix.bytes().chain(body.bytes()));
+            self.output.extend(std::iter::repeat_n(b' ', fill));
+        } else if spec.zero && zero_fill {
+            self.output.extend(prefix.bytes());
+            self.output.extend(std::iter::repeat_n(b'0', fill));
+            self.output.extend(body.bytes());
+        } else {
+            self.output.extend(std::iter::repeat_n(b' ', fill));
+            self.output.extend(prefix.bytes().chain(body.bytes()));
+        }
+    }
+
+    fn write_text(&mut self, spec: &Spec, mut text: Vec<u8>) {
+        if let Some(precision) = spec.precision {
+            text.truncate(precision);
+        }
+        let fill = std::iter::repeat_n(b' ', spec.width.saturating_sub(text.len()));
+        match spec.left {
+            true => {
+                self.output.extend(text);
+                self.output.extend(fill);
+            }
+            false => {
+                self.output.extend(fill);
+                self.output.extend(text);
+            }
+        }
+    }
+}
+
+// Expands the escape sequence following a backslash at `bytes[*idx - 1]`.
+// Inside `%b` arguments octal escapes may carry an extra leading zero and
+// `\c` stops all further output.
+fn unescape(bytes: &[u8], idx: &mut usize, output: &mut Vec<u8>, argument: bool) -> Flow {
+    let Some(&byte) = bytes.get(*idx) else {
+        output.push(b'\\');
+        return Flow::Continue;
+    };
+    *idx += 1;
+    let escaped = match byte {
+        b'\\' => b'\\',
+        b'a' => 0x07,
+        b'b' => 0x08,
+        b'e' | b'E' => 0x1b,
+        b'f' => 0x0c,
+        b'n' => b'\n',
+        b'r' => b'\r',
+        b't' => b'\t',
+        b'v' => 0x0b,
+        b'"' => b'"',
+        b'\'' if !argument => b'\'',
+        b'?' if !argument => b'?',
+        b'c' if argument => return Flow::Stop,
+        b'0'..=b'7' => {
+            *idx -= 1;
+            if argument && byte == b'0' {
+                *idx += 1;
+            }
+            let value = radix_digits(bytes, idx, 8, 3).unwrap_or_default();
+            output.push(value as u8);
+            return Flow::Continue;
+        }
+        b'x' => match radix_digits(bytes, idx, 16, 2) {
+            Some(value) => value as u8,
+            None => {
+                output.extend(b"\\x");
+                return Flow::Continue;
+            }
+        },
+        b'u' | b'U' => {
+            let length = if byte == b'u' { 4 } else { 8 };
+            match radix_digits(bytes, idx, 16, length) {
+                Some(value) => {
+                    let char = char::from_u32(value).unwrap_or(char::REPLACEMENT_CHARACTER);
+                    output.extend(char.to_string().bytes());
+                }
+                None => output.extend([b'\\', byte]),
+            }
+            return Flow::Continue;
+        }
+        other => {
+            output.extend([b'\\', other]);
+            return Flow::Continue;
+        }
+    };
+    output.push(escaped);
+    Flow::Continue
+}
+
+fn radix_digits(bytes: &[u8], idx: &mut usize, radix: u32, limit: usize) -> Option<u32> {
+    let mut value = None;
+    for _ in 0..limit {
+        let Some(digit) = bytes
+            .get(*idx)
+            .and_then(|byte| (*byte as char).to_digit(radix))
+        else {
+            break;
+        };
+        value = Some(value.unwrap_or(0) * radix + digit);
+        *idx += 1;
+    }
+    value
+}
+
+// Too many digits saturate, so that they are refused as out of range.
+fn digits(bytes: &[u8], idx: &mut usize) -> Option<usize> {
+    let mut value = None;
+    while let Some(byte) = bytes.get(*idx).filter(|byte| byte.is_ascii_digit()) {
+        let digit = (byte - b'0') as usize;
+        value = Some(
+            value
+                .unwrap_or(0usize)
+                .saturating_mul(10)
+                .saturating_add(digit),
+        );
+        *idx += 1;
+    }
+    value
+}
+
+fn pad_digits(digits: String, precision: usize) -> String {
+    match digits.as_str() {
+        "0" if precision == 0 => String::new(),
+        _ => format!("{:0>width$}", digits, width = precision),
+    }
+}
+
+fn sign(negative: bool, spec: &Spec) -> &'static str {
+    match negative {
+        true => "-",
+        false if spec.plus => "+",
+        false if spec.space => " ",
+        false => "",
+    }
+}
+
+fn fixed(value: f64, precision: usize) -> String {
+    let shown = precision.min(EXACT_DIGITS);
+    let mut formatted = format!("{:.*}", shown, value);
+    formatted.extend(std::iter::repeat_n('0', precision - shown));
+    formatted
+}
+
+fn exponent(value: f64, precision: usize) -> String {
+    let shown = precision.min(EXACT_DIGITS);
+    let formatted = format!("{:.*e}", shown, value);
+    let (mantissa, exponent) = formatted.split_once('e').unwrap_or((&formatted, "0"));
+    let exponent: i32 = exponent.parse().unwrap_or_default();
+    let sign = if exponent < 0 { '-' } else { '+' };
+    format!(
+        "{}{}e{}{:02}",
+        mantissa,
+        "0".repeat(precision - shown),
+        sign,
+        exponent.abs()
+    )
+}
+
+fn char_constant(text: &str) -> Option<u32> {
+    let rest = text.strip_prefix('\'').or_else(|| text.strip_prefix('"'))?;
+    Some(rest.chars().next().map_or(0, |char| char as u32))
+}
+
+fn parse_integer(arg: &str) -> (i64, Option<String>) {
+    let text = arg.trim_start();
+    if text.is_empty() {
+        return (0, None);
+    }
+    if let Some(code) = char_constant(text) {
+        return (code as i64, None);
+    }
+    let (negative, unsigned) = match text.as_bytes()[0] {
+        b'-' => (true, &text[1..]),
+        b'+' => (false, &text[1..]),
+        _ => (false, text),
+    };
+    let (radix, digits) = match unsigned {
+        hex if hex.len() > 2 && (hex.starts_with("0x") || hex.starts_with("0X")) => (16, &hex[2..]),
+        octal if octal.len() > 1 && octal.starts_with('0') => (8, &octal[1..]),
+        decimal => (10, decimal),
+    };
+    let end = digits
+        .find(|char: char| !char.is_digit(radix))
+        .unwrap_or(digits.len());
+    let mut value: i128 = 0;
+    for char in digits[..end].chars() {
+        value = (value * radix as i128 + char.to_digit(radix).unwrap() as i128)
+            .min(u64::MAX as i128 + 1);
+    }
+    if negative {
+        value = -value;
+    }
+    if end == 0 || end < digits.len() {
+        return (value as i64, Some(format!("{}: invalid number", arg)));
+    }
+    match i64::try_from(value) {
+        Ok(value) => (value, None),
+        Err(_) if value > 0 && value <= u64::MAX as i128 => (value as u64 as i64, None),
+        Err(_) => (
+            if value < 0 { i64::MIN } else { i64::MAX },
+            Some(format!("warning: {}: Numerical result out of range", arg)),
+        ),
+    }
+}
+
+fn parse_float(arg: &str) -> (f64, Option<String>) {
+    let text = arg.trim_start();
+    if text.is_empty() {
+        return (0.0, None);
+    }
+    if let Some(code) = char_constant(text) {
+        return (code as f64, None);
+    }
+    let end = (1..=text.len())
+        .rev()
+        .filter(|end| text.is_char_boundary(*end))
+        .find(|end| {
+            let prefix = &text[..*end];
+            prefix.parse::<f64>().is_ok() && !prefix.ends_with(['e', 'E', '+', '-'])
+        });
+    match end {
+        Some(end) if end == text.len() => (text.parse().unwrap(), None),
+        Some(end) => (
+            text[..end].parse().unwrap(),
+            Some(format!("{}: invalid number", arg)),
+        ),
+        None => (0.0, Some(format!("{}: invalid number", arg))),
+    }
+}
+
+fn quote(text: &str) -> String {
+    if text.is_empty() {
+        return "''".to_string();
+    }
+    if text.chars().any(|char| char.is_control()) {
+        let mut quoted = "$'".to_string();
+        for char in text.chars() {
+            match char {
+                '\x07' => quoted.push_str("\\a"),
+                '\x08' => quoted.push_str("\\b"),
+                '\x1b' => quoted.push_str("\\E"),
+                '\x0c' => quoted.push_str("\\f"),
+                '\n' => quoted.push_str("\\n"),
+                '\r' => quoted.push_str("\\r"),
+                '\t' => quoted.push_str("\\t"),
+                '\x0b' => quoted.push_str("\\v"),
+                '\\' | '\'' => quoted.extend(['\\', char]),
+                char if char.is_control() && (char as u32) < 0x100 => {
+                    quoted.push_str(&format!("\\{:03o}", char as u32))
+                }
+                char => quoted.push(char),
+            }
+        }
+        quoted.push('\'');
+        return quoted;
+    }
+    let mut quoted = String::new();
+    for (idx, char) in text.chars().enumerate() {
+        let special = match char {
+            '~' | '#' => idx == 0,
+            _ => " '\"\\|&;()<>!{}*[?]^$`,".contains(char),
+        };
+        if special {
+            quoted.push('\\');
+        }
+        quoted.push(char);
+    }
+    quoted
+}
+
+#[cfg(test)]
+mod test {
+    use crate::printf::format;
+
+    fn check(format_text: &str, args: &[&str], output: &str, errors: usize) {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        let formatted = format(format_text, &args);
+        assert_eq!(String::from_utf8_lossy(&formatted.output), output);
+        assert_eq!(formatted.errors.len(), errors, "{:?}", formatted.errors);
+    }
+
+    #[test]
+    fn check_printf() {
+        check("%s %s\\n", &["a", "b", "c"], "a b\nc \n", 0);
+        check("x\\n", &["extra"], "x\n", 0);
+        check("%s\\n", &[], "\n", 0);
+        check(
+            "%5.2s|%-5s|%05d|%+d|% d|%#x|%#o|%.3d|%X|%u",
+            &["abc", "ab", "42", "5", "5", "255", "8", "7", "255", "-1"],
+            "   ab|ab   |00042|+5| 5|0xff|010|007|FF|18446744073709551615",
+            0,
+        );
+        check(
+            "%e|%E|%f|%.0f|%08.3f|%c",
+            &["1234.5", "0.000123", "3.14159", "2.5", "-3.14159", "hello"],
+            "1.234500e+03|1.230000E-04|3.141590|2|-003.142|h",
+            0,
+        );
+        check(
+            "%*d|%-*d|%.*f",
+            &["5", "1", "4", "2", "2", "3.14159"],
+            "    1|2   |3.14",
+            0,
+        );
+        check(
+            "%.70000f|%.1200e",
+            &["1", "1"],
+            &format!("1.{}|1.{}e+00", "0".repeat(70000), "0".repeat(1200)),
+            0,
+        );
+        check("a%99999999999999999999999db", &["1"], "a", 1);
+        check("a%.*fb", &["9999999999", "1"], "a", 1);
+        check("a%*db", &["-9223372036854775808", "1"], "a", 1);
+        check(
+            "%d|%d|%d|%d|%i|%i",
+            &["12abc", "abc", "'A", "0x1f", "010", ""],
+            "12|0|65|31|8|0",
+            2,
+        );
+        check("%f|%d", &["abc", "3.5"], "0.000000|3", 2);
+        check(
+            "\\101\\0101\\x41\\c|%b|%b",
+            &["\\0101\\101\\u0416", "a\\cb", "z"],
+            "A\x081A\\c|AAЖ|a",
+            0,
+        );
+        check(
+            "%q %q %q %q %q",
+            &["a b", "", "~x", "a'b", "a\tb"],
+            "a\\ b '' \\~x a\\'b $'a\\tb'",
+            0,
+        );
+        check("a%zb", &["x"], "a", 1);
+        check("%%|%5s|%-3c|", &["x", "y"], "%|    x|y  |", 0);
+    }
+}
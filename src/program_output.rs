@@ -10,7 +10,7 @@ pub struct ProgramOutput {
 impl ProgramOutput {
     pub fn new(code: i32, stdout: Vec<u8>, stderr: Vec<u8>) -> Self {
         Self {
-            code: code,
+            code,
             stdout,
             stderr,
         }
@@ -0,0 +1,96 @@
+use pseudobash_regex::{ClassError, ParseError, Program, Syntax};
+
+#[derive(Debug, Clone)]
+pub struct Regex {
+    program: Program,
+}
+
+impl Regex {
+    pub fn new(pattern: &str) -> anyhow::Result<Self> {
+        let parsed = pseudobash_regex::parse(pattern, Syntax::Extended).map_err(|e| match e {
+            ParseError::UnmatchedOpen => anyhow::anyhow!("unmatched `(' in regular expression"),
+            ParseError::UnmatchedClose => anyhow::anyhow!("unmatched `)' in regular expression"),
+            ParseError::UnmatchedBrace | ParseError::InvalidInterval => {
+                anyhow::anyhow!("invalid interval in regular expression")
+            }
+            ParseError::InvalidBackref => anyhow::anyhow!("invalid back reference"),
+            ParseError::TrailingBackslash => {
+                anyhow::anyhow!("trailing backslash in regular expression")
+            }
+            ParseError::Class(ClassError::Unmatched) => {
+                anyhow::anyhow!("unmatched `[' in regular expression")
+            }
+            ParseError::Class(ClassError::InvalidName(name)) => {
+                anyhow::anyhow!("invalid character class `{}'", name)
+            }
+            ParseError::Class(ClassError::InvalidCollation) => {
+                anyhow::anyhow!("invalid collating element in regular expression")
+            }
+            ParseError::Class(ClassError::InvalidRange(from, to)) => {
+                anyhow::anyhow!("invalid range end `{}-{}'", from, to)
+            }
+        })?;
+        if let Some(operator) = parsed.dangling {
+            anyhow::bail!("`{}': nothing to repeat", operator)
+        }
+        Ok(Self {
+            program: Program::new(&parsed.node, parsed.groups, false),
+        })
+    }
+
+    pub fn captures(&self, text: &str) -> Option<Vec<Option<(usize, usize)>>> {
+        let chars: Vec<char> = text.chars().collect();
+        let offsets: Vec<usize> = text
+            .char_indices()
+            .map(|(offset, _)| offset)
+            .chain([text.len()])
+            .collect();
+        let groups = self.program.captures(&chars, 0)?;
+        Some(
+            groups
+                .into_iter()
+                .map(|group| group.map(|(from, to)| (offsets[from], offsets[to])))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::regex::Regex;
+
+    #[test]
+    fn check_regex() {
+        let find = |pattern: &str, text: &str| {
+            Regex::new(pattern).unwrap().captures(text).map(|groups| {
+                groups
+                    .into_iter()
+                    .map(|group| group.map_or("", |(from, to)| &text[from..to]))
+                    .map(String::from)
+                    .collect::<Vec<String>>()
+            })
+        };
+
+        assert_eq!(
+            find("([a-z]+)-([0-9]{2,3})", "id: abc-1234"),
+            Some(vec!["abc-123".into(), "abc".into(), "123".into()])
+        );
+        assert_eq!(find("^a|b$", "cab"), Some(vec!["b".into()]));
+        assert_eq!(
+            find("^(foo|bar)?baz$", "baz"),
+            Some(vec!["baz".into(), "".into()])
+        );
+        assert_eq!(find("x[[:digit:]]*\\.", "x12.y"), Some(vec!["x12.".into()]));
+        assert_eq!(find("[^ab]", "abc"), Some(vec!["c".into()]));
+        assert_eq!(find("(a*)*b", "aaac"), None);
+        assert_eq!(find("é.", "café!"), Some(vec!["é!".into()]));
+        assert_eq!(find("a{,2}", "aaa"), Some(vec!["aa".into()]));
+        assert_eq!(find("a)", "xa)"), Some(vec!["a)".into()]));
+        assert_eq!(find("\\<is\\>", "this is"), Some(vec!["is".into()]));
+        assert!(Regex::new("(a").is_err());
+        assert!(Regex::new("\\(a\\)\\1").is_err());
+        assert!(Regex::new("[z-a]").is_err());
+        assert!(Regex::new("*a").is_err());
+        assert!(Regex::new("a|+b").is_err());
+    }
+}
@@ -0,0 +1,253 @@
+use std::io::{BufWriter, ErrorKind, Read, Write};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Default, Clone, Copy, PartialEq)]
+struct Options {
+    number: bool,
+    number_nonblank: bool,
+    squeeze_blank: bool,
+    show_ends: bool,
+    show_tabs: bool,
+    show_nonprinting: bool,
+}
+
+struct Formatter {
+    options: Options,
+    line: usize,
+    at_line_start: bool,
+    blank_lines: usize,
+    pending_cr: bool,
+}
+
+pub fn run(args: Vec<String>) -> i32 {
+    let mut options = Options::default();
+    let mut files = Vec::new();
+    let mut options_done = false;
+    for arg in &args[1..] {
+        if options_done || arg == "-" || !arg.starts_with('-') {
+            files.push(arg.as_str());
+            continue;
+        }
+        let flags: Vec<char> = match arg.as_str() {
+            "--" => {
+                options_done = true;
+                continue;
+            }
+            "--show-all" => vec!['A'],
+            "--number-nonblank" => vec!['b'],
+            "--show-ends" => vec!['E'],
+            "--number" => vec!['n'],
+            "--squeeze-blank" => vec!['s'],
+            "--show-tabs" => vec!['T'],
+            "--show-nonprinting" => vec!['v'],
+            long if long.starts_with("--") => {
+                eprintln!("cat: unrecognized option '{}'", long);
+                return 1;
+            }
+            short => short[1..].chars().collect(),
+        };
+        for flag in flags {
+            match flag {
+                'A' => {
+                    options.show_nonprinting = true;
+                    options.show_ends = true;
+                    options.show_tabs = true;
+                }
+                'b' => options.number_nonblank = true,
+                'e' => {
+                    options.show_nonprinting = true;
+                    options.show_ends = true;
+                }
+                'E' => options.show_ends = true,
+                'n' => options.number = true,
+                's' => options.squeeze_blank = true,
+                't' => {
+                    options.show_nonprinting = true;
+                    options.show_tabs = true;
+                }
+                'T' => options.show_tabs = true,
+                'u' => {}
+                'v' => options.show_nonprinting = true,
+                flag => {
+                    eprintln!("cat: invalid option -- '{}'", flag);
+                    return 1;
+                }
+            }
+        }
+    }
+    if files.is_empty() {
+        files.push("-");
+    }
+
+    let mut r_code = 0;
+    let mut formatter = Formatter::new(options);
+    let mut stdout = BufWriter::with_capacity(CHUNK_SIZE, std::io::stdout().lock());
+    for file in files {
+        let result = match file {
+            "-" => formatter.copy(std::io::stdin().lock(), &mut stdout),
+            path => std::fs::File::open(path).and_then(|file| formatter.copy(file, &mut stdout)),
+        };
+        if let Err(e) = result {
+            r_code = 1;
+            eprintln!("cat: {}: {}", file, crate::strerror(&e));
+        }
+    }
+
+    r_code
+}
+
+impl Formatter {
+    fn new(options: Options) -> Self {
+        Self {
+            options,
+            line: 0,
+            at_line_start: true,
+            blank_lines: 0,
+            pending_cr: false,
+        }
+    }
+
+    fn copy(&mut self, mut reader: impl Read, writer: &mut impl Write) -> std::io::Result<()> {
+        if self.options == Options::default() {
+            std::io::copy(&mut reader, writer)?;
+            return writer.flush();
+        }
+        let mut chunk = vec![0; CHUNK_SIZE];
+        loop {
+            match reader.read(&mut chunk) {
+                Ok(0) => {
+                    if std::mem::take(&mut self.pending_cr) {
+                        writer.write_all(b"\r")?;
+                    }
+                    return writer.flush();
+                }
+                Ok(read) => self.format(&chunk[..read], writer)?,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn format(&mut self, chunk: &[u8], writer: &mut impl Write) -> std::io::Result<()> {
+        for &byte in chunk {
+            if self.at_line_start {
+                if byte == b'\n' {
+                    self.blank_lines += 1;
+                    if self.options.squeeze_blank && self.blank_lines > 1 {
+                        continue;
+                    }
+                    if self.options.number && !self.options.number_nonblank {
+                        self.write_number(writer)?;
+                    }
+                    self.write_end(writer)?;
+                    continue;
+                }
+                if self.options.number || self.options.number_nonblank {
+                    self.write_number(writer)?;
+                }
+                self.at_line_start = false;
+                self.blank_lines = 0;
+            }
+            if std::mem::take(&mut self.pending_cr) {
+                match byte {
+                    b'\n' => writer.write_all(b"^M")?,
+                    _ => writer.write_all(b"\r")?,
+                }
+            }
+            match byte {
+                b'\r' if self.options.show_ends && !self.options.show_nonprinting => {
+                    self.pending_cr = true
+                }
+                b'\n' => {
+                    self.write_end(writer)?;
+                    self.at_line_start = true;
+                }
+                b'\t' if self.options.show_tabs => writer.write_all(b"^I")?,
+                byte if self.options.show_nonprinting => write_visible(byte, writer)?,
+                byte => writer.write_all(&[byte])?,
+            }
+        }
+        Ok(())
+    }
+
+    fn write_number(&mut self, writer: &mut impl Write) -> std::io::Result<()> {
+        self.line += 1;
+        write!(writer, "{:>6}\t", self.line)
+    }
+
+    fn write_end(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        match self.options.show_ends {
+            true => writer.write_all(b"$\n"),
+            false => writer.write_all(b"\n"),
+        }
+    }
+}
+
+fn write_visible(byte: u8, writer: &mut impl Write) -> std::io::Result<()> {
+    if byte == b'\t' {
+        return writer.write_all(b"\t");
+    }
+    let byte = match byte {
+        0x80.. => {
+            writer.write_all(b"M-")?;
+            byte - 0x80
+        }
+        byte => byte,
+    };
+    match byte {
+        0x00..0x20 => writer.write_all(&[b'^', byte + 0x40]),
+        0x7f => writer.write_all(b"^?"),
+        byte => writer.write_all(&[byte]),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cat::{Formatter, Options};
+
+    fn format(options: Options, chunks: &[&[u8]]) -> Vec<u8> {
+        let mut formatter = Formatter::new(options);
+        let mut output = Vec::new();
+        for chunk in chunks {
+            formatter.format(chunk, &mut output).unwrap();
+        }
+        output
+    }
+
+    #[test]
+    fn check_options() {
+        let text: &[u8] = b"a\n\n\n\tb\x01\x7f\xe9\x89\r\n";
+        let chunks = [&text[..2], &text[2..11], &text[11..]];
+        let numbered = Options {
+            number: true,
+            squeeze_blank: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            format(numbered, &chunks),
+            b"     1\ta\n     2\t\n     3\t\tb\x01\x7f\xe9\x89\r\n"
+        );
+        let shown = Options {
+            number_nonblank: true,
+            show_ends: true,
+            show_tabs: true,
+            show_nonprinting: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            format(shown, &chunks),
+            b"     1\ta$\n$\n$\n     2\t^Ib^A^?M-iM-^I^M$\n"
+        );
+        let ends = Options {
+            show_ends: true,
+            ..Default::default()
+        };
+        assert_eq!(format(ends, &chunks), b"a$\n$\n$\n\tb\x01\x7f\xe9\x89^M$\n");
+        let nonprinting = Options {
+            show_nonprinting: true,
+            ..Default::default()
+        };
+        assert_eq!(format(nonprinting, &[b"\t\x89\n"]), b"\tM-^I\n");
+    }
+}
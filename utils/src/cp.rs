@@ -0,0 +1,380 @@
+use {
+    crate::getopt::{self, Args, Opt},
+    std::{
+        fs::{FileTimes, Metadata},
+        io::ErrorKind,
+        os::unix::fs::{DirBuilderExt, MetadataExt, OpenOptionsExt, PermissionsExt},
+        path::Path,
+    },
+};
+
+#[derive(Default)]
+pub struct Cp {
+    // Prefix for error messages, mv copies across file systems with this.
+    pub applet: &'static str,
+    pub recursive: bool,
+    pub preserve: bool,
+    // `None` follows symbolic links unless copying recursively.
+    pub dereference: Option<bool>,
+    pub force: bool,
+    pub interactive: bool,
+    pub no_clobber: bool,
+    pub verbose: bool,
+}
+
+pub fn run(args: Vec<String>) -> i32 {
+    let mut cp = Cp {
+        applet: "cp",
+        ..Default::default()
+    };
+    let mut operands = Vec::new();
+    for opt in Args::new(args) {
+        match opt {
+            opt if opt.is('r', "recursive") || opt.is('R', "recursive") => cp.recursive = true,
+            opt if opt.is('p', "preserve") => cp.preserve = true,
+            opt if opt.is('a', "archive") => {
+                cp.recursive = true;
+                cp.preserve = true;
+                cp.dereference = Some(false);
+            }
+            opt if opt.is('L', "dereference") => cp.dereference = Some(true),
+            opt if opt.is('P', "no-dereference") => cp.dereference = Some(false),
+            opt if opt.is('f', "force") => cp.force = true,
+            opt if opt.is('i', "interactive") => {
+                cp.interactive = true;
+                cp.no_clobber = false;
+            }
+            opt if opt.is('n', "no-clobber") => {
+                cp.no_clobber = true;
+                cp.interactive = false;
+            }
+            opt if opt.is('v', "verbose") => cp.verbose = true,
+            Opt::Operand(operand) => operands.push(operand),
+            opt => return getopt::unknown("cp", &opt),
+        }
+    }
+    let Some(pairs) = destinations("cp", operands) else {
+        return 1;
+    };
+
+    let mut r_code = 0;
+    for (source, destination) in pairs {
+        if !cp.copy(Path::new(&source), Path::new(&destination)) {
+            r_code = 1;
+        }
+    }
+    r_code
+}
+
+// Resolves `SOURCE DEST` and `SOURCE... DIRECTORY` into (source, destination)
+// pairs, reporting usage errors as `applet`.
+pub fn destinations(applet: &str, mut operands: Vec<String>) -> Option<Vec<(String, String)>> {
+    let target = match operands.len() {
+        0 => {
+            eprintln!("{}: missing file operand", applet);
+            return None;
+        }
+        1 => {
+            eprintln!(
+                "{}: missing destination file operand after '{}'",
+                applet, operands[0]
+            );
+            return None;
+        }
+        _ => operands.pop().unwrap(),
+    };
+    if !Path::new(&target).is_dir() {
+        if operands.len() > 1 {
+            match std::fs::metadata(&target) {
+                Err(e) => eprintln!("{}: target '{}': {}", applet, target, crate::strerror(&e)),
+                Ok(_) => eprintln!("{}: target '{}' is not a directory", applet, target),
+            }
+            return None;
+        }
+        return Some(vec![(operands.remove(0), target)]);
+    }
+    let pairs = operands
+        .into_iter()
+        .map(|source| {
+            let name = Path::new(&source)
+                .file_name()
+                .map_or(source.clone(), |name| name.to_string_lossy().into_owned());
+            let destination = format!("{}/{}", target.trim_end_matches('/'), name);
+            (source, destination)
+        })
+        .collect();
+    Some(pairs)
+}
+
+impl Cp {
+    // Copies `source` and reports whether everything below it was copied.
+    pub fn copy(&self, source: &Path, destination: &Path) -> bool {
+        match self.try_copy(source, destination) {
+            Ok(copied) => copied,
+            Err(message) => {
+                eprintln!("{}: {}", self.applet, message);
+                false
+            }
+        }
+    }
+
+    fn try_copy(&self, source: &Path, destination: &Path) -> Result<bool, String> {
+        let follow = self.dereference.unwrap_or(!self.recursive);
+        let stat = |path| match follow {
+            true => std::fs::metadata(path),
+            false => std::fs::symlink_metadata(path),
+        };
+        let metadata = stat(source).map_err(|e| {
+            format!(
+                "cannot stat '{}': {}",
+                source.display(),
+                crate::strerror(&e)
+            )
+        })?;
+        let existing = std::fs::symlink_metadata(destination).ok();
+        if let Ok(target) = stat(destination)
+            && target.dev() == metadata.dev()
+            && target.ino() == metadata.ino()
+        {
+            return Err(format!(
+                "'{}' and '{}' are the same file",
+                source.display(),
+                destination.display()
+            ));
+        }
+
+        let mut copied = true;
+        if metadata.is_dir() {
+            copied = self.copy_directory(source, destination, &metadata, existing.as_ref())?;
+        } else {
+            if existing.is_some() {
+                if self.no_clobber {
+                    return Ok(true);
+                }
+                let prompt = format!("{}: overwrite '{}'? ", self.applet, destination.display());
+                if self.interactive && !crate::confirm(&prompt) {
+                    return Ok(true);
+                }
+            }
+            if metadata.is_symlink() {
+                copy_symlink(source, destination, existing.is_some())
+            } else if metadata.is_file() || !self.recursive {
+                self.copy_file(source, destination, &metadata)
+            } else {
+                Err(std::io::Error::from(ErrorKind::Unsupported))
+            }
+            .map_err(|e| {
+                format!(
+                    "cannot create '{}': {}",
+                    destination.display(),
+                    crate::strerror(&e)
+                )
+            })?;
+            self.report(source, destination);
+        }
+        if self.preserve && !metadata.is_symlink() {
+            preserve(destination, &metadata).map_err(|e| {
+                format!(
+                    "preserving attributes of '{}': {}",
+                    destination.display(),
+                    crate::strerror(&e)
+                )
+            })?;
+        }
+        Ok(copied)
+    }
+
+    fn copy_directory(
+        &self,
+        source: &Path,
+        destination: &Path,
+        metadata: &Metadata,
+        existing: Option<&Metadata>,
+    ) -> Result<bool, String> {
+        if !self.recursive {
+            return Err(format!(
+                "-r not specified; omitting directory '{}'",
+                source.display()
+            ));
+        }
+        let canonical = source.canonicalize().map_err(|e| e.to_string())?;
+        let parent = destination
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        if parent
+            .canonicalize()
+            .is_ok_and(|parent| parent.starts_with(&canonical))
+        {
+            return Err(format!(
+                "cannot copy a directory, '{}', into itself, '{}'",
+                source.display(),
+                destination.display()
+            ));
+        }
+        match existing {
+            Some(existing) if !existing.is_dir() => {
+                return Err(format!(
+                    "cannot overwrite non-directory '{}' with directory '{}'",
+                    destination.display(),
+                    source.display()
+                ));
+            }
+            Some(_) => {}
+            // Owner access is needed to fill the directory, `preserve` or the
+            // end of this function restores the original mode.
+            None => std::fs::DirBuilder::new()
+                .mode(metadata.mode() & 0o7777 | 0o700)
+                .create(destination)
+                .map_err(|e| {
+                    format!(
+                        "cannot create directory '{}': {}",
+                        destination.display(),
+                        crate::strerror(&e)
+                    )
+                })?,
+        }
+
+        self.report(source, destination);
+
+        let entries = std::fs::read_dir(source).and_then(|entries| {
+            let mut entries: Vec<_> = entries
+                .map(|entry| entry.map(|entry| entry.file_name()))
+                .collect::<std::io::Result<_>>()?;
+            entries.sort();
+            Ok(entries)
+        });
+        let entries = entries.map_err(|e| {
+            format!(
+                "cannot access '{}': {}",
+                source.display(),
+                crate::strerror(&e)
+            )
+        })?;
+        let mut copied = true;
+        for entry in entries {
+            copied &= self.copy(&source.join(&entry), &destination.join(&entry));
+        }
+        if existing.is_none() && metadata.mode() & 0o700 != 0o700 && !self.preserve {
+            let permissions = std::fs::Permissions::from_mode(metadata.mode() & 0o7777);
+            std::fs::set_permissions(destination, permissions).map_err(|e| e.to_string())?;
+        }
+        Ok(copied)
+    }
+
+    fn report(&self, source: &Path, destination: &Path) {
+        if self.verbose {
+            println!("'{}' -> '{}'", source.display(), destination.display());
+        }
+    }
+
+    fn copy_file(
+        &self,
+        source: &Path,
+        destination: &Path,
+        metadata: &Metadata,
+    ) -> std::io::Result<()> {
+        let mut reader = std::fs::File::open(source)?;
+        let open = || {
+            std::fs::File::options()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .mode(metadata.mode() & 0o777)
+                .open(destination)
+        };
+        let mut writer = match open() {
+            Err(e) if self.force && e.kind() == ErrorKind::PermissionDenied => {
+                std::fs::remove_file(destination)?;
+                open()?
+            }
+            result => result?,
+        };
+        std::io::copy(&mut reader, &mut writer)?;
+        Ok(())
+    }
+}
+
+fn copy_symlink(source: &Path, destination: &Path, existing: bool) -> std::io::Result<()> {
+    let target = std::fs::read_link(source)?;
+    if existing {
+        std::fs::remove_file(destination)?;
+    }
+    std::os::unix::fs::symlink(target, destination)
+}
+
+// Copies the mode, owner and timestamps. Changing the owner needs privileges,
+// so failing to do that is not an error, as in coreutils.
+fn preserve(destination: &Path, metadata: &Metadata) -> std::io::Result<()> {
+    let _ = std::os::unix::fs::chown(destination, Some(metadata.uid()), Some(metadata.gid()));
+    std::fs::set_permissions(
+        destination,
+        std::fs::Permissions::from_mode(metadata.mode() & 0o7777),
+    )?;
+    let times = FileTimes::new()
+        .set_accessed(metadata.accessed()?)
+        .set_modified(metadata.modified()?);
+    std::fs::File::open(destination)?.set_times(times)
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        crate::cp::{Cp, destinations},
+        std::os::unix::fs::PermissionsExt,
+    };
+
+    #[test]
+    fn check_copy() {
+        let root = std::env::temp_dir().join(format!("pseudobash-cp-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("src/sub")).unwrap();
+        std::fs::write(root.join("src/sub/file"), "text").unwrap();
+        std::fs::set_permissions(
+            root.join("src/sub/file"),
+            std::fs::Permissions::from_mode(0o640),
+        )
+        .unwrap();
+        std::os::unix::fs::symlink("sub/file", root.join("src/link")).unwrap();
+
+        let plain = Cp::default();
+        assert!(!plain.copy(&root.join("src"), &root.join("dst")));
+        let archive = Cp {
+            recursive: true,
+            preserve: true,
+            dereference: Some(false),
+            ..Default::default()
+        };
+        assert!(archive.copy(&root.join("src"), &root.join("dst")));
+        let file = std::fs::metadata(root.join("dst/sub/file")).unwrap();
+        assert_eq!(file.permissions().mode() & 0o777, 0o640);
+        assert_eq!(
+            file.modified().unwrap(),
+            std::fs::metadata(root.join("src/sub/file"))
+                .unwrap()
+                .modified()
+                .unwrap()
+        );
+        let link = std::fs::read_link(root.join("dst/link")).unwrap();
+        assert_eq!(link.to_str(), Some("sub/file"));
+        assert!(!archive.copy(&root.join("src"), &root.join("src/sub/inner")));
+        assert!(!plain.copy(&root.join("src/link"), &root.join("src/sub/file")));
+
+        let operands = |operands: &[&str]| {
+            destinations(
+                "cp",
+                operands.iter().map(|operand| operand.to_string()).collect(),
+            )
+        };
+        let target = root.to_string_lossy().into_owned();
+        assert_eq!(
+            operands(&["a/x", "b", &target]),
+            Some(vec![
+                ("a/x".to_string(), format!("{}/x", target)),
+                ("b".to_string(), format!("{}/b", target)),
+            ])
+        );
+        assert_eq!(operands(&["a", "b"]), Some(vec![("a".into(), "b".into())]));
+        assert_eq!(operands(&["a", "b", "c"]), None);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,253 @@
+use {
+    crate::getopt::{self, Args, Opt},
+    std::io::{BufRead, BufWriter, Write},
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Bytes,
+    Chars,
+    Fields,
+}
+
+struct Cut {
+    mode: Mode,
+    ranges: Vec<(usize, usize)>,
+    complement: bool,
+    delimiter: Vec<u8>,
+    output_delimiter: Option<Vec<u8>>,
+    only_delimited: bool,
+}
+
+pub fn run(args: Vec<String>) -> i32 {
+    let mut list = None;
+    let mut delimiter = b"\t".to_vec();
+    let mut output_delimiter = None;
+    let mut complement = false;
+    let mut only_delimited = false;
+    let mut files = Vec::new();
+    let mut args = Args::new(args);
+    while let Some(opt) = args.next() {
+        match opt {
+            opt if opt.is('b', "bytes") || opt.is('c', "characters") || opt.is('f', "fields") => {
+                if list.is_some() {
+                    eprintln!("cut: only one type of list may be specified");
+                    return 1;
+                }
+                let mode = match opt {
+                    opt if opt.is('b', "bytes") => Mode::Bytes,
+                    opt if opt.is('c', "characters") => Mode::Chars,
+                    _ => Mode::Fields,
+                };
+                let Some(value) = args.value() else {
+                    return getopt::missing("cut", "f");
+                };
+                match parse_list(&value) {
+                    Some(ranges) => list = Some((mode, ranges)),
+                    None => {
+                        eprintln!("cut: invalid field range '{}'", value);
+                        return 1;
+                    }
+                }
+            }
+            opt if opt.is('d', "delimiter") => match args.value() {
+                Some(value) if value.chars().count() == 1 => delimiter = value.into_bytes(),
+                _ => {
+                    eprintln!("cut: the delimiter must be a single character");
+                    return 1;
+                }
+            },
+            Opt::Long(ref long) if long == "output-delimiter" => {
+                output_delimiter = args.value().map(String::into_bytes)
+            }
+            opt if opt.is('s', "only-delimited") => only_delimited = true,
+            Opt::Long(ref long) if long == "complement" => complement = true,
+            Opt::Short('n') => {}
+            Opt::Operand(file) => files.push(file),
+            opt => return getopt::unknown("cut", &opt),
+        }
+    }
+    let Some((mode, ranges)) = list else {
+        eprintln!("cut: you must specify a list of bytes, characters, or fields");
+        return 1;
+    };
+    if files.is_empty() {
+        files.push("-".to_string());
+    }
+
+    let cut = Cut {
+        mode,
+        ranges,
+        complement,
+        delimiter,
+        output_delimiter,
+        only_delimited,
+    };
+    let mut r_code = 0;
+    let mut stdout = BufWriter::new(std::io::stdout().lock());
+    for file in &files {
+        let result = getopt::open(file).and_then(|mut reader| cut.filter(&mut reader, &mut stdout));
+        if let Err(e) = result {
+            r_code = 1;
+            eprintln!("cut: {}: {}", file, crate::strerror(&e));
+        }
+    }
+
+    r_code
+}
+
+// LIST is made of N, N-M, N- and -M separated by commas, counted from 1.
+fn parse_list(text: &str) -> Option<Vec<(usize, usize)>> {
+    let mut ranges = Vec::new();
+    for part in text.split(',') {
+        let (start, end) = match part.split_once('-') {
+            Some(("", "")) => return None,
+            Some((start, end)) => (
+                if start.is_empty() {
+                    1
+                } else {
+                    start.parse().ok()?
+                },
+                if end.is_empty() {
+                    usize::MAX
+                } else {
+                    end.parse().ok()?
+                },
+            ),
+            None => (part.parse().ok()?, part.parse().ok()?),
+        };
+        if start == 0 || end < start {
+            return None;
+        }
+        ranges.push((start, end));
+    }
+    Some(ranges)
+}
+
+impl Cut {
+    fn filter(&self, reader: &mut dyn BufRead, writer: &mut impl Write) -> std::io::Result<()> {
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                return writer.flush();
+            }
+            if line.last() == Some(&b'\n') {
+                line.pop();
+            }
+            match self.mode {
+                Mode::Bytes => {
+                    let bytes: Vec<&[u8]> = line.chunks(1).collect();
+                    self.write_units(writer, &bytes, &[])?;
+                }
+                Mode::Chars => self.write_units(writer, &chars(&line), &[])?,
+                Mode::Fields => {
+                    let fields = split(&line, &self.delimiter);
+                    if fields.len() == 1 {
+                        if self.only_delimited {
+                            continue;
+                        }
+                        writer.write_all(&line)?;
+                    } else {
+                        self.write_units(writer, &fields, &self.delimiter)?;
+                    }
+                }
+            }
+            writer.write_all(b"\n")?;
+        }
+    }
+
+    // Writes the selected units. Fields are always joined with a delimiter,
+    // bytes and characters only get one between non-adjacent ranges.
+    fn write_units(
+        &self,
+        writer: &mut impl Write,
+        units: &[&[u8]],
+        joiner: &[u8],
+    ) -> std::io::Result<()> {
+        let joiner = self.output_delimiter.as_deref().unwrap_or(joiner);
+        let mut previous = None;
+        for (idx, unit) in units.iter().enumerate() {
+            if !self.is_selected(idx + 1) {
+                continue;
+            }
+            let separated = match self.mode {
+                Mode::Fields => previous.is_some(),
+                _ => previous.is_some_and(|previous| previous + 1 != idx),
+            };
+            if separated {
+                writer.write_all(joiner)?;
+            }
+            writer.write_all(unit)?;
+            previous = Some(idx);
+        }
+        Ok(())
+    }
+
+    fn is_selected(&self, position: usize) -> bool {
+        let selected = self
+            .ranges
+            .iter()
+            .any(|(start, end)| (*start..=*end).contains(&position));
+        selected != self.complement
+    }
+}
+
+fn split<'a>(line: &'a [u8], delimiter: &[u8]) -> Vec<&'a [u8]> {
+    let mut fields = Vec::new();
+    let mut start = 0;
+    let mut idx = 0;
+    while idx + delimiter.len() <= line.len() {
+        if line[idx..].starts_with(delimiter) {
+            fields.push(&line[start..idx]);
+            idx += delimiter.len();
+            start = idx;
+        } else {
+            idx += 1;
+        }
+    }
+    fields.push(&line[start..]);
+    fields
+}
+
+fn chars(line: &[u8]) -> Vec<&[u8]> {
+    let mut chars = Vec::new();
+    let mut start = 0;
+    for idx in 1..=line.len() {
+        if idx == line.len() || line[idx] & 0xc0 != 0x80 {
+            chars.push(&line[start..idx]);
+            start = idx;
+        }
+    }
+    chars
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cut::{Cut, Mode, parse_list};
+
+    fn check(mode: Mode, list: &str, complement: bool, input: &str, expected: &str) {
+        let cut = Cut {
+            mode,
+            ranges: parse_list(list).unwrap(),
+            complement,
+            delimiter: b":".to_vec(),
+            output_delimiter: None,
+            only_delimited: false,
+        };
+        let mut output = Vec::new();
+        cut.filter(&mut input.as_bytes(), &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    #[test]
+    fn check_cut() {
+        assert!(parse_list("0").is_none());
+        assert!(parse_list("3-1").is_none());
+        check(Mode::Fields, "1,3", false, "a:b:c:d\nnone\n", "a:c\nnone\n");
+        check(Mode::Fields, "2-", false, "a:b:c", "b:c\n");
+        check(Mode::Fields, "2", true, "a:b:c", "a:c\n");
+        check(Mode::Bytes, "-2,4", false, "abcde", "abd\n");
+        check(Mode::Chars, "2-3", false, "жёлтый", "ёл\n");
+    }
+}
@@ -0,0 +1,132 @@
+use std::io::Write;
+
+pub fn run(args: Vec<String>) -> i32 {
+    let mut newline = true;
+    let mut escapes = false;
+    let mut words = &args[1..];
+    while let Some(flags) = words.first().and_then(|word| word.strip_prefix('-')) {
+        if flags.is_empty() || !flags.chars().all(|flag| "neE".contains(flag)) {
+            break;
+        }
+        for flag in flags.chars() {
+            match flag {
+                'n' => newline = false,
+                'e' => escapes = true,
+                _ => escapes = false,
+            }
+        }
+        words = &words[1..];
+    }
+
+    let mut output = Vec::new();
+    for (idx, word) in words.iter().enumerate() {
+        if idx > 0 {
+            output.push(b' ');
+        }
+        match escapes {
+            true => {
+                if unescape(word.as_bytes(), &mut output) {
+                    newline = false;
+                    break;
+                }
+            }
+            false => output.extend(word.as_bytes()),
+        }
+    }
+    if newline {
+        output.push(b'\n');
+    }
+
+    let mut stdout = std::io::stdout().lock();
+    match stdout.write_all(&output).and_then(|_| stdout.flush()) {
+        Ok(_) => 0,
+        Err(e) => {
+            eprintln!("echo: write error: {}", crate::strerror(&e));
+            1
+        }
+    }
+}
+
+// Returns true when `\c` asks to stop producing output.
+fn unescape(word: &[u8], output: &mut Vec<u8>) -> bool {
+    let mut idx = 0;
+    while idx < word.len() {
+        idx += 1;
+        if word[idx - 1] != b'\\' || idx == word.len() {
+            output.push(word[idx - 1]);
+            continue;
+        }
+        idx += 1;
+        let escaped = match word[idx - 1] {
+            b'\\' => b'\\',
+            b'a' => 0x07,
+            b'b' => 0x08,
+            b'c' => return true,
+            b'e' | b'E' => 0x1b,
+            b'f' => 0x0c,
+            b'n' => b'\n',
+            b'r' => b'\r',
+            b't' => b'\t',
+            b'v' => 0x0b,
+            b'0' => number(word, &mut idx, 8, 3).unwrap_or_default() as u8,
+            b'x' => match number(word, &mut idx, 16, 2) {
+                Some(value) => value as u8,
+                None => {
+                    output.extend(b"\\x");
+                    continue;
+                }
+            },
+            byte @ (b'u' | b'U') => {
+                let limit = if byte == b'u' { 4 } else { 8 };
+                match number(word, &mut idx, 16, limit) {
+                    Some(value) => {
+                        let char = char::from_u32(value).unwrap_or(char::REPLACEMENT_CHARACTER);
+                        output.extend(char.to_string().bytes());
+                    }
+                    None => output.extend([b'\\', byte]),
+                }
+                continue;
+            }
+            other => {
+                output.extend([b'\\', other]);
+                continue;
+            }
+        };
+        output.push(escaped);
+    }
+    false
+}
+
+fn number(word: &[u8], idx: &mut usize, radix: u32, limit: usize) -> Option<u32> {
+    let mut value = None;
+    for _ in 0..limit {
+        let Some(digit) = word
+            .get(*idx)
+            .and_then(|byte| (*byte as char).to_digit(radix))
+        else {
+            break;
+        };
+        value = Some(value.unwrap_or(0) * radix + digit);
+        *idx += 1;
+    }
+    value
+}
+
+#[cfg(test)]
+mod test {
+    use crate::echo::unescape;
+
+    fn check(word: &str, expected: &[u8], stopped: bool) {
+        let mut output = Vec::new();
+        assert_eq!(unescape(word.as_bytes(), &mut output), stopped);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn check_escapes() {
+        check("a\\tb\\n", b"a\tb\n", false);
+        check("\\0101\\101\\x41\\x", b"A\\101A\\x", false);
+        check("\\u0416\\e\\", "Ж\x1b\\".as_bytes(), false);
+        check("a\\cb", b"a", true);
+    }
+}
@@ -0,0 +1,613 @@
+use {
+    pseudobash_regex::glob::Pattern,
+    std::{
+        cmp::Ordering,
+        fs::Metadata,
+        io::{BufWriter, Write},
+        os::unix::fs::{FileTypeExt, MetadataExt},
+        process::Command,
+        time::SystemTime,
+    },
+};
+
+// `-exec ... {} +` runs the command once this many bytes of paths are queued.
+const BATCH_SIZE: usize = 128 * 1024;
+
+enum Expr {
+    True,
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Name(Pattern),
+    Path(Pattern),
+    Type(Vec<char>),
+    // Size in units of the given number of bytes, rounded up.
+    Size(Ordering, u64, u64),
+    // Age in whole days.
+    Mtime(Ordering, i64),
+    Newer(i64, i64),
+    Print,
+    Print0,
+    Delete,
+    Prune,
+    // `batch` indexes the paths queued for a `-exec ... {} +`.
+    Exec {
+        command: Vec<String>,
+        batch: Option<usize>,
+    },
+}
+
+struct Find {
+    paths: Vec<String>,
+    min_depth: usize,
+    max_depth: usize,
+    depth_first: bool,
+    // Set by `-prune` to skip the contents of the directory being evaluated.
+    pruned: bool,
+    now: i64,
+    batches: Vec<(Vec<String>, usize)>,
+    status: i32,
+}
+
+struct Parser {
+    args: Vec<String>,
+    pos: usize,
+    find: Find,
+    action: bool,
+}
+
+pub fn run(args: Vec<String>) -> i32 {
+    let (mut find, expr) = match parse(args.into_iter().skip(1).collect()) {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            eprintln!("find: {}", message);
+            return 1;
+        }
+    };
+    let mut stdout = BufWriter::new(std::io::stdout().lock());
+    if let Err(e) = find.search(&expr, &mut stdout).and_then(|_| stdout.flush()) {
+        eprintln!("find: write error: {}", crate::strerror(&e));
+        return 1;
+    }
+    find.status
+}
+
+// Paths come first, the expression starts at the first argument that looks
+// like an option, `(` or `!`.
+fn parse(args: Vec<String>) -> Result<(Find, Expr), String> {
+    let start = args
+        .iter()
+        .position(|arg| arg.len() > 1 && arg.starts_with('-') || arg == "(" || arg == "!")
+        .unwrap_or(args.len());
+    let mut paths = args[..start].to_vec();
+    if paths.is_empty() {
+        paths.push(".".to_string());
+    }
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs() as i64);
+    let mut parser = Parser {
+        args: args[start..].to_vec(),
+        pos: 0,
+        find: Find {
+            paths,
+            min_depth: 0,
+            max_depth: usize::MAX,
+            depth_first: false,
+            pruned: false,
+            now,
+            batches: vec![],
+            status: 0,
+        },
+        action: false,
+    };
+
+    let mut expr = match parser.args.is_empty() {
+        true => Expr::True,
+        false => parser.parse_or()?,
+    };
+    if let Some(arg) = parser.peek() {
+        return Err(match arg {
+            ")" => "you have too many ')'".to_string(),
+            arg => format!("unexpected argument `{}'", arg),
+        });
+    }
+    if !parser.action {
+        expr = Expr::And(Box::new(expr), Box::new(Expr::Print));
+    }
+    Ok((parser.find, expr))
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.args.get(self.pos).map(|arg| arg.as_str())
+    }
+
+    fn value(&mut self, predicate: &str) -> Result<String, String> {
+        let value = self.args.get(self.pos).cloned();
+        self.pos += 1;
+        value.ok_or(format!("missing argument to `{}'", predicate))
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_and()?;
+        while let Some("-o" | "-or") = self.peek() {
+            self.pos += 1;
+            let right = self.parse_and()?;
+            left = Expr::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_not()?;
+        loop {
+            match self.peek() {
+                None | Some(")" | "-o" | "-or") => return Ok(left),
+                Some("-a" | "-and") => self.pos += 1,
+                Some(_) => {}
+            }
+            let right = self.parse_not()?;
+            left = Expr::And(Box::new(left), Box::new(right));
+        }
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, String> {
+        match self.peek() {
+            Some("!" | "-not") => {
+                self.pos += 1;
+                Ok(Expr::Not(Box::new(self.parse_not()?)))
+            }
+            _ => self.parse_primary(),
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, String> {
+        let Some(arg) = self.args.get(self.pos).cloned() else {
+            let last = self
+                .pos
+                .checked_sub(1)
+                .map_or("", |last| self.args[last].as_str());
+            return Err(format!("expected an expression after '{}'", last));
+        };
+        self.pos += 1;
+        let expr = match arg.as_str() {
+            "(" => {
+                let expr = self.parse_or()?;
+                if self.peek() != Some(")") {
+                    return Err("invalid expression; I was expecting to find a ')' \
+                        somewhere but did not see one."
+                        .to_string());
+                }
+                self.pos += 1;
+                expr
+            }
+            ")" => return Err("invalid expression; empty parentheses are not allowed.".into()),
+            "-a" | "-and" | "-o" | "-or" => {
+                return Err(format!(
+                    "invalid expression; you have used a binary operator '{}' with nothing before it.",
+                    arg
+                ));
+            }
+            "-name" | "-iname" => Expr::Name(Pattern::escaped(&self.value(&arg)?, arg == "-iname")),
+            "-path" | "-ipath" | "-wholename" => {
+                Expr::Path(Pattern::escaped(&self.value(&arg)?, arg == "-ipath"))
+            }
+            "-type" => {
+                let value = self.value(&arg)?;
+                let kinds: Vec<char> = value.split(',').flat_map(|kind| kind.chars()).collect();
+                let valid = value
+                    .split(',')
+                    .all(|kind| kind.len() == 1 && "bcdpfls".contains(kind));
+                if !valid {
+                    return Err(format!("Unknown argument to -type: {}", value));
+                }
+                Expr::Type(kinds)
+            }
+            "-size" => {
+                let value = self.value(&arg)?;
+                let (ordering, number) = comparison(&value);
+                let (number, unit) = match number.char_indices().last() {
+                    Some((idx, unit)) if unit.is_ascii_alphabetic() => {
+                        let unit = match unit {
+                            'b' => 512,
+                            'c' => 1,
+                            'w' => 2,
+                            'k' => 1024,
+                            'M' => 1024 * 1024,
+                            'G' => 1024 * 1024 * 1024,
+                            _ => return Err(format!("invalid -size type `{}'", unit)),
+                        };
+                        (&number[..idx], unit)
+                    }
+                    _ => (number, 512),
+                };
+                match number.parse() {
+                    Ok(number) => Expr::Size(ordering, number, unit),
+                    Err(_) => return Err(format!("invalid argument `{}' to `-size'", value)),
+                }
+            }
+            "-mtime" => {
+                let value = self.value(&arg)?;
+                let (ordering, number) = comparison(&value);
+                match number.parse() {
+                    Ok(days) => Expr::Mtime(ordering, days),
+                    Err(_) => return Err(format!("invalid argument `{}' to `-mtime'", value)),
+                }
+            }
+            "-newer" => {
+                let reference = self.value(&arg)?;
+                match std::fs::metadata(&reference) {
+                    Ok(metadata) => Expr::Newer(metadata.mtime(), metadata.mtime_nsec()),
+                    Err(e) => return Err(format!("'{}': {}", reference, crate::strerror(&e))),
+                }
+            }
+            "-maxdepth" | "-mindepth" => {
+                let value = self.value(&arg)?;
+                let Ok(depth) = value.parse() else {
+                    return Err(format!(
+                        "Expected a positive decimal integer argument to {}, but got '{}'",
+                        arg, value
+                    ));
+                };
+                match arg.as_str() {
+                    "-maxdepth" => self.find.max_depth = depth,
+                    _ => self.find.min_depth = depth,
+                }
+                Expr::True
+            }
+            "-prune" => Expr::Prune,
+            "-depth" => {
+                self.find.depth_first = true;
+                Expr::True
+            }
+            "-print" | "-print0" => {
+                self.action = true;
+                match arg.as_str() {
+                    "-print" => Expr::Print,
+                    _ => Expr::Print0,
+                }
+            }
+            // Contents have to go before their directory.
+            "-delete" => {
+                self.action = true;
+                self.find.depth_first = true;
+                Expr::Delete
+            }
+            "-exec" => {
+                self.action = true;
+                let mut command = Vec::new();
+                let batch = loop {
+                    match self.args.get(self.pos) {
+                        None => return Err("missing argument to `-exec'".to_string()),
+                        Some(end) if end == ";" => break None,
+                        Some(end)
+                            if end == "+" && command.last().is_some_and(|arg| arg == "{}") =>
+                        {
+                            command.pop();
+                            self.find.batches.push((vec![], 0));
+                            break Some(self.find.batches.len() - 1);
+                        }
+                        Some(arg) => command.push(arg.clone()),
+                    }
+                    self.pos += 1;
+                };
+                self.pos += 1;
+                if command.is_empty() {
+                    return Err("missing argument to `-exec'".to_string());
+                }
+                Expr::Exec { command, batch }
+            }
+            arg if arg.starts_with('-') => return Err(format!("unknown predicate `{}'", arg)),
+            arg => return Err(format!("paths must precede expression: `{}'", arg)),
+        };
+        Ok(expr)
+    }
+}
+
+// `+N` is more than N, `-N` less than N and `N` exactly N.
+fn comparison(value: &str) -> (Ordering, &str) {
+    match value.as_bytes().first() {
+        Some(b'+') => (Ordering::Greater, &value[1..]),
+        Some(b'-') => (Ordering::Less, &value[1..]),
+        _ => (Ordering::Equal, value),
+    }
+}
+
+impl Find {
+    fn search(&mut self, expr: &Expr, writer: &mut impl Write) -> std::io::Result<()> {
+        for path in self.paths.clone() {
+            self.visit(expr, &path, 0, writer)?;
+        }
+        for batch in 0..self.batches.len() {
+            self.run_batch(expr, batch, writer)?;
+        }
+        Ok(())
+    }
+
+    fn visit(
+        &mut self,
+        expr: &Expr,
+        path: &str,
+        depth: usize,
+        writer: &mut impl Write,
+    ) -> std::io::Result<()> {
+        let metadata = match std::fs::symlink_metadata(path) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                self.report(path, &e);
+                return Ok(());
+            }
+        };
+        let selected = depth >= self.min_depth;
+        self.pruned = false;
+        if selected && !self.depth_first {
+            self.eval(expr, path, &metadata, writer)?;
+        }
+        if metadata.is_dir() && depth < self.max_depth && !self.pruned {
+            let entries = std::fs::read_dir(path).and_then(|entries| {
+                entries
+                    .map(|entry| entry.map(|entry| entry.file_name()))
+                    .collect::<std::io::Result<Vec<_>>>()
+            });
+            match entries {
+                Ok(mut entries) => {
+                    entries.sort();
+                    for entry in entries {
+                        let child = match path.ends_with('/') {
+                            true => format!("{}{}", path, entry.to_string_lossy()),
+                            false => format!("{}/{}", path, entry.to_string_lossy()),
+                        };
+                        self.visit(expr, &child, depth + 1, writer)?;
+                    }
+                }
+                Err(e) => self.report(path, &e),
+            }
+        }
+        if selected && self.depth_first {
+            self.eval(expr, path, &metadata, writer)?;
+        }
+        Ok(())
+    }
+
+    fn eval(
+        &mut self,
+        expr: &Expr,
+        path: &str,
+        metadata: &Metadata,
+        writer: &mut impl Write,
+    ) -> std::io::Result<bool> {
+        let matched = match expr {
+            Expr::True => true,
+            Expr::And(left, right) => {
+                self.eval(left, path, metadata, writer)?
+                    && self.eval(right, path, metadata, writer)?
+            }
+            Expr::Or(left, right) => {
+                self.eval(left, path, metadata, writer)?
+                    || self.eval(right, path, metadata, writer)?
+            }
+            Expr::Not(expr) => !self.eval(expr, path, metadata, writer)?,
+            Expr::Name(pattern) => pattern.matches(base_name(path)),
+            Expr::Path(pattern) => pattern.matches(path),
+            Expr::Type(kinds) => kinds.contains(&kind(metadata)),
+            Expr::Size(ordering, size, unit) => {
+                metadata.len().div_ceil(*unit).cmp(size) == *ordering
+            }
+            Expr::Mtime(ordering, days) => {
+                (self.now - metadata.mtime()).div_euclid(86400).cmp(days) == *ordering
+            }
+            Expr::Newer(seconds, nanoseconds) => {
+                (metadata.mtime(), metadata.mtime_nsec()) > (*seconds, *nanoseconds)
+            }
+            Expr::Print => {
+                writeln!(writer, "{}", path)?;
+                true
+            }
+            Expr::Print0 => {
+                write!(writer, "{}\0", path)?;
+                true
+            }
+            Expr::Delete => self.delete(path, metadata),
+            Expr::Prune => {
+                self.pruned = !self.depth_first;
+                true
+            }
+            Expr::Exec {
+                command,
+                batch: None,
+            } => {
+                let command: Vec<String> =
+                    command.iter().map(|arg| arg.replace("{}", path)).collect();
+                writer.flush()?;
+                self.execute(&command)
+            }
+            Expr::Exec {
+                batch: Some(batch), ..
+            } => {
+                let (paths, size) = &mut self.batches[*batch];
+                paths.push(path.to_string());
+                *size += path.len() + 1;
+                if *size >= BATCH_SIZE {
+                    self.run_batch(expr, *batch, writer)?;
+                }
+                true
+            }
+        };
+        Ok(matched)
+    }
+
+    // Runs the command of `-exec ... {} +` number `batch`, found below `expr`,
+    // on the paths queued for it.
+    fn run_batch(
+        &mut self,
+        expr: &Expr,
+        batch: usize,
+        writer: &mut impl Write,
+    ) -> std::io::Result<()> {
+        let (paths, _) = std::mem::take(&mut self.batches[batch]);
+        if paths.is_empty() {
+            return Ok(());
+        }
+        let Some(command) = batch_command(expr, batch) else {
+            return Ok(());
+        };
+        let mut command = command.to_vec();
+        command.extend(paths);
+        writer.flush()?;
+        if !self.execute(&command) {
+            self.status = 1;
+        }
+        Ok(())
+    }
+
+    // Only a failing `-exec ... +` changes the exit status, for `-exec ... ;`
+    // failing is just a false test.
+    fn execute(&self, command: &[String]) -> bool {
+        match Command::new(&command[0]).args(&command[1..]).status() {
+            Ok(status) => status.success(),
+            Err(e) => {
+                eprintln!("find: '{}': {}", command[0], crate::strerror(&e));
+                false
+            }
+        }
+    }
+
+    // `find . -delete` removes everything below `.` but not `.` itself.
+    fn delete(&mut self, path: &str, metadata: &Metadata) -> bool {
+        if path == "." {
+            return true;
+        }
+        let result = match metadata.is_dir() {
+            true => std::fs::remove_dir(path),
+            false => std::fs::remove_file(path),
+        };
+        match result {
+            Ok(_) => true,
+            Err(e) => {
+                eprintln!("find: cannot delete '{}': {}", path, crate::strerror(&e));
+                self.status = 1;
+                false
+            }
+        }
+    }
+
+    fn report(&mut self, path: &str, e: &std::io::Error) {
+        eprintln!("find: '{}': {}", path, crate::strerror(e));
+        self.status = 1;
+    }
+}
+
+fn batch_command(expr: &Expr, batch: usize) -> Option<&[String]> {
+    match expr {
+        Expr::Exec {
+            command,
+            batch: Some(found),
+        } if *found == batch => Some(command),
+        Expr::And(left, right) | Expr::Or(left, right) => {
+            batch_command(left, batch).or_else(|| batch_command(right, batch))
+        }
+        Expr::Not(expr) => batch_command(expr, batch),
+        _ => None,
+    }
+}
+
+fn base_name(path: &str) -> &str {
+    let trimmed = path.trim_end_matches('/');
+    match trimmed.rfind('/') {
+        Some(idx) => &trimmed[idx + 1..],
+        None if trimmed.is_empty() && !path.is_empty() => "/",
+        None => trimmed,
+    }
+}
+
+fn kind(metadata: &Metadata) -> char {
+    let kind = metadata.file_type();
+    match () {
+        _ if kind.is_dir() => 'd',
+        _ if kind.is_symlink() => 'l',
+        _ if kind.is_fifo() => 'p',
+        _ if kind.is_socket() => 's',
+        _ if kind.is_block_device() => 'b',
+        _ if kind.is_char_device() => 'c',
+        _ => 'f',
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::find::{base_name, parse};
+
+    #[test]
+    fn check_find() {
+        let root = std::env::temp_dir().join(format!("pseudobash-find-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("src/sub")).unwrap();
+        std::fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(root.join("src/sub/Notes.TXT"), vec![b'x'; 2000]).unwrap();
+        std::fs::write(root.join("empty"), "").unwrap();
+        let root = root.to_string_lossy().into_owned();
+
+        let find = |args: &[&str]| -> Result<String, String> {
+            let mut full = vec![root.clone()];
+            full.extend(args.iter().map(|arg| arg.to_string()));
+            let (mut find, expr) = parse(full)?;
+            let mut output = Vec::new();
+            find.search(&expr, &mut output).unwrap();
+            Ok(String::from_utf8(output).unwrap().replace(&root, "R"))
+        };
+        assert_eq!(
+            find(&[]).unwrap(),
+            "R\nR/empty\nR/src\nR/src/main.rs\nR/src/sub\nR/src/sub/Notes.TXT\n"
+        );
+        assert_eq!(find(&["-name", "*.rs"]).unwrap(), "R/src/main.rs\n");
+        assert_eq!(find(&["-iname", "*.txt"]).unwrap(), "R/src/sub/Notes.TXT\n");
+        assert_eq!(
+            find(&["-type", "d", "-mindepth", "1"]).unwrap(),
+            "R/src\nR/src/sub\n"
+        );
+        assert_eq!(
+            find(&["-maxdepth", "1", "-type", "f"]).unwrap(),
+            "R/empty\n"
+        );
+        assert_eq!(
+            find(&["-type", "f", "-size", "+3"]).unwrap(),
+            "R/src/sub/Notes.TXT\n"
+        );
+        assert_eq!(find(&["-size", "-1c", "-print0"]).unwrap(), "R/empty\0");
+        assert_eq!(find(&["-mtime", "+0"]).unwrap(), "");
+        assert_eq!(
+            find(&[
+                "-path", "*/src/*", "!", "(", "-type", "d", "-o", "-name", "*.rs", ")"
+            ])
+            .unwrap(),
+            "R/src/sub/Notes.TXT\n"
+        );
+        assert_eq!(
+            find(&["-name", "empty", "-o", "-name", "sub", "-print"]).unwrap(),
+            "R/src/sub\n"
+        );
+        assert_eq!(
+            find(&["-name", "sub", "-prune", "-o", "-print"]).unwrap(),
+            "R\nR/empty\nR/src\nR/src/main.rs\n"
+        );
+        assert_eq!(
+            find(&["-name"]),
+            Err("missing argument to `-name'".to_string())
+        );
+        assert_eq!(
+            find(&["-bogus"]),
+            Err("unknown predicate `-bogus'".to_string())
+        );
+        assert!(find(&["(", "-type", "f"]).is_err());
+        assert!(find(&["-type", "x"]).is_err());
+        assert!(find(&["-exec", "true", "{}"]).is_err());
+
+        assert_eq!(find(&["-type", "f", "-delete"]).unwrap(), "");
+        assert_eq!(find(&[]).unwrap(), "R\nR/src\nR/src/sub\n");
+        std::fs::remove_dir_all(root).unwrap();
+
+        assert_eq!(base_name("a/b/"), "b");
+        assert_eq!(base_name("/"), "/");
+        assert_eq!(base_name("."), ".");
+    }
+}
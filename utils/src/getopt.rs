@@ -0,0 +1,149 @@
+use std::io::{BufRead, BufReader};
+
+pub enum Opt {
+    Short(char),
+    Long(String),
+    Operand(String),
+}
+
+impl Opt {
+    pub fn is(&self, short: char, long: &str) -> bool {
+        match self {
+            Opt::Short(option) => *option == short,
+            Opt::Long(option) => option == long,
+            Opt::Operand(_) => false,
+        }
+    }
+}
+
+// Splits arguments into options and operands. Short options may be clustered,
+// options may follow operands, and an option's value is taken with `value`,
+// either from the rest of the cluster, after `=` or from the next argument.
+pub struct Args {
+    args: std::vec::IntoIter<String>,
+    cluster: Vec<char>,
+    long_value: Option<String>,
+    options_done: bool,
+}
+
+impl Args {
+    pub fn new(args: Vec<String>) -> Self {
+        let mut args = args.into_iter();
+        args.next();
+        Self {
+            args,
+            cluster: vec![],
+            long_value: None,
+            options_done: false,
+        }
+    }
+
+    pub fn value(&mut self) -> Option<String> {
+        if let Some(value) = self.long_value.take() {
+            return Some(value);
+        }
+        match self.cluster.is_empty() {
+            true => self.args.next(),
+            false => Some(self.cluster.drain(..).rev().collect()),
+        }
+    }
+
+    // Like `value`, but never takes the next argument, for options such as
+    // `--color[=WHEN]` whose value is optional.
+    pub fn attached_value(&mut self) -> Option<String> {
+        match self.long_value.is_some() || !self.cluster.is_empty() {
+            true => self.value(),
+            false => None,
+        }
+    }
+
+    pub fn cluster_rest(&mut self) -> String {
+        self.cluster.drain(..).rev().collect()
+    }
+
+    // The arguments left after an operand, unparsed, for applets such as
+    // xargs whose options end at the command they run.
+    pub fn rest(&mut self) -> Vec<String> {
+        self.args.by_ref().collect()
+    }
+}
+
+impl Iterator for Args {
+    type Item = Opt;
+
+    fn next(&mut self) -> Option<Opt> {
+        if let Some(short) = self.cluster.pop() {
+            return Some(Opt::Short(short));
+        }
+        let arg = self.args.next()?;
+        if self.options_done || arg == "-" || !arg.starts_with('-') {
+            return Some(Opt::Operand(arg));
+        }
+        if arg == "--" {
+            self.options_done = true;
+            return self.next();
+        }
+        if let Some(long) = arg.strip_prefix("--") {
+            return Some(match long.split_once('=') {
+                Some((name, value)) => {
+                    self.long_value = Some(value.to_string());
+                    Opt::Long(name.to_string())
+                }
+                None => Opt::Long(long.to_string()),
+            });
+        }
+        self.cluster = arg[1..].chars().rev().collect();
+        self.next()
+    }
+}
+
+pub fn unknown(applet: &str, opt: &Opt) -> i32 {
+    match opt {
+        Opt::Short(short) => eprintln!("{}: invalid option -- '{}'", applet, short),
+        Opt::Long(long) => eprintln!("{}: unrecognized option '--{}'", applet, long),
+        Opt::Operand(operand) => eprintln!("{}: extra operand '{}'", applet, operand),
+    }
+    1
+}
+
+pub fn missing(applet: &str, option: &str) -> i32 {
+    eprintln!("{}: option requires an argument -- '{}'", applet, option);
+    1
+}
+
+pub fn open(path: &str) -> std::io::Result<Box<dyn BufRead>> {
+    match path {
+        "-" => Ok(Box::new(std::io::stdin().lock())),
+        path => Ok(Box::new(BufReader::new(std::fs::File::open(path)?))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::getopt::{Args, Opt};
+
+    #[test]
+    fn check_args() {
+        let args = [
+            "head",
+            "-qn5",
+            "a",
+            "--lines=-2",
+            "-c",
+            "7",
+            "--",
+            "-x",
+            "-",
+        ];
+        let mut args = Args::new(args.iter().map(|arg| arg.to_string()).collect());
+        let mut seen = vec![];
+        while let Some(opt) = args.next() {
+            seen.push(match opt {
+                Opt::Short('n' | 'c') | Opt::Long(_) => args.value().unwrap(),
+                Opt::Short(short) => short.to_string(),
+                Opt::Operand(operand) => format!("[{}]", operand),
+            });
+        }
+        assert_eq!(seen, ["q", "5", "[a]", "-2", "7", "[-x]", "[-]"]);
+    }
+}
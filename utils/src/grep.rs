@@ -0,0 +1,560 @@
+use {
+    crate::{
+        getopt::{self, Args, Opt},
+        regex::{Options, Regex, Syntax},
+    },
+    std::{
+        collections::VecDeque,
+        io::{BufRead, BufWriter, IsTerminal, Write},
+        path::Path,
+    },
+};
+
+const MATCH_COLOR: &str = "01;31";
+const FILE_COLOR: &str = "35";
+const LINE_COLOR: &str = "32";
+const SEPARATOR_COLOR: &str = "36";
+
+#[derive(Clone, Copy, Default, PartialEq)]
+enum Mode {
+    #[default]
+    Lines,
+    Count,
+    FilesWithMatches,
+    FilesWithoutMatch,
+    Quiet,
+}
+
+#[derive(Default)]
+struct Grep {
+    regexes: Vec<Regex>,
+    mode: Mode,
+    invert: bool,
+    only_matching: bool,
+    line_number: bool,
+    with_filename: bool,
+    before: usize,
+    after: usize,
+    color: bool,
+    no_messages: bool,
+    // `None` when not recursive, otherwise whether symlinks are followed.
+    recursive: Option<bool>,
+    matched: bool,
+    failed: bool,
+    separated: bool,
+}
+
+// A line split into characters for matching, with the byte offset of each.
+struct Text {
+    chars: Vec<char>,
+    offsets: Vec<usize>,
+    binary: bool,
+}
+
+pub fn run(args: Vec<String>) -> i32 {
+    let mut grep = Grep::default();
+    let mut options = Options::default();
+    let mut patterns: Option<Vec<String>> = None;
+    let mut with_filename = None;
+    let (mut before, mut after, mut context) = (None, None, None);
+    let mut operands = Vec::new();
+    let mut args = Args::new(args);
+    while let Some(opt) = args.next() {
+        match opt {
+            opt if opt.is('E', "extended-regexp") => options.syntax = Syntax::Extended,
+            opt if opt.is('F', "fixed-strings") => options.syntax = Syntax::Fixed,
+            opt if opt.is('G', "basic-regexp") => options.syntax = Syntax::Basic,
+            opt if opt.is('e', "regexp") => {
+                let Some(value) = args.value() else {
+                    getopt::missing("grep", "e");
+                    return 2;
+                };
+                patterns
+                    .get_or_insert_default()
+                    .extend(value.split('\n').map(String::from));
+            }
+            opt if opt.is('f', "file") => {
+                let Some(file) = args.value() else {
+                    getopt::missing("grep", "f");
+                    return 2;
+                };
+                let text = match file.as_str() {
+                    "-" => std::io::read_to_string(std::io::stdin()),
+                    file => std::fs::read_to_string(file),
+                };
+                match text {
+                    Ok(text) => patterns
+                        .get_or_insert_default()
+                        .extend(text.lines().map(String::from)),
+                    Err(e) => {
+                        eprintln!("grep: {}: {}", file, crate::strerror(&e));
+                        return 2;
+                    }
+                }
+            }
+            opt if opt.is('i', "ignore-case") || opt.is('y', "ignore-case") => {
+                options.ignore_case = true
+            }
+            opt if opt.is('w', "word-regexp") => options.whole_word = true,
+            opt if opt.is('x', "line-regexp") => options.whole_line = true,
+            opt if opt.is('v', "invert-match") => grep.invert = true,
+            opt if opt.is('c', "count") => grep.mode = Mode::Count,
+            opt if opt.is('l', "files-with-matches") => grep.mode = Mode::FilesWithMatches,
+            opt if opt.is('L', "files-without-match") => grep.mode = Mode::FilesWithoutMatch,
+            opt if opt.is('q', "quiet") || opt.is('q', "silent") => grep.mode = Mode::Quiet,
+            opt if opt.is('s', "no-messages") => grep.no_messages = true,
+            opt if opt.is('o', "only-matching") => grep.only_matching = true,
+            opt if opt.is('n', "line-number") => grep.line_number = true,
+            opt if opt.is('H', "with-filename") => with_filename = Some(true),
+            opt if opt.is('h', "no-filename") => with_filename = Some(false),
+            opt if opt.is('r', "recursive") => grep.recursive = Some(false),
+            opt if opt.is('R', "dereference-recursive") => grep.recursive = Some(true),
+            opt if opt.is('A', "after-context")
+                || opt.is('B', "before-context")
+                || opt.is('C', "context")
+                || matches!(opt, Opt::Short('0'..='9')) =>
+            {
+                let value = match opt {
+                    Opt::Short(digit) if digit.is_ascii_digit() => {
+                        Some(format!("{}{}", digit, args.cluster_rest()))
+                    }
+                    _ => args.value(),
+                };
+                let Some(value) = value else {
+                    getopt::missing("grep", "C");
+                    return 2;
+                };
+                let Ok(lines) = value.parse() else {
+                    eprintln!("grep: {}: invalid context length argument", value);
+                    return 2;
+                };
+                match opt {
+                    opt if opt.is('A', "after-context") => after = Some(lines),
+                    opt if opt.is('B', "before-context") => before = Some(lines),
+                    _ => context = Some(lines),
+                }
+            }
+            Opt::Long(ref long) if long == "color" || long == "colour" => {
+                grep.color = match args.attached_value().as_deref() {
+                    None | Some("auto" | "tty" | "if-tty") => {
+                        std::io::stdout().is_terminal()
+                            && std::env::var("TERM").is_ok_and(|term| term != "dumb")
+                    }
+                    Some("always" | "yes" | "force") => true,
+                    Some("never" | "no" | "none") => false,
+                    Some(other) => {
+                        eprintln!("grep: invalid argument '{}' for '--color'", other);
+                        return 2;
+                    }
+                }
+            }
+            Opt::Operand(operand) => operands.push(operand),
+            opt => {
+                getopt::unknown("grep", &opt);
+                return 2;
+            }
+        }
+    }
+
+    let patterns = match patterns {
+        Some(patterns) => patterns,
+        None if !operands.is_empty() => {
+            let pattern = operands.remove(0);
+            pattern.split('\n').map(String::from).collect()
+        }
+        None => {
+            eprintln!("Usage: grep [OPTION]... PATTERNS [FILE]...");
+            return 2;
+        }
+    };
+    for pattern in &patterns {
+        match Regex::new(pattern, options) {
+            Ok(regex) => grep.regexes.push(regex),
+            Err(e) => {
+                eprintln!("grep: {}", e);
+                return 2;
+            }
+        }
+    }
+    if !grep.only_matching {
+        grep.before = before.or(context).unwrap_or(0);
+        grep.after = after.or(context).unwrap_or(0);
+    }
+
+    let implicit = operands.is_empty();
+    if implicit {
+        operands.push(if grep.recursive.is_some() { "." } else { "-" }.to_string());
+    }
+    grep.with_filename = with_filename.unwrap_or(
+        operands.len() > 1
+            || grep.recursive.is_some()
+                && operands.iter().any(|operand| Path::new(operand).is_dir()),
+    );
+
+    let mut stdout = BufWriter::new(std::io::stdout().lock());
+    for operand in &operands {
+        let name = if implicit && operand == "." {
+            ""
+        } else {
+            operand
+        };
+        grep.visit(Path::new(operand), name, true, &mut stdout);
+        if grep.mode == Mode::Quiet && grep.matched {
+            break;
+        }
+    }
+    if let Err(e) = stdout.flush() {
+        grep.report("write error", &e.to_string());
+    }
+
+    match (grep.matched, grep.failed) {
+        (true, _) if grep.mode == Mode::Quiet => 0,
+        (_, true) => 2,
+        (true, false) => 0,
+        (false, false) => 1,
+    }
+}
+
+impl Text {
+    fn new(line: &[u8]) -> Self {
+        let mut text = Text {
+            chars: Vec::with_capacity(line.len()),
+            offsets: Vec::with_capacity(line.len() + 1),
+            binary: line.contains(&0),
+        };
+        let mut offset = 0;
+        for chunk in line.utf8_chunks() {
+            for (idx, char) in chunk.valid().char_indices() {
+                text.chars.push(char);
+                text.offsets.push(offset + idx);
+            }
+            offset += chunk.valid().len();
+            for _ in chunk.invalid() {
+                text.chars.push(char::REPLACEMENT_CHARACTER);
+                text.offsets.push(offset);
+                offset += 1;
+                text.binary = true;
+            }
+        }
+        text.offsets.push(offset);
+        text
+    }
+}
+
+impl Grep {
+    // Searches a file, or with -r everything below a directory. `name` is the
+    // path as it is printed, empty for the implicit `.` of `grep -r`.
+    fn visit(&mut self, path: &Path, name: &str, top: bool, writer: &mut impl Write) {
+        if top && name == "-" {
+            let result = self.search(&mut std::io::stdin().lock(), "(standard input)", writer);
+            if let Err(e) = result {
+                self.report("(standard input)", &crate::strerror(&e));
+            }
+            return;
+        }
+        let metadata = match top || self.recursive == Some(true) {
+            true => std::fs::metadata(path),
+            false => std::fs::symlink_metadata(path),
+        };
+        let metadata = match metadata {
+            Ok(metadata) => metadata,
+            Err(e) => return self.report(name, &crate::strerror(&e)),
+        };
+
+        if metadata.is_dir() {
+            if self.recursive.is_none() {
+                return self.report(name, "Is a directory");
+            }
+            let entries = std::fs::read_dir(path).and_then(|entries| {
+                entries
+                    .map(|entry| entry.map(|entry| entry.file_name()))
+                    .collect::<std::io::Result<Vec<_>>>()
+            });
+            let mut entries = match entries {
+                Ok(entries) => entries,
+                Err(e) => return self.report(name, &crate::strerror(&e)),
+            };
+            entries.sort();
+            for entry in entries {
+                let entry = entry.to_string_lossy();
+                let child = match name {
+                    "" => entry.to_string(),
+                    name => format!("{}/{}", name.trim_end_matches('/'), entry),
+                };
+                self.visit(&path.join(&*entry), &child, false, writer);
+                if self.mode == Mode::Quiet && self.matched {
+                    return;
+                }
+            }
+        } else if top || metadata.is_file() {
+            let result = getopt::open(&path.to_string_lossy())
+                .and_then(|mut reader| self.search(&mut reader, name, writer));
+            if let Err(e) = result {
+                self.report(name, &crate::strerror(&e));
+            }
+        }
+    }
+
+    fn report(&mut self, name: &str, message: &str) {
+        self.failed = true;
+        if !self.no_messages {
+            eprintln!("grep: {}: {}", name, message);
+        }
+    }
+
+    fn search(
+        &mut self,
+        reader: &mut dyn BufRead,
+        label: &str,
+        writer: &mut impl Write,
+    ) -> std::io::Result<()> {
+        let mut count = 0;
+        let mut number = 0;
+        let mut line = Vec::new();
+        let mut before = VecDeque::new();
+        let mut after = 0;
+        let mut last = None;
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            number += 1;
+            if line.last() == Some(&b'\n') {
+                line.pop();
+            }
+            let text = Text::new(&line);
+            if self.find(&text.chars, 0).is_some() == self.invert {
+                if self.mode != Mode::Lines {
+                    continue;
+                }
+                if after > 0 {
+                    after -= 1;
+                    self.write_line(writer, label, number, &line, b'-', &mut last)?;
+                } else if self.before > 0 {
+                    before.push_back((number, line.clone()));
+                    if before.len() > self.before {
+                        before.pop_front();
+                    }
+                }
+                continue;
+            }
+
+            count += 1;
+            self.matched = true;
+            match self.mode {
+                Mode::Lines if text.binary => {
+                    writeln!(writer, "grep: {}: binary file matches", label)?;
+                    break;
+                }
+                Mode::Lines if self.only_matching => {
+                    for (from, to) in self.matches(&text.chars) {
+                        self.write_prefix(writer, label, number, b':')?;
+                        let matched = &line[text.offsets[from]..text.offsets[to]];
+                        self.paint(writer, MATCH_COLOR, matched)?;
+                        writer.write_all(b"\n")?;
+                    }
+                }
+                Mode::Lines => {
+                    for (number, line) in before.drain(..) {
+                        self.write_line(writer, label, number, &line, b'-', &mut last)?;
+                    }
+                    self.write_line(writer, label, number, &line, b':', &mut last)?;
+                    after = self.after;
+                }
+                Mode::Count => {}
+                _ => break,
+            }
+        }
+
+        match self.mode {
+            Mode::Count => {
+                if self.with_filename {
+                    self.paint(writer, FILE_COLOR, label.as_bytes())?;
+                    self.paint(writer, SEPARATOR_COLOR, b":")?;
+                }
+                writeln!(writer, "{}", count)
+            }
+            Mode::FilesWithMatches if count > 0 => self.write_name(writer, label),
+            Mode::FilesWithoutMatch if count == 0 => self.write_name(writer, label),
+            _ => Ok(()),
+        }
+    }
+
+    // Leftmost match of any pattern, the longest one if several start there.
+    fn find(&self, chars: &[char], from: usize) -> Option<(usize, usize)> {
+        self.regexes
+            .iter()
+            .filter_map(|regex| regex.find(chars, from))
+            .min_by_key(|(start, end)| (*start, usize::MAX - end))
+    }
+
+    fn matches(&self, chars: &[char]) -> Vec<(usize, usize)> {
+        let mut matches = Vec::new();
+        let mut from = 0;
+        while let Some((start, end)) = self.find(chars, from) {
+            if end > start {
+                matches.push((start, end));
+                from = end;
+            } else if start < chars.len() {
+                from = start + 1;
+            } else {
+                break;
+            }
+        }
+        matches
+    }
+
+    fn write_line(
+        &mut self,
+        writer: &mut impl Write,
+        label: &str,
+        number: u64,
+        line: &[u8],
+        separator: u8,
+        last: &mut Option<u64>,
+    ) -> std::io::Result<()> {
+        if self.before > 0 || self.after > 0 {
+            let adjacent = last.is_some_and(|last| last + 1 == number);
+            if !adjacent && self.separated {
+                self.paint(writer, SEPARATOR_COLOR, b"--")?;
+                writer.write_all(b"\n")?;
+            }
+            *last = Some(number);
+            self.separated = true;
+        }
+        self.write_prefix(writer, label, number, separator)?;
+
+        let mut written = 0;
+        if self.color {
+            let text = Text::new(line);
+            for (from, to) in self.matches(&text.chars) {
+                let (from, to) = (text.offsets[from], text.offsets[to]);
+                writer.write_all(&line[written..from])?;
+                self.paint(writer, MATCH_COLOR, &line[from..to])?;
+                written = to;
+            }
+        }
+        writer.write_all(&line[written..])?;
+        writer.write_all(b"\n")
+    }
+
+    fn write_prefix(
+        &self,
+        writer: &mut impl Write,
+        label: &str,
+        number: u64,
+        separator: u8,
+    ) -> std::io::Result<()> {
+        if self.with_filename {
+            self.paint(writer, FILE_COLOR, label.as_bytes())?;
+            self.paint(writer, SEPARATOR_COLOR, &[separator])?;
+        }
+        if self.line_number {
+            self.paint(writer, LINE_COLOR, number.to_string().as_bytes())?;
+            self.paint(writer, SEPARATOR_COLOR, &[separator])?;
+        }
+        Ok(())
+    }
+
+    fn write_name(&self, writer: &mut impl Write, label: &str) -> std::io::Result<()> {
+        self.paint(writer, FILE_COLOR, label.as_bytes())?;
+        writer.write_all(b"\n")
+    }
+
+    fn paint(&self, writer: &mut impl Write, color: &str, text: &[u8]) -> std::io::Result<()> {
+        if !self.color {
+            return writer.write_all(text);
+        }
+        write!(writer, "\x1b[{}m\x1b[K", color)?;
+        writer.write_all(text)?;
+        writer.write_all(b"\x1b[m\x1b[K")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        grep::{Grep, Mode},
+        regex::{Options, Regex, Syntax},
+    };
+
+    fn check(grep: Grep, patterns: &[&str], input: &str, expected: &str) {
+        let options = Options {
+            syntax: Syntax::Extended,
+            ..Default::default()
+        };
+        let grep = &mut Grep {
+            regexes: patterns
+                .iter()
+                .map(|pattern| Regex::new(pattern, options).unwrap())
+                .collect(),
+            ..grep
+        };
+        let mut output = Vec::new();
+        grep.search(&mut input.as_bytes(), "file", &mut output)
+            .unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    #[test]
+    fn check_grep() {
+        let input = "one\ntwo\nthree\nfour\nfive\nsix\nseven\n";
+        check(Grep::default(), &["o"], input, "one\ntwo\nfour\n");
+        let inverted = Grep {
+            invert: true,
+            line_number: true,
+            ..Default::default()
+        };
+        check(inverted, &["e", "o"], input, "6:six\n");
+        let context = Grep {
+            before: 1,
+            after: 1,
+            ..Default::default()
+        };
+        check(
+            context,
+            &["^t", "^seven"],
+            input,
+            "one\ntwo\nthree\nfour\n--\nsix\nseven\n",
+        );
+        let only = Grep {
+            only_matching: true,
+            with_filename: true,
+            ..Default::default()
+        };
+        check(
+            only,
+            &["e+", "th"],
+            input,
+            "file:e\nfile:th\nfile:ee\nfile:e\nfile:e\nfile:e\n",
+        );
+        let counted = Grep {
+            mode: Mode::Count,
+            ..Default::default()
+        };
+        check(counted, &["i"], input, "2\n");
+        let colored = Grep {
+            color: true,
+            mode: Mode::FilesWithMatches,
+            ..Default::default()
+        };
+        check(colored, &["x"], input, "\x1b[35m\x1b[Kfile\x1b[m\x1b[K\n");
+        let colored = Grep {
+            color: true,
+            ..Default::default()
+        };
+        check(
+            colored,
+            &["i"],
+            "six\n",
+            "s\x1b[01;31m\x1b[Ki\x1b[m\x1b[Kx\n",
+        );
+        check(
+            Grep::default(),
+            &["a"],
+            "a\0b\nab\n",
+            "grep: file: binary file matches\n",
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use {
+    crate::getopt::{self, Args, Opt},
+    std::{
+        collections::VecDeque,
+        io::{BufRead, BufWriter, Read, Write},
+    },
+};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Count {
+    First(u64),
+    AllBut(u64),
+}
+
+pub fn run(args: Vec<String>) -> i32 {
+    let mut lines = true;
+    let mut count = Count::First(10);
+    let mut headers = None;
+    let mut files = Vec::new();
+    let mut args = Args::new(args);
+    while let Some(opt) = args.next() {
+        let value = match opt {
+            opt if opt.is('n', "lines") || opt.is('c', "bytes") => {
+                lines = opt.is('n', "lines");
+                args.value()
+            }
+            Opt::Short(digit) if digit.is_ascii_digit() => {
+                lines = true;
+                Some(format!("{}{}", digit, args.cluster_rest()))
+            }
+            opt if opt.is('q', "quiet") || opt.is('q', "silent") => {
+                headers = Some(false);
+                continue;
+            }
+            opt if opt.is('v', "verbose") => {
+                headers = Some(true);
+                continue;
+            }
+            Opt::Operand(file) => {
+                files.push(file);
+                continue;
+            }
+            opt => return getopt::unknown("head", &opt),
+        };
+        let Some(value) = value else {
+            return getopt::missing("head", if lines { "n" } else { "c" });
+        };
+        match parse_count(&value) {
+            Some(parsed) => count = parsed,
+            None => {
+                let unit = if lines { "lines" } else { "bytes" };
+                eprintln!("head: invalid number of {}: '{}'", unit, value);
+                return 1;
+            }
+        }
+    }
+    if files.is_empty() {
+        files.push("-".to_string());
+    }
+    let headers = headers.unwrap_or(files.len() > 1);
+
+    let mut r_code = 0;
+    let mut stdout = BufWriter::new(std::io::stdout().lock());
+    for (idx, file) in files.iter().enumerate() {
+        let mut reader = match getopt::open(file) {
+            Ok(reader) => reader,
+            Err(e) => {
+                r_code = 1;
+                eprintln!(
+                    "head: cannot open '{}' for reading: {}",
+                    file,
+                    crate::strerror(&e)
+                );
+                continue;
+            }
+        };
+        let result = write_header(&mut stdout, headers, idx == 0, file)
+            .and_then(|_| match lines {
+                true => head_lines(&mut reader, &mut stdout, count),
+                false => head_bytes(&mut reader, &mut stdout, count),
+            })
+            .and_then(|_| stdout.flush());
+        if let Err(e) = result {
+            r_code = 1;
+            eprintln!("head: error reading '{}': {}", file, crate::strerror(&e));
+        }
+    }
+
+    r_code
+}
+
+pub fn write_header(
+    writer: &mut impl Write,
+    headers: bool,
+    first: bool,
+    file: &str,
+) -> std::io::Result<()> {
+    if !headers {
+        return Ok(());
+    }
+    let name = if file == "-" { "standard input" } else { file };
+    match first {
+        true => writeln!(writer, "==> {} <==", name),
+        false => writeln!(writer, "\n==> {} <==", name),
+    }
+}
+
+fn parse_count(value: &str) -> Option<Count> {
+    match value.strip_prefix('-') {
+        Some(count) => count.parse().ok().map(Count::AllBut),
+        None => value.parse().ok().map(Count::First),
+    }
+}
+
+fn head_lines(
+    reader: &mut dyn BufRead,
+    writer: &mut impl Write,
+    count: Count,
+) -> std::io::Result<()> {
+    let mut line = Vec::new();
+    match count {
+        Count::First(count) => {
+            for _ in 0..count {
+                line.clear();
+                if reader.read_until(b'\n', &mut line)? == 0 {
+                    break;
+                }
+                writer.write_all(&line)?;
+            }
+        }
+        Count::AllBut(count) => {
+            let mut kept = VecDeque::new();
+            loop {
+                line.clear();
+                if reader.read_until(b'\n', &mut line)? == 0 {
+                    break;
+                }
+                kept.push_back(line.clone());
+                if kept.len() as u64 > count {
+                    writer.write_all(&kept.pop_front().unwrap())?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn head_bytes(
+    reader: &mut dyn BufRead,
+    writer: &mut impl Write,
+    count: Count,
+) -> std::io::Result<()> {
+    match count {
+        Count::First(count) => {
+            std::io::copy(&mut reader.take(count), writer)?;
+        }
+        Count::AllBut(count) => {
+            let mut kept = VecDeque::new();
+            loop {
+                let chunk = reader.fill_buf()?;
+                if chunk.is_empty() {
+                    break;
+                }
+                kept.extend(chunk);
+                let read = chunk.len();
+                reader.consume(read);
+                let excess = kept.len().saturating_sub(count as usize);
+                let (front, back) = kept.as_slices();
+                let front_len = front.len().min(excess);
+                writer.write_all(&front[..front_len])?;
+                writer.write_all(&back[..excess - front_len])?;
+                kept.drain(..excess);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::head::{Count, head_bytes, head_lines, parse_count};
+
+    fn check(lines: bool, count: &str, input: &str, expected: &str) {
+        let count = parse_count(count).unwrap();
+        let mut output = Vec::new();
+        let mut reader = input.as_bytes();
+        match lines {
+            true => head_lines(&mut reader, &mut output, count).unwrap(),
+            false => head_bytes(&mut reader, &mut output, count).unwrap(),
+        }
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    #[test]
+    fn check_head() {
+        assert_eq!(parse_count("-3"), Some(Count::AllBut(3)));
+        assert_eq!(parse_count("x"), None);
+        check(true, "2", "a\nb\nc\n", "a\nb\n");
+        check(true, "-1", "a\nb\nc", "a\nb\n");
+        check(true, "5", "a\nb", "a\nb");
+        check(false, "3", "abcdef", "abc");
+        check(false, "-2", "abcdef", "abcd");
+    }
+}
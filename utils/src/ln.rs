@@ -0,0 +1,128 @@
+use {
+    crate::getopt::{self, Args, Opt},
+    std::{io::ErrorKind, path::Path},
+};
+
+pub fn run(args: Vec<String>) -> i32 {
+    let mut symbolic = false;
+    let mut force = false;
+    let mut no_dereference = false;
+    let mut verbose = false;
+    let mut operands = Vec::new();
+    for opt in Args::new(args) {
+        match opt {
+            opt if opt.is('s', "symbolic") => symbolic = true,
+            opt if opt.is('f', "force") => force = true,
+            opt if opt.is('n', "no-dereference") => no_dereference = true,
+            opt if opt.is('v', "verbose") => verbose = true,
+            Opt::Operand(operand) => operands.push(operand),
+            opt => return getopt::unknown("ln", &opt),
+        }
+    }
+    if operands.is_empty() {
+        eprintln!("ln: missing file operand");
+        return 1;
+    }
+
+    // With one operand the link goes into the current directory. A last
+    // operand that is a directory, or a link to one without -n, receives
+    // links named after the targets.
+    let directory = match operands.len() {
+        1 => Some(".".to_string()),
+        _ => {
+            let last = operands.last().unwrap();
+            let path = Path::new(last);
+            let is_directory = match no_dereference {
+                true => path
+                    .symlink_metadata()
+                    .is_ok_and(|metadata| metadata.is_dir()),
+                false => path.is_dir(),
+            };
+            match is_directory {
+                true => operands.pop(),
+                false if operands.len() > 2 => {
+                    match std::fs::metadata(last) {
+                        Err(e) => eprintln!("ln: target '{}': {}", last, crate::strerror(&e)),
+                        Ok(_) => eprintln!("ln: target '{}' is not a directory", last),
+                    }
+                    return 1;
+                }
+                false => None,
+            }
+        }
+    };
+    let pairs: Vec<(String, String)> = match directory {
+        Some(directory) => operands
+            .into_iter()
+            .map(|target| {
+                let name = Path::new(&target)
+                    .file_name()
+                    .map_or(target.clone(), |name| name.to_string_lossy().into_owned());
+                let link = match directory.as_str() {
+                    "." => name,
+                    directory => format!("{}/{}", directory.trim_end_matches('/'), name),
+                };
+                (target, link)
+            })
+            .collect(),
+        None => vec![(operands[0].clone(), operands[1].clone())],
+    };
+
+    let mut r_code = 0;
+    for (target, link) in pairs {
+        if !symbolic {
+            match std::fs::metadata(&target) {
+                Ok(metadata) if metadata.is_dir() => {
+                    eprintln!("ln: {}: hard link not allowed for directory", target);
+                    r_code = 1;
+                    continue;
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    eprintln!("ln: failed to access '{}': {}", target, crate::strerror(&e));
+                    r_code = 1;
+                    continue;
+                }
+            }
+        }
+        let result = replace(&link, force).and_then(|_| match symbolic {
+            true => std::os::unix::fs::symlink(&target, &link),
+            false => std::fs::hard_link(&target, &link),
+        });
+        match result {
+            Ok(_) if verbose => {
+                let arrow = if symbolic { "->" } else { "=>" };
+                println!("'{}' {} '{}'", link, arrow, target);
+            }
+            Ok(_) => {}
+            Err(e) if symbolic || e.kind() == ErrorKind::AlreadyExists => {
+                let kind = if symbolic { "symbolic" } else { "hard" };
+                eprintln!(
+                    "ln: failed to create {} link '{}': {}",
+                    kind,
+                    link,
+                    crate::strerror(&e)
+                );
+                r_code = 1;
+            }
+            Err(e) => {
+                eprintln!(
+                    "ln: failed to create hard link '{}' => '{}': {}",
+                    link,
+                    target,
+                    crate::strerror(&e)
+                );
+                r_code = 1;
+            }
+        }
+    }
+
+    r_code
+}
+
+fn replace(link: &str, force: bool) -> std::io::Result<()> {
+    match std::fs::symlink_metadata(link) {
+        Ok(metadata) if force && !metadata.is_dir() => std::fs::remove_file(link),
+        _ => Ok(()),
+    }
+}
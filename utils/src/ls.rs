@@ -0,0 +1,595 @@
+use {
+    crate::{
+        getopt::{self, Args, Opt},
+        time::DateTime,
+    },
+    std::{
+        collections::HashMap,
+        fs::Metadata,
+        io::{BufWriter, IsTerminal, Write},
+        os::unix::fs::{FileTypeExt, MetadataExt},
+        path::{Path, PathBuf},
+        time::SystemTime,
+    },
+};
+
+const SIX_MONTHS: i64 = 31556952 / 2;
+const DEFAULT_COLORS: &str = "di=01;34:ln=01;36:pi=33:so=01;35:do=01;35:bd=01;33:cd=01;33:\
+                              su=37;41:sg=30;43:tw=30;42:ow=34;42:st=37;44:ex=01;32";
+
+#[derive(Clone, Copy, Default, PartialEq)]
+enum Sort {
+    #[default]
+    Name,
+    Time,
+    Size,
+    Unsorted,
+}
+
+#[derive(Clone, Copy, Default, PartialEq)]
+enum Hidden {
+    #[default]
+    Skip,
+    AlmostAll,
+    All,
+}
+
+#[derive(Default)]
+struct Ls {
+    long: bool,
+    human: bool,
+    recursive: bool,
+    directory: bool,
+    classify: bool,
+    columns: bool,
+    width: usize,
+    hidden: Hidden,
+    sort: Sort,
+    reverse: bool,
+    colors: Option<HashMap<String, String>>,
+    now: i64,
+    users: HashMap<u32, String>,
+    groups: HashMap<u32, String>,
+    status: i32,
+    sections: usize,
+    colored: bool,
+}
+
+struct Entry {
+    name: String,
+    path: PathBuf,
+    metadata: Metadata,
+    // What a symbolic link points to, `Err` when the link is dangling.
+    target: Option<Result<Metadata, ()>>,
+}
+
+pub fn run(args: Vec<String>) -> i32 {
+    let mut ls = Ls {
+        columns: std::io::stdout().is_terminal(),
+        width: std::env::var("COLUMNS")
+            .ok()
+            .and_then(|columns| columns.parse().ok())
+            .unwrap_or(80),
+        now: SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs() as i64),
+        ..Default::default()
+    };
+    let mut operands = Vec::new();
+    let mut args = Args::new(args);
+    while let Some(opt) = args.next() {
+        match opt {
+            opt if opt.is('l', "long") => {
+                ls.long = true;
+                ls.columns = false;
+            }
+            opt if opt.is('1', "one-per-line") => ls.columns = false,
+            opt if opt.is('C', "columns") => {
+                ls.columns = true;
+                ls.long = false;
+            }
+            opt if opt.is('a', "all") => ls.hidden = Hidden::All,
+            opt if opt.is('A', "almost-all") => ls.hidden = Hidden::AlmostAll,
+            opt if opt.is('h', "human-readable") => ls.human = true,
+            opt if opt.is('R', "recursive") => ls.recursive = true,
+            opt if opt.is('d', "directory") => ls.directory = true,
+            opt if opt.is('F', "classify") => ls.classify = true,
+            opt if opt.is('r', "reverse") => ls.reverse = true,
+            opt if opt.is('t', "time") => ls.sort = Sort::Time,
+            opt if opt.is('S', "size") => ls.sort = Sort::Size,
+            opt if opt.is('U', "unsorted") => ls.sort = Sort::Unsorted,
+            opt if opt.is('w', "width") => match args.value().map(|value| value.parse()) {
+                Some(Ok(width)) => ls.width = width,
+                Some(Err(_)) => {
+                    eprintln!("ls: invalid line width");
+                    return 2;
+                }
+                None => {
+                    getopt::missing("ls", "w");
+                    return 2;
+                }
+            },
+            Opt::Long(ref long) if long == "color" || long == "colour" => {
+                let enabled = match args.attached_value().as_deref() {
+                    None | Some("always" | "yes" | "force") => true,
+                    Some("auto" | "tty" | "if-tty") => std::io::stdout().is_terminal(),
+                    Some("never" | "no" | "none") => false,
+                    Some(other) => {
+                        eprintln!("ls: invalid argument '{}' for '--color'", other);
+                        return 2;
+                    }
+                };
+                ls.colors = enabled.then(|| parse_colors(DEFAULT_COLORS));
+            }
+            Opt::Operand(operand) => operands.push(operand),
+            opt => {
+                getopt::unknown("ls", &opt);
+                return 2;
+            }
+        }
+    }
+    if let Some(colors) = &mut ls.colors
+        && let Ok(custom) = std::env::var("LS_COLORS")
+    {
+        colors.extend(parse_colors(&custom));
+    }
+    if ls.long {
+        ls.users = read_names("/etc/passwd");
+        ls.groups = read_names("/etc/group");
+    }
+    if operands.is_empty() {
+        operands.push(".".to_string());
+    }
+
+    let mut stdout = BufWriter::new(std::io::stdout().lock());
+    let result = ls.list_operands(&operands, &mut stdout);
+    if let Err(e) = result.and_then(|_| stdout.flush()) {
+        eprintln!("ls: write error: {}", crate::strerror(&e));
+        return 2;
+    }
+    ls.status
+}
+
+impl Ls {
+    fn list_operands(
+        &mut self,
+        operands: &[String],
+        writer: &mut impl Write,
+    ) -> std::io::Result<()> {
+        let follow = !self.long && !self.directory && !self.classify;
+        let (mut files, mut directories) = (Vec::new(), Vec::new());
+        for operand in operands {
+            let path = PathBuf::from(operand);
+            let entry = match Entry::new(operand.clone(), path, follow) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    eprintln!("ls: cannot access '{}': {}", operand, crate::strerror(&e));
+                    self.status = 2;
+                    continue;
+                }
+            };
+            match entry.metadata.is_dir() && !self.directory {
+                true => directories.push(entry),
+                false => files.push(entry),
+            }
+        }
+
+        self.sort(&mut files);
+        self.sort(&mut directories);
+        let headers = operands.len() > 1 || self.recursive;
+        if !files.is_empty() {
+            self.write_entries(&files, writer)?;
+            self.sections += 1;
+        }
+        for directory in directories {
+            self.list_directory(&directory.path, &directory.name, headers, true, writer)?;
+        }
+        Ok(())
+    }
+
+    fn list_directory(
+        &mut self,
+        path: &Path,
+        name: &str,
+        header: bool,
+        top: bool,
+        writer: &mut impl Write,
+    ) -> std::io::Result<()> {
+        let entries = std::fs::read_dir(path).and_then(|entries| {
+            let mut names: Vec<String> = entries
+                .map(|entry| entry.map(|entry| entry.file_name().to_string_lossy().into_owned()))
+                .collect::<std::io::Result<_>>()?;
+            if self.hidden == Hidden::All {
+                names.extend([".".to_string(), "..".to_string()]);
+            }
+            Ok(names)
+        });
+        let names = match entries {
+            Ok(names) => names,
+            Err(e) => {
+                eprintln!(
+                    "ls: cannot open directory '{}': {}",
+                    name,
+                    crate::strerror(&e)
+                );
+                self.status = if top { 2 } else { self.status.max(1) };
+                return Ok(());
+            }
+        };
+
+        let mut entries = Vec::new();
+        for file in names {
+            if file.starts_with('.') && self.hidden == Hidden::Skip {
+                continue;
+            }
+            match Entry::new(file.clone(), path.join(&file), false) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => {
+                    eprintln!(
+                        "ls: cannot access '{}': {}",
+                        path.join(&file).display(),
+                        crate::strerror(&e)
+                    );
+                    self.status = self.status.max(1);
+                }
+            }
+        }
+        self.sort(&mut entries);
+
+        if self.sections > 0 {
+            writeln!(writer)?;
+        }
+        self.sections += 1;
+        if header {
+            writeln!(writer, "{}:", name)?;
+        }
+        if self.long {
+            let blocks: u64 = entries.iter().map(|entry| entry.metadata.blocks()).sum();
+            match self.human {
+                true => writeln!(writer, "total {}", human_size(blocks * 512))?,
+                false => writeln!(writer, "total {}", blocks.div_ceil(2))?,
+            }
+        }
+        self.write_entries(&entries, writer)?;
+
+        if self.recursive {
+            for entry in &entries {
+                if entry.metadata.is_dir() && entry.name != "." && entry.name != ".." {
+                    let name = match name.ends_with('/') {
+                        true => format!("{}{}", name, entry.name),
+                        false => format!("{}/{}", name, entry.name),
+                    };
+                    self.list_directory(&entry.path, &name, true, false, writer)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn sort(&self, entries: &mut [Entry]) {
+        match self.sort {
+            Sort::Name => entries.sort_by(|left, right| left.name.cmp(&right.name)),
+            Sort::Time => entries.sort_by(|left, right| {
+                let time = |entry: &Entry| (entry.metadata.mtime(), entry.metadata.mtime_nsec());
+                time(right)
+                    .cmp(&time(left))
+                    .then(left.name.cmp(&right.name))
+            }),
+            Sort::Size => entries.sort_by(|left, right| {
+                (right.metadata.len().cmp(&left.metadata.len())).then(left.name.cmp(&right.name))
+            }),
+            Sort::Unsorted => return,
+        }
+        if self.reverse {
+            entries.reverse();
+        }
+    }
+
+    fn write_entries(&mut self, entries: &[Entry], writer: &mut impl Write) -> std::io::Result<()> {
+        if self.long {
+            return self.write_long(entries, writer);
+        }
+        let widths: Vec<usize> = entries
+            .iter()
+            .map(|entry| entry.name.chars().count() + self.indicator(entry).len())
+            .collect();
+        let (rows, columns) = match self.columns {
+            true => layout(&widths, self.width),
+            false => (entries.len(), vec![0]),
+        };
+        for row in 0..rows {
+            let mut start = 0;
+            for (column, width) in columns.iter().enumerate() {
+                let Some(idx) = Some(column * rows + row).filter(|idx| *idx < entries.len()) else {
+                    break;
+                };
+                self.write_name(&entries[idx], writer)?;
+                write!(writer, "{}", self.indicator(&entries[idx]))?;
+                if column + 1 < columns.len() && idx + rows < entries.len() {
+                    self.pad(start + widths[idx], start + width, writer)?;
+                    start += width;
+                }
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+
+    // Like coreutils, pads columns with tabs where they fit unless the names
+    // are colored.
+    fn pad(&self, mut from: usize, to: usize, writer: &mut impl Write) -> std::io::Result<()> {
+        while from < to {
+            if self.colors.is_none() && to / 8 > (from + 1) / 8 {
+                write!(writer, "\t")?;
+                from += 8 - from % 8;
+            } else {
+                write!(writer, " ")?;
+                from += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_long(&mut self, entries: &[Entry], writer: &mut impl Write) -> std::io::Result<()> {
+        let rows: Vec<[String; 4]> = entries
+            .iter()
+            .map(|entry| {
+                let metadata = &entry.metadata;
+                let size = match metadata.file_type() {
+                    kind if kind.is_block_device() || kind.is_char_device() => {
+                        let device = metadata.rdev();
+                        let major = (device >> 8) & 0xfff | (device >> 32) & !0xfff;
+                        let minor = device & 0xff | (device >> 12) & !0xff;
+                        format!("{}, {}", major, minor)
+                    }
+                    _ if self.human => human_size(metadata.len()),
+                    _ => metadata.len().to_string(),
+                };
+                [
+                    metadata.nlink().to_string(),
+                    name_of(&self.users, metadata.uid()),
+                    name_of(&self.groups, metadata.gid()),
+                    size,
+                ]
+            })
+            .collect();
+        let width = |column: usize| rows.iter().map(|row| row[column].len()).max().unwrap_or(0);
+        let widths = [width(0), width(1), width(2), width(3)];
+
+        for (entry, row) in entries.iter().zip(&rows) {
+            write!(
+                writer,
+                "{} {:>links$} {:<user$} {:<group$} {:>size$} {} ",
+                mode_string(entry.metadata.mode()),
+                row[0],
+                row[1],
+                row[2],
+                row[3],
+                self.date(entry.metadata.mtime()),
+                links = widths[0],
+                user = widths[1],
+                group = widths[2],
+                size = widths[3],
+            )?;
+            self.write_name(entry, writer)?;
+            match std::fs::read_link(&entry.path) {
+                Ok(target) if entry.metadata.is_symlink() => {
+                    write!(writer, " -> {}", target.display())?
+                }
+                _ => write!(writer, "{}", self.indicator(entry))?,
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+
+    fn write_name(&mut self, entry: &Entry, writer: &mut impl Write) -> std::io::Result<()> {
+        let Some(color) = self.color(entry).map(str::to_string) else {
+            return write!(writer, "{}", entry.name);
+        };
+        if !self.colored {
+            self.colored = true;
+            write!(writer, "\x1b[0m")?;
+        }
+        write!(writer, "\x1b[{}m{}\x1b[0m", color, entry.name)
+    }
+
+    fn color(&self, entry: &Entry) -> Option<&str> {
+        let colors = self.colors.as_ref()?;
+        let mode = entry.metadata.mode();
+        let kind = entry.metadata.file_type();
+        let key = match () {
+            _ if kind.is_symlink() => match (&entry.target, colors.contains_key("or")) {
+                (Some(Err(_)), true) => "or",
+                _ => "ln",
+            },
+            _ if kind.is_dir() => match (mode & 0o1000 != 0, mode & 0o002 != 0) {
+                (true, true) => "tw",
+                (false, true) => "ow",
+                (true, false) => "st",
+                (false, false) => "di",
+            },
+            _ if kind.is_fifo() => "pi",
+            _ if kind.is_socket() => "so",
+            _ if kind.is_block_device() => "bd",
+            _ if kind.is_char_device() => "cd",
+            _ if mode & 0o4000 != 0 => "su",
+            _ if mode & 0o2000 != 0 => "sg",
+            _ if mode & 0o111 != 0 => "ex",
+            _ => {
+                let extension = colors.iter().find(|(key, _)| {
+                    key.strip_prefix('*')
+                        .is_some_and(|suffix| entry.name.ends_with(suffix))
+                });
+                return extension
+                    .map(|(_, color)| color.as_str())
+                    .or(colors.get("fi").map(|color| color.as_str()));
+            }
+        };
+        colors
+            .get(key)
+            .map(|color| color.as_str())
+            .filter(|color| !color.is_empty())
+    }
+
+    fn indicator(&self, entry: &Entry) -> &'static str {
+        if !self.classify {
+            return "";
+        }
+        let kind = entry.metadata.file_type();
+        match () {
+            _ if kind.is_dir() => "/",
+            _ if kind.is_symlink() && !self.long => "@",
+            _ if kind.is_fifo() => "|",
+            _ if kind.is_socket() => "=",
+            _ if kind.is_file() && entry.metadata.mode() & 0o111 != 0 => "*",
+            _ => "",
+        }
+    }
+
+    fn date(&self, time: i64) -> String {
+        let date = DateTime::local(time);
+        match self.now - SIX_MONTHS < time && time <= self.now {
+            true => format!(
+                "{} {:>2} {:02}:{:02}",
+                date.month_name(),
+                date.day,
+                date.hour,
+                date.minute
+            ),
+            false => format!("{} {:>2} {:>5}", date.month_name(), date.day, date.year),
+        }
+    }
+}
+
+impl Entry {
+    fn new(name: String, path: PathBuf, follow: bool) -> std::io::Result<Self> {
+        let mut metadata = std::fs::symlink_metadata(&path)?;
+        let mut target = None;
+        if metadata.is_symlink() {
+            let followed = std::fs::metadata(&path);
+            match followed {
+                Ok(followed) if follow && followed.is_dir() => metadata = followed,
+                Ok(followed) => target = Some(Ok(followed)),
+                Err(_) => target = Some(Err(())),
+            }
+        }
+        Ok(Self {
+            name,
+            path,
+            metadata,
+            target,
+        })
+    }
+}
+
+// Picks the most columns that fit in `width`, filling them top to bottom.
+// Every column but the last is padded to its widest name plus two spaces.
+fn layout(widths: &[usize], width: usize) -> (usize, Vec<usize>) {
+    for columns in (1..=widths.len().max(1)).rev() {
+        let rows = widths.len().div_ceil(columns);
+        if rows * columns - widths.len() >= rows {
+            continue;
+        }
+        let sizes: Vec<usize> = widths
+            .chunks(rows.max(1))
+            .map(|column| column.iter().max().unwrap() + 2)
+            .collect();
+        if columns == 1 || sizes.iter().sum::<usize>() - 2 <= width {
+            return (rows, sizes);
+        }
+    }
+    (0, vec![])
+}
+
+fn parse_colors(text: &str) -> HashMap<String, String> {
+    text.split(':')
+        .filter_map(|item| item.split_once('='))
+        .map(|(key, color)| (key.to_string(), color.to_string()))
+        .collect()
+}
+
+fn read_names(path: &str) -> HashMap<u32, String> {
+    std::fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .map(|line| line.split(':').collect::<Vec<&str>>())
+        .filter(|fields| fields.len() > 2)
+        .filter_map(|fields| Some((fields[2].parse().ok()?, fields[0].to_string())))
+        .collect()
+}
+
+fn name_of(names: &HashMap<u32, String>, id: u32) -> String {
+    names.get(&id).cloned().unwrap_or_else(|| id.to_string())
+}
+
+fn mode_string(mode: u32) -> String {
+    let kind = match mode & 0o170000 {
+        0o040000 => 'd',
+        0o120000 => 'l',
+        0o010000 => 'p',
+        0o140000 => 's',
+        0o060000 => 'b',
+        0o020000 => 'c',
+        _ => '-',
+    };
+    let mut text = String::from(kind);
+    for (shift, special, marks) in [
+        (6, 0o4000, ['s', 'S']),
+        (3, 0o2000, ['s', 'S']),
+        (0, 0o1000, ['t', 'T']),
+    ] {
+        let bits = mode >> shift;
+        text.push(if bits & 4 != 0 { 'r' } else { '-' });
+        text.push(if bits & 2 != 0 { 'w' } else { '-' });
+        text.push(match (mode & special != 0, bits & 1 != 0) {
+            (true, true) => marks[0],
+            (true, false) => marks[1],
+            (false, true) => 'x',
+            (false, false) => '-',
+        });
+    }
+    text
+}
+
+// Sizes in powers of 1024, rounded up like coreutils: one decimal below 10,
+// whole numbers above.
+fn human_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        return bytes.to_string();
+    }
+    let mut value = bytes as f64;
+    for unit in ["K", "M", "G", "T", "P", "E"] {
+        value /= 1024.0;
+        let tenths = (value * 10.0).ceil() / 10.0;
+        if tenths < 10.0 {
+            return format!("{:.1}{}", tenths, unit);
+        }
+        if value.ceil() < 1024.0 {
+            return format!("{}{}", value.ceil(), unit);
+        }
+    }
+    format!("{}E", value.ceil())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ls::{human_size, layout, mode_string};
+
+    #[test]
+    fn check_format() {
+        assert_eq!(mode_string(0o100644), "-rw-r--r--");
+        assert_eq!(mode_string(0o041777), "drwxrwxrwt");
+        assert_eq!(mode_string(0o104754), "-rwsr-xr--");
+        assert_eq!(mode_string(0o102640), "-rw-r-S---");
+        assert_eq!(human_size(1000), "1000");
+        assert_eq!(human_size(1024), "1.0K");
+        assert_eq!(human_size(5000), "4.9K");
+        assert_eq!(human_size(10189), "10K");
+        assert_eq!(human_size(1048575), "1.0M");
+        assert_eq!(human_size(5 << 30), "5.0G");
+        assert_eq!(layout(&[3, 5, 1, 2], 80), (1, vec![5, 7, 3, 4]));
+        assert_eq!(layout(&[3, 5, 1, 2], 10), (2, vec![7, 4]));
+        assert_eq!(layout(&[30, 30, 30], 20), (3, vec![32]));
+    }
+}
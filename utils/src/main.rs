@@ -0,0 +1,153 @@
+mod cat;
+mod cp;
+mod cut;
+mod echo;
+mod find;
+mod getopt;
+mod grep;
+mod head;
+mod ln;
+mod ls;
+mod mkdir;
+mod mv;
+mod pwd;
+mod regex;
+mod rm;
+mod sort;
+mod tail;
+mod time;
+mod touch;
+mod tr;
+mod uniq;
+mod wc;
+mod xargs;
+
+use std::path::Path;
+
+type Applet = fn(args: Vec<String>) -> i32;
+
+const APPLETS: [(&str, Applet); 20] = [
+    ("cat", cat::run),
+    ("cp", cp::run),
+    ("cut", cut::run),
+    ("echo", echo::run),
+    ("find", find::run),
+    ("grep", grep::run),
+    ("head", head::run),
+    ("ln", ln::run),
+    ("ls", ls::run),
+    ("mkdir", mkdir::run),
+    ("mv", mv::run),
+    ("pwd", pwd::run),
+    ("rm", rm::run),
+    ("sort", sort::run),
+    ("tail", tail::run),
+    ("touch", touch::run),
+    ("tr", tr::run),
+    ("uniq", uniq::run),
+    ("wc", wc::run),
+    ("xargs", xargs::run),
+];
+
+fn main() {
+    std::process::exit(dispatch(std::env::args().collect()))
+}
+
+fn dispatch(mut args: Vec<String>) -> i32 {
+    if let Some(applet) = args.first().and_then(|arg| find(applet_name(arg))) {
+        return applet(args);
+    }
+    if !args.is_empty() {
+        args.remove(0);
+    }
+
+    match args.first().map(|arg| arg.as_str()) {
+        None => {
+            eprintln!("usage: pseudobash-utils [--list | --install DIR | APPLET [ARGS...]]");
+            1
+        }
+        Some("--list") => {
+            APPLETS.iter().for_each(|(name, _)| println!("{}", name));
+            0
+        }
+        Some("--install") => match args.get(1) {
+            Some(dir) => install(Path::new(dir)),
+            None => {
+                eprintln!("--install: directory expected");
+                1
+            }
+        },
+        Some(name) => match find(name) {
+            Some(applet) => applet(args),
+            None => {
+                eprintln!("{}: applet not found", name);
+                127
+            }
+        },
+    }
+}
+
+fn find(name: &str) -> Option<Applet> {
+    APPLETS
+        .iter()
+        .find(|(applet, _)| *applet == name)
+        .map(|(_, applet)| *applet)
+}
+
+fn strerror(e: &std::io::Error) -> String {
+    let text = e.to_string();
+    match text.find(" (os error") {
+        Some(end) => text[..end].to_string(),
+        None => text,
+    }
+}
+
+// Asks a yes/no question on stderr for the interactive modes of rm, cp and mv.
+fn confirm(prompt: &str) -> bool {
+    eprint!("{}", prompt);
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).is_ok() && answer.starts_with(['y', 'Y'])
+}
+
+fn applet_name(arg: &str) -> &str {
+    Path::new(arg)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(arg)
+}
+
+fn install(dir: &Path) -> i32 {
+    let result = std::env::current_exe()
+        .and_then(|exe| exe.canonicalize())
+        .and_then(|exe| {
+            std::fs::create_dir_all(dir)?;
+            for (name, _) in APPLETS {
+                let link = dir.join(name);
+                if link.symlink_metadata().is_ok() {
+                    std::fs::remove_file(&link)?;
+                }
+                std::os::unix::fs::symlink(&exe, &link)?;
+            }
+            Ok(())
+        });
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("--install: {}: {}", dir.display(), e);
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{applet_name, find};
+
+    #[test]
+    fn check_applet_lookup() {
+        assert_eq!(applet_name("/usr/local/lib/pseudobash/wc"), "wc");
+        assert_eq!(applet_name("cat"), "cat");
+        assert!(find(applet_name("./utils/release/echo")).is_some());
+        assert!(find(applet_name("/usr/bin/pseudobash-utils")).is_none());
+    }
+}
@@ -0,0 +1,93 @@
+use {
+    crate::getopt::{self, Args, Opt},
+    std::{
+        io::ErrorKind,
+        os::unix::fs::{DirBuilderExt, PermissionsExt},
+        path::Path,
+    },
+};
+
+pub fn run(args: Vec<String>) -> i32 {
+    let mut parents = false;
+    let mut verbose = false;
+    let mut mode = None;
+    let mut directories = Vec::new();
+    let mut args = Args::new(args);
+    while let Some(opt) = args.next() {
+        match opt {
+            opt if opt.is('p', "parents") => parents = true,
+            opt if opt.is('v', "verbose") => verbose = true,
+            opt if opt.is('m', "mode") => match args.value() {
+                Some(value) => match u32::from_str_radix(&value, 8) {
+                    Ok(bits) if bits <= 0o7777 => mode = Some(bits),
+                    _ => {
+                        eprintln!("mkdir: invalid mode '{}'", value);
+                        return 1;
+                    }
+                },
+                None => return getopt::missing("mkdir", "m"),
+            },
+            Opt::Operand(directory) => directories.push(directory),
+            opt => return getopt::unknown("mkdir", &opt),
+        }
+    }
+    if directories.is_empty() {
+        eprintln!("mkdir: missing operand");
+        return 1;
+    }
+
+    let mut r_code = 0;
+    for directory in &directories {
+        let path = Path::new(directory);
+        let mut ancestors: Vec<&Path> = match parents {
+            true => path
+                .ancestors()
+                .skip(1)
+                .filter(|ancestor| !ancestor.as_os_str().is_empty())
+                .collect(),
+            false => vec![],
+        };
+        ancestors.reverse();
+        // An ancestor that exists as a file fails like the kernel would fail
+        // the whole path.
+        let result = ancestors
+            .into_iter()
+            .try_for_each(|ancestor| {
+                create(ancestor, None, true, verbose).map_err(|e| match e.kind() {
+                    ErrorKind::AlreadyExists => {
+                        (ancestor, std::io::Error::other("Not a directory"))
+                    }
+                    _ => (ancestor, e),
+                })
+            })
+            .and_then(|_| create(path, mode, parents, verbose).map_err(|e| (path, e)));
+        if let Err((failed, e)) = result {
+            r_code = 1;
+            eprintln!(
+                "mkdir: cannot create directory '{}': {}",
+                failed.display(),
+                crate::strerror(&e)
+            );
+        }
+    }
+
+    r_code
+}
+
+// Creates one directory. With `existing`, a directory that is already there
+// is fine. An explicit mode is set after creation so the umask does not apply.
+fn create(path: &Path, mode: Option<u32>, existing: bool, verbose: bool) -> std::io::Result<()> {
+    match std::fs::DirBuilder::new().mode(0o777).create(path) {
+        Err(e) if existing && e.kind() == ErrorKind::AlreadyExists && path.is_dir() => {
+            return Ok(());
+        }
+        result => result?,
+    }
+    if let Some(mode) = mode {
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+    }
+    if verbose {
+        println!("mkdir: created directory '{}'", path.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use {
+    crate::{
+        cp::{self, Cp},
+        getopt::{self, Args, Opt},
+    },
+    std::{io::ErrorKind, os::unix::fs::MetadataExt, path::Path},
+};
+
+pub fn run(args: Vec<String>) -> i32 {
+    let mut interactive = false;
+    let mut no_clobber = false;
+    let mut verbose = false;
+    let mut operands = Vec::new();
+    for opt in Args::new(args) {
+        match opt {
+            opt if opt.is('f', "force") => {
+                interactive = false;
+                no_clobber = false;
+            }
+            opt if opt.is('i', "interactive") => {
+                interactive = true;
+                no_clobber = false;
+            }
+            opt if opt.is('n', "no-clobber") => {
+                no_clobber = true;
+                interactive = false;
+            }
+            opt if opt.is('v', "verbose") => verbose = true,
+            Opt::Operand(operand) => operands.push(operand),
+            opt => return getopt::unknown("mv", &opt),
+        }
+    }
+    let Some(pairs) = cp::destinations("mv", operands) else {
+        return 1;
+    };
+
+    let mut r_code = 0;
+    for (source, destination) in pairs {
+        let (from, to) = (Path::new(&source), Path::new(&destination));
+        let metadata = match std::fs::symlink_metadata(from) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                eprintln!("mv: cannot stat '{}': {}", source, crate::strerror(&e));
+                r_code = 1;
+                continue;
+            }
+        };
+        if let Ok(existing) = std::fs::symlink_metadata(to) {
+            if existing.dev() == metadata.dev() && existing.ino() == metadata.ino() {
+                eprintln!("mv: '{}' and '{}' are the same file", source, destination);
+                r_code = 1;
+                continue;
+            }
+            let prompt = format!("mv: overwrite '{}'? ", destination);
+            if no_clobber || interactive && !crate::confirm(&prompt) {
+                continue;
+            }
+        }
+        if metadata.is_dir() && is_inside(to, from) {
+            eprintln!(
+                "mv: cannot move '{}' to a subdirectory of itself, '{}'",
+                source, destination
+            );
+            r_code = 1;
+            continue;
+        }
+
+        let result = match std::fs::rename(from, to) {
+            Err(e) if e.kind() == ErrorKind::CrossesDevices => move_across(from, to),
+            result => result.map(|_| true),
+        };
+        match result {
+            Ok(true) if verbose => println!("renamed '{}' -> '{}'", source, destination),
+            Ok(true) => {}
+            Ok(false) => r_code = 1,
+            Err(e) => {
+                eprintln!(
+                    "mv: cannot move '{}' to '{}': {}",
+                    source,
+                    destination,
+                    crate::strerror(&e)
+                );
+                r_code = 1;
+            }
+        }
+    }
+
+    r_code
+}
+
+fn is_inside(path: &Path, directory: &Path) -> bool {
+    let Ok(directory) = directory.canonicalize() else {
+        return false;
+    };
+    path.parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+        .canonicalize()
+        .is_ok_and(|parent| parent.starts_with(directory))
+}
+
+// `rename` only works within one file system. Elsewhere the tree is copied
+// with its attributes and the original removed once the copy succeeded.
+fn move_across(from: &Path, to: &Path) -> std::io::Result<bool> {
+    let cp = Cp {
+        applet: "mv",
+        recursive: true,
+        preserve: true,
+        dereference: Some(false),
+        ..Default::default()
+    };
+    if std::fs::symlink_metadata(to).is_ok_and(|existing| !existing.is_dir()) {
+        std::fs::remove_file(to)?;
+    }
+    if !cp.copy(from, to) {
+        return Ok(false);
+    }
+    match std::fs::symlink_metadata(from)?.is_dir() {
+        true => std::fs::remove_dir_all(from)?,
+        false => std::fs::remove_file(from)?,
+    }
+    Ok(true)
+}
@@ -0,0 +1,46 @@
+use std::{
+    os::unix::fs::MetadataExt,
+    path::{Component, Path, PathBuf},
+};
+
+pub fn run(args: Vec<String>) -> i32 {
+    let mut logical = false;
+    for arg in &args[1..] {
+        match arg.as_str() {
+            "-L" | "--logical" => logical = true,
+            "-P" | "--physical" => logical = false,
+            option => {
+                eprintln!("pwd: invalid option '{}'", option);
+                return 1;
+            }
+        }
+    }
+
+    let pwd = std::env::var_os("PWD")
+        .map(PathBuf::from)
+        .filter(|path| logical && is_current_dir(path));
+    match pwd.map(Ok).unwrap_or_else(std::env::current_dir) {
+        Ok(path) => {
+            println!("{}", path.display());
+            0
+        }
+        Err(e) => {
+            eprintln!("pwd: {}", crate::strerror(&e));
+            1
+        }
+    }
+}
+
+fn is_current_dir(path: &Path) -> bool {
+    if !path.is_absolute()
+        || path
+            .components()
+            .any(|component| matches!(component, Component::CurDir | Component::ParentDir))
+    {
+        return false;
+    }
+    match (std::fs::metadata(path), std::fs::metadata(".")) {
+        (Ok(path), Ok(current)) => path.dev() == current.dev() && path.ino() == current.ino(),
+        _ => false,
+    }
+}
@@ -0,0 +1,124 @@
+use pseudobash_regex::{Assert, ClassError, Node, ParseError, Parsed, Program};
+
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub enum Syntax {
+    #[default]
+    Basic,
+    Extended,
+    Fixed,
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct Options {
+    pub syntax: Syntax,
+    pub ignore_case: bool,
+    pub whole_word: bool,
+    pub whole_line: bool,
+}
+
+// A POSIX regular expression. Matches are leftmost-longest, as in grep, and
+// positions are counted in characters.
+#[derive(Debug, Clone)]
+pub struct Regex {
+    program: Program,
+}
+
+impl Regex {
+    pub fn new(pattern: &str, options: Options) -> Result<Self, String> {
+        let syntax = match options.syntax {
+            Syntax::Basic => pseudobash_regex::Syntax::Basic,
+            Syntax::Extended => pseudobash_regex::Syntax::Extended,
+            Syntax::Fixed => {
+                return Ok(Self::compile(
+                    Node::Concat(pattern.chars().map(Node::Char).collect()),
+                    0,
+                    options,
+                ));
+            }
+        };
+        let Parsed {
+            node,
+            groups,
+            dangling,
+        } = pseudobash_regex::parse(pattern, syntax).map_err(|e| match e {
+            ParseError::UnmatchedOpen => "Unmatched ( or \\(",
+            ParseError::UnmatchedClose => "Unmatched ) or \\)",
+            ParseError::UnmatchedBrace => "Unmatched \\{",
+            ParseError::InvalidInterval => "Invalid content of \\{\\}",
+            ParseError::InvalidBackref => "Invalid back reference",
+            ParseError::TrailingBackslash => "Trailing backslash",
+            ParseError::Class(ClassError::Unmatched) => "Unmatched [, [^, [:, [., or [=",
+            ParseError::Class(ClassError::InvalidName(_)) => "Invalid character class name",
+            ParseError::Class(ClassError::InvalidCollation) => "Invalid collation character",
+            ParseError::Class(ClassError::InvalidRange(..)) => "Invalid range end",
+        })?;
+        if let Some(operator) = dangling {
+            eprintln!("grep: warning: {} at start of expression", operator);
+        }
+        Ok(Self::compile(node, groups, options))
+    }
+
+    fn compile(mut node: Node, groups: usize, options: Options) -> Self {
+        if options.whole_line {
+            node = Node::Concat(vec![
+                Node::Assert(Assert::LineStart),
+                node,
+                Node::Assert(Assert::LineEnd),
+            ]);
+        } else if options.whole_word {
+            node = Node::Concat(vec![
+                Node::Assert(Assert::NoWordBefore),
+                node,
+                Node::Assert(Assert::NoWordAfter),
+            ]);
+        }
+        Self {
+            program: Program::new(&node, groups, options.ignore_case),
+        }
+    }
+
+    pub fn find(&self, text: &[char], from: usize) -> Option<(usize, usize)> {
+        self.program.find(text, from)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::regex::{Options, Regex, Syntax};
+
+    fn find(pattern: &str, syntax: Syntax, text: &str) -> Option<String> {
+        let options = Options {
+            syntax,
+            ..Default::default()
+        };
+        let text: Vec<char> = text.chars().collect();
+        let (from, to) = Regex::new(pattern, options).unwrap().find(&text, 0)?;
+        Some(text[from..to].iter().collect())
+    }
+
+    #[test]
+    fn check_regex() {
+        let (basic, extended) = (Syntax::Basic, Syntax::Extended);
+        assert_eq!(find("a|ab", extended, "xab"), Some("ab".into()));
+        assert_eq!(find("a|b", basic, "a|b"), Some("a|b".into()));
+        assert_eq!(find("a.c", Syntax::Fixed, "abc a.c"), Some("a.c".into()));
+        assert_eq!(find("*a", extended, "b*a"), Some("a".into()));
+
+        let options = |ignore_case, whole_word, whole_line| Options {
+            syntax: extended,
+            ignore_case,
+            whole_word,
+            whole_line,
+        };
+        let text: Vec<char> = "Foo foobar FOO".chars().collect();
+        let regex = Regex::new("foo", options(true, true, false)).unwrap();
+        assert_eq!(regex.find(&text, 0), Some((0, 3)));
+        assert_eq!(regex.find(&text, 1), Some((11, 14)));
+        let regex = Regex::new("foo.*", options(true, false, true)).unwrap();
+        assert_eq!(regex.find(&text, 0), Some((0, 14)));
+
+        assert!(Regex::new("\\(a", Options::default()).is_err());
+        assert!(Regex::new("(a", options(false, false, false)).is_err());
+        assert!(Regex::new("[[:foo:]]", options(false, false, false)).is_err());
+    }
+}
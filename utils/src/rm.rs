@@ -0,0 +1,201 @@
+use {
+    crate::getopt::{self, Args, Opt},
+    std::{
+        fs::Metadata,
+        io::ErrorKind,
+        os::unix::fs::FileTypeExt,
+        path::{Component, Path},
+    },
+};
+
+#[derive(Default)]
+struct Rm {
+    force: bool,
+    interactive: bool,
+    recursive: bool,
+    directories: bool,
+    verbose: bool,
+}
+
+pub fn run(args: Vec<String>) -> i32 {
+    let mut rm = Rm::default();
+    let mut files = Vec::new();
+    for opt in Args::new(args) {
+        match opt {
+            opt if opt.is('f', "force") => {
+                rm.force = true;
+                rm.interactive = false;
+            }
+            opt if opt.is('i', "interactive") => {
+                rm.interactive = true;
+                rm.force = false;
+            }
+            opt if opt.is('r', "recursive") || opt.is('R', "recursive") => rm.recursive = true,
+            opt if opt.is('d', "dir") => rm.directories = true,
+            opt if opt.is('v', "verbose") => rm.verbose = true,
+            Opt::Operand(file) => files.push(file),
+            opt => return getopt::unknown("rm", &opt),
+        }
+    }
+    if files.is_empty() && !rm.force {
+        eprintln!("rm: missing operand");
+        return 1;
+    }
+
+    let mut r_code = 0;
+    for file in &files {
+        let path = Path::new(file);
+        if matches!(
+            path.components().next_back(),
+            Some(Component::CurDir | Component::ParentDir)
+        ) {
+            eprintln!(
+                "rm: refusing to remove '.' or '..' directory: skipping '{}'",
+                file
+            );
+            r_code = 1;
+            continue;
+        }
+        if rm.recursive && path.components().eq([Component::RootDir]) {
+            eprintln!("rm: it is dangerous to operate recursively on '{}'", file);
+            eprintln!("rm: use --no-preserve-root to override this failsafe");
+            r_code = 1;
+            continue;
+        }
+        if !rm.remove(path, file) {
+            r_code = 1;
+        }
+    }
+
+    r_code
+}
+
+impl Rm {
+    // Removes `path`, shown to the user as `name`, and reports whether that
+    // went without errors. Files the user chose to keep are not errors.
+    fn remove(&self, path: &Path, name: &str) -> bool {
+        let metadata = match std::fs::symlink_metadata(path) {
+            Ok(metadata) => metadata,
+            Err(e) if self.force && e.kind() == ErrorKind::NotFound => return true,
+            Err(e) => return self.fail(name, &e),
+        };
+
+        if !metadata.is_dir() {
+            if self.interactive && !self.confirm("remove", &metadata, name) {
+                return true;
+            }
+            return match std::fs::remove_file(path) {
+                Ok(_) => self.removed("removed", name),
+                Err(e) => self.fail(name, &e),
+            };
+        }
+
+        if !self.recursive {
+            if !self.directories {
+                eprintln!("rm: cannot remove '{}': Is a directory", name);
+                return false;
+            }
+        } else {
+            let entries = std::fs::read_dir(path).and_then(|entries| {
+                let mut entries: Vec<_> = entries
+                    .map(|entry| entry.map(|entry| entry.file_name()))
+                    .collect::<std::io::Result<_>>()?;
+                entries.sort();
+                Ok(entries)
+            });
+            let entries = match entries {
+                Ok(entries) => entries,
+                Err(e) => return self.fail(name, &e),
+            };
+            if self.interactive
+                && !entries.is_empty()
+                && !self.confirm("descend into", &metadata, name)
+            {
+                return true;
+            }
+            let mut ok = true;
+            for entry in entries {
+                let child = format!("{}/{}", name.trim_end_matches('/'), entry.to_string_lossy());
+                ok &= self.remove(&path.join(entry), &child);
+            }
+            if !ok {
+                return false;
+            }
+            // A declined entry keeps the directory, so there is nothing to ask.
+            let mut remaining = std::fs::read_dir(path).into_iter().flatten();
+            if self.interactive && remaining.next().is_some() {
+                return true;
+            }
+        }
+
+        if self.interactive && !self.confirm("remove", &metadata, name) {
+            return true;
+        }
+        match std::fs::remove_dir(path) {
+            Ok(_) => self.removed("removed directory", name),
+            Err(e) => self.fail(name, &e),
+        }
+    }
+
+    fn confirm(&self, action: &str, metadata: &Metadata, name: &str) -> bool {
+        let kind = metadata.file_type();
+        let kind = match () {
+            _ if kind.is_dir() => "directory",
+            _ if kind.is_symlink() => "symbolic link",
+            _ if kind.is_fifo() => "fifo",
+            _ if kind.is_socket() => "socket",
+            _ if kind.is_block_device() => "block special file",
+            _ if kind.is_char_device() => "character special file",
+            _ if metadata.len() == 0 => "regular empty file",
+            _ => "regular file",
+        };
+        crate::confirm(&format!("rm: {} {} '{}'? ", action, kind, name))
+    }
+
+    fn removed(&self, action: &str, name: &str) -> bool {
+        if self.verbose {
+            println!("{} '{}'", action, name);
+        }
+        true
+    }
+
+    fn fail(&self, name: &str, e: &std::io::Error) -> bool {
+        eprintln!("rm: cannot remove '{}': {}", name, crate::strerror(e));
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::rm::Rm;
+
+    #[test]
+    fn check_remove() {
+        let root = std::env::temp_dir().join(format!("pseudobash-rm-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("a/b")).unwrap();
+        std::fs::write(root.join("a/b/file"), "x").unwrap();
+        std::os::unix::fs::symlink("/", root.join("a/link")).unwrap();
+
+        let rm = Rm::default();
+        assert!(!rm.remove(&root.join("a"), "a"));
+        assert!(!rm.remove(&root.join("missing"), "missing"));
+        let forced = Rm {
+            force: true,
+            ..Default::default()
+        };
+        assert!(forced.remove(&root.join("missing"), "missing"));
+        let recursive = Rm {
+            recursive: true,
+            ..Default::default()
+        };
+        assert!(recursive.remove(&root.join("a"), "a"));
+        assert!(!root.join("a").exists());
+        assert!(
+            Rm {
+                directories: true,
+                ..Default::default()
+            }
+            .remove(&root, "root")
+        );
+    }
+}
@@ -0,0 +1,449 @@
+use {
+    crate::getopt::{self, Args, Opt},
+    std::{
+        cmp::Ordering,
+        io::{BufRead, BufWriter, Write},
+    },
+};
+
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+struct Order {
+    numeric: bool,
+    reverse: bool,
+    fold: bool,
+    blanks: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Position {
+    field: usize,
+    char: usize,
+    blanks: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Key {
+    start: Position,
+    end: Option<Position>,
+    order: Order,
+}
+
+struct Sorter {
+    keys: Vec<Key>,
+    separator: Option<u8>,
+    order: Order,
+    stable: bool,
+    unique: bool,
+}
+
+pub fn run(args: Vec<String>) -> i32 {
+    let mut order = Order::default();
+    let mut keys = Vec::new();
+    let mut separator = None;
+    let mut stable = false;
+    let mut unique = false;
+    let mut output = None;
+    let mut files = Vec::new();
+    let mut args = Args::new(args);
+    while let Some(opt) = args.next() {
+        match opt {
+            opt if opt.is('n', "numeric-sort") => order.numeric = true,
+            opt if opt.is('r', "reverse") => order.reverse = true,
+            opt if opt.is('f', "ignore-case") => order.fold = true,
+            opt if opt.is('b', "ignore-leading-blanks") => order.blanks = true,
+            opt if opt.is('s', "stable") => stable = true,
+            opt if opt.is('u', "unique") => unique = true,
+            opt if opt.is('k', "key") => {
+                let Some(value) = args.value() else {
+                    return getopt::missing("sort", "k");
+                };
+                match parse_key(&value) {
+                    Some(key) => keys.push(key),
+                    None => {
+                        eprintln!("sort: invalid key specification '{}'", value);
+                        return 2;
+                    }
+                }
+            }
+            opt if opt.is('t', "field-separator") => {
+                match args.value().map(String::into_bytes).as_deref() {
+                    Some([byte]) => separator = Some(*byte),
+                    Some(b"\\0") => separator = Some(0),
+                    _ => {
+                        eprintln!("sort: the field separator must be a single character");
+                        return 2;
+                    }
+                }
+            }
+            opt if opt.is('o', "output") => {
+                let Some(value) = args.value() else {
+                    return getopt::missing("sort", "o");
+                };
+                output = Some(value);
+            }
+            Opt::Operand(file) => files.push(file),
+            opt => {
+                getopt::unknown("sort", &opt);
+                return 2;
+            }
+        }
+    }
+    if files.is_empty() {
+        files.push("-".to_string());
+    }
+    for key in keys.iter_mut() {
+        if key.order == Order::default() {
+            key.order = order;
+            key.start.blanks |= order.blanks;
+            if let Some(end) = key.end.as_mut() {
+                end.blanks |= order.blanks;
+            }
+        }
+    }
+
+    let mut lines = Vec::new();
+    for file in &files {
+        let result = getopt::open(file).and_then(|mut reader| read_lines(&mut reader, &mut lines));
+        if let Err(e) = result {
+            eprintln!("sort: {}: {}", file, crate::strerror(&e));
+            return 2;
+        }
+    }
+
+    let sorter = Sorter {
+        keys,
+        separator,
+        order,
+        stable,
+        unique,
+    };
+    sorter.sort(&mut lines);
+
+    let result = match output {
+        Some(path) => std::fs::File::create(&path)
+            .and_then(|file| write_lines(&mut BufWriter::new(file), &lines))
+            .map_err(|e| (path, e)),
+        None => write_lines(&mut BufWriter::new(std::io::stdout().lock()), &lines)
+            .map_err(|e| ("standard output".to_string(), e)),
+    };
+    match result {
+        Ok(_) => 0,
+        Err((path, e)) => {
+            eprintln!("sort: {}: {}", path, crate::strerror(&e));
+            2
+        }
+    }
+}
+
+fn read_lines(reader: &mut dyn BufRead, lines: &mut Vec<Vec<u8>>) -> std::io::Result<()> {
+    loop {
+        let mut line = Vec::new();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            return Ok(());
+        }
+        if line.last() == Some(&b'\n') {
+            line.pop();
+        }
+        lines.push(line);
+    }
+}
+
+fn write_lines(writer: &mut impl Write, lines: &[Vec<u8>]) -> std::io::Result<()> {
+    for line in lines {
+        writer.write_all(line)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
+}
+
+// KEYDEF is F[.C][OPTS][,F[.C][OPTS]] with OPTS made of `b`, `f`, `n` and `r`.
+fn parse_key(text: &str) -> Option<Key> {
+    let mut order = Order::default();
+    let (start, end) = match text.split_once(',') {
+        Some((start, end)) => (start, Some(end)),
+        None => (text, None),
+    };
+    let start = parse_position(start, &mut order, true)?;
+    let end = match end {
+        Some(end) => Some(parse_position(end, &mut order, false)?),
+        None => None,
+    };
+    Some(Key { start, end, order })
+}
+
+fn parse_position(text: &str, order: &mut Order, start: bool) -> Option<Position> {
+    let options = text.trim_start_matches(|char: char| char.is_ascii_digit() || char == '.');
+    let (field, char) = match text[..text.len() - options.len()].split_once('.') {
+        Some((field, char)) => (field.parse().ok()?, char.parse().ok()?),
+        None => (text[..text.len() - options.len()].parse().ok()?, 0),
+    };
+    if field == 0 || (start && char == 0 && text.contains('.')) {
+        return None;
+    }
+    let mut position = Position {
+        field,
+        char,
+        blanks: false,
+    };
+    for option in options.chars() {
+        match option {
+            'b' => position.blanks = true,
+            'f' => order.fold = true,
+            'n' => order.numeric = true,
+            'r' => order.reverse = true,
+            _ => return None,
+        }
+    }
+    Some(position)
+}
+
+impl Sorter {
+    fn sort(&self, lines: &mut Vec<Vec<u8>>) {
+        lines.sort_by(|left, right| self.compare(left, right));
+        if self.unique {
+            lines.dedup_by(|right, left| self.compare_keys(left, right) == Ordering::Equal);
+        }
+    }
+
+    fn compare(&self, left: &[u8], right: &[u8]) -> Ordering {
+        let ordering = self.compare_keys(left, right);
+        if ordering != Ordering::Equal || self.stable || self.unique {
+            return ordering;
+        }
+        match self.order.reverse {
+            true => right.cmp(left),
+            false => left.cmp(right),
+        }
+    }
+
+    fn compare_keys(&self, left: &[u8], right: &[u8]) -> Ordering {
+        if self.keys.is_empty() {
+            let (left, right) = match self.order.blanks {
+                true => (skip_blanks(left), skip_blanks(right)),
+                false => (left, right),
+            };
+            return compare_with(left, right, &self.order);
+        }
+        for key in &self.keys {
+            let ordering = compare_with(
+                self.extract(left, key),
+                self.extract(right, key),
+                &key.order,
+            );
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        Ordering::Equal
+    }
+
+    fn extract<'a>(&self, line: &'a [u8], key: &Key) -> &'a [u8] {
+        let start = self.position(line, &key.start, false);
+        let end = match &key.end {
+            Some(end) => self.position(line, end, true),
+            None => line.len(),
+        };
+        &line[start..end.max(start)]
+    }
+
+    fn position(&self, line: &[u8], position: &Position, end: bool) -> usize {
+        let (field_start, field_end) = self.field(line, position.field);
+        if end && position.char == 0 {
+            return field_end;
+        }
+        let mut idx = field_start;
+        if position.blanks {
+            idx += line[idx..field_end]
+                .iter()
+                .take_while(|byte| is_blank(**byte))
+                .count();
+        }
+        let offset = match end {
+            true => position.char,
+            false => position.char.saturating_sub(1),
+        };
+        (idx + offset).min(field_end)
+    }
+
+    // Returns the bounds of the 1-based field. Without a separator each field
+    // keeps the blanks that precede it.
+    fn field(&self, line: &[u8], field: usize) -> (usize, usize) {
+        let mut start = 0;
+        for _ in 1..field {
+            start = match self.separator {
+                Some(separator) => match line[start..].iter().position(|byte| *byte == separator) {
+                    Some(idx) => start + idx + 1,
+                    None => return (line.len(), line.len()),
+                },
+                None => next_field(line, start),
+            };
+        }
+        let end = match self.separator {
+            Some(separator) => line[start..]
+                .iter()
+                .position(|byte| *byte == separator)
+                .map_or(line.len(), |idx| start + idx),
+            None => next_field(line, start),
+        };
+        (start, end)
+    }
+}
+
+fn next_field(line: &[u8], start: usize) -> usize {
+    let blanks = line[start..]
+        .iter()
+        .take_while(|byte| is_blank(**byte))
+        .count();
+    let word = line[start + blanks..]
+        .iter()
+        .take_while(|byte| !is_blank(**byte))
+        .count();
+    start + blanks + word
+}
+
+fn compare_with(left: &[u8], right: &[u8], order: &Order) -> Ordering {
+    let result = if order.numeric {
+        compare_numbers(left, right)
+    } else if order.fold {
+        left.iter()
+            .map(u8::to_ascii_uppercase)
+            .cmp(right.iter().map(u8::to_ascii_uppercase))
+    } else {
+        left.cmp(right)
+    };
+    match order.reverse {
+        true => result.reverse(),
+        false => result,
+    }
+}
+
+struct Number<'a> {
+    negative: bool,
+    integer: &'a [u8],
+    fraction: &'a [u8],
+}
+
+fn parse_number(text: &[u8]) -> Number<'_> {
+    let text = skip_blanks(text);
+    let (negative, text) = match text.first() {
+        Some(b'-') => (true, &text[1..]),
+        _ => (false, text),
+    };
+    let digits = text.iter().take_while(|byte| byte.is_ascii_digit()).count();
+    let integer = &text[..digits];
+    let integer = &integer[integer.iter().take_while(|byte| **byte == b'0').count()..];
+    let fraction = match text.get(digits) {
+        Some(b'.') => {
+            let rest = &text[digits + 1..];
+            let fraction = &rest[..rest.iter().take_while(|byte| byte.is_ascii_digit()).count()];
+            let zeros = fraction
+                .iter()
+                .rev()
+                .take_while(|byte| **byte == b'0')
+                .count();
+            &fraction[..fraction.len() - zeros]
+        }
+        _ => &[],
+    };
+    Number {
+        negative: negative && !(integer.is_empty() && fraction.is_empty()),
+        integer,
+        fraction,
+    }
+}
+
+fn compare_numbers(left: &[u8], right: &[u8]) -> Ordering {
+    let (left, right) = (parse_number(left), parse_number(right));
+    if left.negative != right.negative {
+        return match left.negative {
+            true => Ordering::Less,
+            false => Ordering::Greater,
+        };
+    }
+    let magnitude = left
+        .integer
+        .len()
+        .cmp(&right.integer.len())
+        .then_with(|| left.integer.cmp(right.integer))
+        .then_with(|| left.fraction.cmp(right.fraction));
+    match left.negative {
+        true => magnitude.reverse(),
+        false => magnitude,
+    }
+}
+
+fn skip_blanks(text: &[u8]) -> &[u8] {
+    &text[text.iter().take_while(|byte| is_blank(**byte)).count()..]
+}
+
+fn is_blank(byte: u8) -> bool {
+    byte == b' ' || byte == b'\t'
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sort::{Order, Sorter, parse_key};
+
+    fn check(keys: &[&str], separator: Option<u8>, flags: &str, input: &[&str], expected: &[&str]) {
+        let order = Order {
+            numeric: flags.contains('n'),
+            reverse: flags.contains('r'),
+            fold: flags.contains('f'),
+            blanks: flags.contains('b'),
+        };
+        let sorter = Sorter {
+            keys: keys.iter().map(|key| parse_key(key).unwrap()).collect(),
+            separator,
+            order,
+            stable: flags.contains('s'),
+            unique: flags.contains('u'),
+        };
+        let mut lines = input.iter().map(|line| line.as_bytes().to_vec()).collect();
+        sorter.sort(&mut lines);
+        assert_eq!(
+            lines,
+            expected
+                .iter()
+                .map(|line| line.as_bytes().to_vec())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn check_sort() {
+        check(&[], None, "", &["b", "a", "B"], &["B", "a", "b"]);
+        check(
+            &[],
+            None,
+            "n",
+            &["10", "9", "-1.5", "x", "-2"],
+            &["-2", "-1.5", "x", "9", "10"],
+        );
+        check(&[], None, "nr", &["1", "3", "2"], &["3", "2", "1"]);
+        check(&[], None, "nu", &["1", "01", "2"], &["1", "2"]);
+        check(
+            &["2,2n"],
+            Some(b':'),
+            "",
+            &["a:10", "b:9", "c:9"],
+            &["b:9", "c:9", "a:10"],
+        );
+        check(
+            &["2,2nr"],
+            Some(b':'),
+            "s",
+            &["c:9", "b:9", "a:10"],
+            &["a:10", "c:9", "b:9"],
+        );
+        check(&["2"], None, "", &["x  b", "y a"], &["x  b", "y a"]);
+        check(&["2b"], None, "", &["x  b", "y a"], &["y a", "x  b"]);
+        check(
+            &["1.2,1.2"],
+            None,
+            "",
+            &["ab", "ba", "ca"],
+            &["ba", "ca", "ab"],
+        );
+        check(&[], None, "fu", &["a", "A", "b"], &["a", "b"]);
+    }
+}
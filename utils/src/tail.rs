@@ -0,0 +1,269 @@
+use {
+    crate::{
+        getopt::{self, Args, Opt},
+        head::write_header,
+    },
+    std::{
+        collections::VecDeque,
+        fs::File,
+        io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+        time::Duration,
+    },
+};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Count {
+    Last(u64),
+    From(u64),
+}
+
+pub fn run(args: Vec<String>) -> i32 {
+    let mut lines = true;
+    let mut count = Count::Last(10);
+    let mut follow = false;
+    let mut interval = Duration::from_secs(1);
+    let mut headers = None;
+    let mut files = Vec::new();
+    let mut args = Args::new(args);
+    while let Some(opt) = args.next() {
+        let value = match opt {
+            opt if opt.is('n', "lines") || opt.is('c', "bytes") => {
+                lines = opt.is('n', "lines");
+                args.value()
+            }
+            Opt::Short(digit) if digit.is_ascii_digit() => {
+                lines = true;
+                Some(format!("{}{}", digit, args.cluster_rest()))
+            }
+            opt if opt.is('f', "follow") => {
+                follow = true;
+                continue;
+            }
+            opt if opt.is('s', "sleep-interval") => {
+                let value = args.value().unwrap_or_default();
+                match value.parse::<f64>() {
+                    Ok(seconds) if seconds >= 0.0 => interval = Duration::from_secs_f64(seconds),
+                    _ => {
+                        eprintln!("tail: invalid number of seconds: '{}'", value);
+                        return 1;
+                    }
+                }
+                continue;
+            }
+            opt if opt.is('q', "quiet") || opt.is('q', "silent") => {
+                headers = Some(false);
+                continue;
+            }
+            opt if opt.is('v', "verbose") => {
+                headers = Some(true);
+                continue;
+            }
+            Opt::Operand(file) => {
+                files.push(file);
+                continue;
+            }
+            opt => return getopt::unknown("tail", &opt),
+        };
+        let Some(value) = value else {
+            return getopt::missing("tail", if lines { "n" } else { "c" });
+        };
+        match parse_count(&value) {
+            Some(parsed) => count = parsed,
+            None => {
+                let unit = if lines { "lines" } else { "bytes" };
+                eprintln!("tail: invalid number of {}: '{}'", unit, value);
+                return 1;
+            }
+        }
+    }
+    if files.is_empty() {
+        files.push("-".to_string());
+    }
+    let headers = headers.unwrap_or(files.len() > 1);
+
+    let mut r_code = 0;
+    let mut followed = Vec::new();
+    let mut stdout = BufWriter::new(std::io::stdout().lock());
+    for (idx, file) in files.iter().enumerate() {
+        let result = match file.as_str() {
+            "-" => write_header(&mut stdout, headers, idx == 0, file)
+                .and_then(|_| tail(&mut std::io::stdin().lock(), &mut stdout, lines, count)),
+            path => match File::open(path) {
+                Ok(opened) => {
+                    let mut reader = BufReader::new(opened);
+                    let result = write_header(&mut stdout, headers, idx == 0, file)
+                        .and_then(|_| tail(&mut reader, &mut stdout, lines, count));
+                    if follow {
+                        followed.push((idx, reader));
+                    }
+                    result
+                }
+                Err(e) => {
+                    r_code = 1;
+                    eprintln!(
+                        "tail: cannot open '{}' for reading: {}",
+                        file,
+                        crate::strerror(&e)
+                    );
+                    continue;
+                }
+            },
+        };
+        if let Err(e) = result.and_then(|_| stdout.flush()) {
+            r_code = 1;
+            eprintln!("tail: error reading '{}': {}", file, crate::strerror(&e));
+        }
+    }
+
+    if follow && !followed.is_empty() {
+        let mut last = files.len() - 1;
+        loop {
+            std::thread::sleep(interval);
+            for (idx, reader) in followed.iter_mut() {
+                let header = (headers && last != *idx).then_some(files[*idx].as_str());
+                match follow_file(reader, &files[*idx], header, &mut stdout) {
+                    Ok(true) => last = *idx,
+                    Ok(false) => {}
+                    Err(e) => eprintln!("tail: {}: {}", files[*idx], crate::strerror(&e)),
+                }
+            }
+        }
+    }
+
+    r_code
+}
+
+fn parse_count(value: &str) -> Option<Count> {
+    match value.strip_prefix('+') {
+        Some(count) => count.parse().ok().map(Count::From),
+        None => value
+            .strip_prefix('-')
+            .unwrap_or(value)
+            .parse()
+            .ok()
+            .map(Count::Last),
+    }
+}
+
+fn tail(
+    reader: &mut impl BufRead,
+    writer: &mut impl Write,
+    lines: bool,
+    count: Count,
+) -> std::io::Result<()> {
+    let mut line = Vec::new();
+    match (lines, count) {
+        (true, Count::From(count)) => {
+            for _ in 1..count {
+                line.clear();
+                if reader.read_until(b'\n', &mut line)? == 0 {
+                    return Ok(());
+                }
+            }
+            std::io::copy(reader, writer)?;
+        }
+        (true, Count::Last(count)) => {
+            let mut kept = VecDeque::new();
+            loop {
+                line.clear();
+                if reader.read_until(b'\n', &mut line)? == 0 {
+                    break;
+                }
+                if count > 0 {
+                    if kept.len() as u64 == count {
+                        kept.pop_front();
+                    }
+                    kept.push_back(line.clone());
+                }
+            }
+            for line in kept {
+                writer.write_all(&line)?;
+            }
+        }
+        (false, Count::From(count)) => {
+            std::io::copy(
+                &mut reader.take(count.saturating_sub(1)),
+                &mut std::io::sink(),
+            )?;
+            std::io::copy(reader, writer)?;
+        }
+        (false, Count::Last(count)) => {
+            let mut kept = VecDeque::new();
+            loop {
+                let chunk = reader.fill_buf()?;
+                if chunk.is_empty() {
+                    break;
+                }
+                kept.extend(chunk);
+                let read = chunk.len();
+                reader.consume(read);
+                let excess = kept.len().saturating_sub(count as usize);
+                kept.drain(..excess);
+            }
+            let (front, back) = kept.as_slices();
+            writer.write_all(front)?;
+            writer.write_all(back)?;
+        }
+    }
+    Ok(())
+}
+
+// Copies whatever was appended since the last call and reports whether
+// anything was written.
+fn follow_file(
+    reader: &mut BufReader<File>,
+    name: &str,
+    header: Option<&str>,
+    writer: &mut impl Write,
+) -> std::io::Result<bool> {
+    let position = reader.stream_position()?;
+    if reader.get_ref().metadata()?.len() < position {
+        eprintln!("tail: {}: file truncated", name);
+        reader.seek(SeekFrom::Start(0))?;
+    }
+    let mut written = false;
+    loop {
+        let chunk = reader.fill_buf()?;
+        if chunk.is_empty() {
+            writer.flush()?;
+            return Ok(written);
+        }
+        if !written {
+            written = true;
+            if let Some(header) = header {
+                writeln!(writer, "\n==> {} <==", header)?;
+            }
+        }
+        writer.write_all(chunk)?;
+        let read = chunk.len();
+        reader.consume(read);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::tail::{Count, parse_count, tail};
+
+    fn check(lines: bool, count: &str, input: &str, expected: &str) {
+        let mut output = Vec::new();
+        tail(
+            &mut input.as_bytes(),
+            &mut output,
+            lines,
+            parse_count(count).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    #[test]
+    fn check_tail() {
+        assert_eq!(parse_count("+3"), Some(Count::From(3)));
+        assert_eq!(parse_count("-3"), Some(Count::Last(3)));
+        check(true, "2", "a\nb\nc\n", "b\nc\n");
+        check(true, "+2", "a\nb\nc", "b\nc");
+        check(true, "0", "a\nb\n", "");
+        check(false, "2", "abcdef", "ef");
+        check(false, "+3", "abcdef", "cdef");
+    }
+}
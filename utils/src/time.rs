@@ -0,0 +1,352 @@
+use std::sync::OnceLock;
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct DateTime {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+
+// A time zone read from a TZif file, with the POSIX rule from its footer (or
+// from $TZ itself) for times after the last transition.
+#[derive(Default)]
+struct Zone {
+    transitions: Vec<(i64, i64)>,
+    initial: i64,
+    rule: Option<Rule>,
+}
+
+struct Rule {
+    offset: i64,
+    dst: Option<(i64, Change, Change)>,
+}
+
+// `Mm.w.d/time`: day `d` of week `w` of month `m`, week 5 being the last.
+struct Change {
+    month: u32,
+    week: u32,
+    weekday: u32,
+    time: i64,
+}
+
+impl DateTime {
+    pub fn local(timestamp: i64) -> Self {
+        Self::utc(timestamp + zone().offset(timestamp))
+    }
+
+    pub fn utc(timestamp: i64) -> Self {
+        let (days, seconds) = (timestamp.div_euclid(86400), timestamp.rem_euclid(86400));
+        let (year, month, day) = civil_from_days(days);
+        Self {
+            year,
+            month,
+            day,
+            hour: (seconds / 3600) as u32,
+            minute: (seconds / 60 % 60) as u32,
+            second: (seconds % 60) as u32,
+        }
+    }
+
+    // The timestamp of this time in UTC, or `None` if a field is out of range.
+    pub fn to_timestamp(self) -> Option<i64> {
+        let timestamp = days_from_civil(self.year, self.month, self.day) * 86400
+            + (self.hour * 3600 + self.minute * 60 + self.second) as i64;
+        (Self::utc(timestamp) == self).then_some(timestamp)
+    }
+
+    pub fn to_local_timestamp(self) -> Option<i64> {
+        let naive = self.to_timestamp()?;
+        let guess = naive - zone().offset(naive);
+        Some(naive - zone().offset(guess))
+    }
+
+    pub fn month_name(&self) -> &'static str {
+        MONTHS[self.month as usize - 1]
+    }
+}
+
+impl Zone {
+    fn offset(&self, timestamp: i64) -> i64 {
+        let last = self.transitions.last().map(|(at, _)| *at);
+        if let Some(rule) = &self.rule
+            && last.is_none_or(|last| timestamp >= last)
+        {
+            return rule.offset(timestamp);
+        }
+        match self
+            .transitions
+            .iter()
+            .rposition(|(at, _)| *at <= timestamp)
+        {
+            Some(idx) => self.transitions[idx].1,
+            None => self.initial,
+        }
+    }
+}
+
+impl Rule {
+    fn offset(&self, timestamp: i64) -> i64 {
+        let Some((dst, start, end)) = &self.dst else {
+            return self.offset;
+        };
+        let year = DateTime::utc(timestamp + self.offset).year;
+        let start = start.timestamp(year) - self.offset;
+        let end = end.timestamp(year) - dst;
+        let in_dst = match start < end {
+            true => (start..end).contains(&timestamp),
+            false => !(end..start).contains(&timestamp),
+        };
+        if in_dst { *dst } else { self.offset }
+    }
+}
+
+impl Change {
+    // Local seconds since the epoch at which the change happens in `year`.
+    fn timestamp(&self, year: i64) -> i64 {
+        let first = days_from_civil(year, self.month, 1);
+        let first_weekday = (first + 4).rem_euclid(7) as u32;
+        let mut day = (self.weekday + 7 - first_weekday) % 7 + (self.week - 1) * 7;
+        while day >= days_in_month(year, self.month) {
+            day -= 7;
+        }
+        (first + day as i64) * 86400 + self.time
+    }
+}
+
+fn zone() -> &'static Zone {
+    static ZONE: OnceLock<Zone> = OnceLock::new();
+    ZONE.get_or_init(|| {
+        let tz = std::env::var("TZ").ok();
+        let path = match tz.as_deref().map(|tz| tz.strip_prefix(':').unwrap_or(tz)) {
+            None => "/etc/localtime".to_string(),
+            Some(tz) if tz.starts_with('/') => tz.to_string(),
+            Some(tz) => format!("/usr/share/zoneinfo/{}", tz),
+        };
+        std::fs::read(path)
+            .ok()
+            .and_then(|data| parse_tzif(&data))
+            .or_else(|| {
+                let rule = parse_rule(tz.as_deref()?)?;
+                Some(Zone {
+                    transitions: vec![],
+                    initial: rule.offset,
+                    rule: Some(rule),
+                })
+            })
+            .unwrap_or_default()
+    })
+}
+
+fn parse_tzif(data: &[u8]) -> Option<Zone> {
+    let number = |data: &[u8], at: usize, size: usize| -> Option<i64> {
+        let bytes = data.get(at..at + size)?;
+        let value = bytes
+            .iter()
+            .fold(0u64, |value, byte| value << 8 | *byte as u64);
+        let shift = 64 - 8 * size as u32;
+        Some(((value << shift) as i64) >> shift)
+    };
+    let counts = |data: &[u8]| -> Option<Vec<usize>> {
+        if !data.starts_with(b"TZif") {
+            return None;
+        }
+        (0..6)
+            .map(|idx| number(data, 20 + 4 * idx, 4).map(|count| count as usize))
+            .collect()
+    };
+
+    let version = *data.get(4)?;
+    let (data, size) = match version {
+        0 => (data, 4),
+        _ => {
+            let [ut, std, leap, times, types, chars] = counts(data)?[..] else {
+                return None;
+            };
+            let skipped = 44 + times * 5 + types * 6 + chars + leap * 8 + std + ut;
+            (data.get(skipped..)?, 8)
+        }
+    };
+    let [ut, std, leap, times, types, chars] = counts(data)?[..] else {
+        return None;
+    };
+    let indices = 44 + times * size;
+    let infos = indices + times;
+    let offset = |idx: usize| number(data, infos + idx * 6, 4);
+
+    let mut zone = Zone {
+        initial: offset(0)?,
+        ..Default::default()
+    };
+    for idx in 0..times {
+        let at = number(data, 44 + idx * size, size)?;
+        let info = *data.get(indices + idx)? as usize;
+        zone.transitions.push((at, offset(info)?));
+    }
+    if size == 8 {
+        let footer = infos + types * 6 + chars + leap * (size + 4) + std + ut;
+        let footer = String::from_utf8_lossy(data.get(footer..)?);
+        zone.rule = parse_rule(footer.trim());
+    }
+    Some(zone)
+}
+
+// Parses a POSIX TZ string such as `CET-1CEST,M3.5.0,M10.5.0/3`. Offsets in
+// it count west of Greenwich, the opposite of TZif.
+fn parse_rule(text: &str) -> Option<Rule> {
+    let mut rest = text;
+    take_name(&mut rest)?;
+    let offset = -take_duration(&mut rest)?;
+    if rest.is_empty() {
+        return Some(Rule { offset, dst: None });
+    }
+    take_name(&mut rest)?;
+    let dst = match rest.starts_with(',') || rest.is_empty() {
+        true => offset + 3600,
+        false => -take_duration(&mut rest)?,
+    };
+    let rest = match rest {
+        "" => "M3.2.0,M11.1.0",
+        rest => rest.strip_prefix(',')?,
+    };
+    let (start, end) = rest.split_once(',')?;
+    Some(Rule {
+        offset,
+        dst: Some((dst, parse_change(start)?, parse_change(end)?)),
+    })
+}
+
+fn parse_change(text: &str) -> Option<Change> {
+    let (date, time) = text.split_once('/').unwrap_or((text, "2"));
+    let mut fields = date
+        .strip_prefix('M')?
+        .split('.')
+        .map(|field| field.parse());
+    let (Some(Ok(month)), Some(Ok(week)), Some(Ok(weekday)), None) =
+        (fields.next(), fields.next(), fields.next(), fields.next())
+    else {
+        return None;
+    };
+    let mut time = time;
+    let time = take_duration(&mut time).filter(|_| time.is_empty())?;
+    ((1..=12).contains(&month) && (1..=5).contains(&week) && weekday < 7).then_some(Change {
+        month,
+        week,
+        weekday,
+        time,
+    })
+}
+
+fn take_name<'a>(text: &mut &'a str) -> Option<&'a str> {
+    let (name, rest) = match text.strip_prefix('<') {
+        Some(quoted) => {
+            let (name, rest) = quoted.split_once('>')?;
+            (name, rest)
+        }
+        None => {
+            let length = text
+                .find(|char: char| !char.is_ascii_alphabetic())
+                .unwrap_or(text.len());
+            text.split_at(length)
+        }
+    };
+    *text = rest;
+    (!name.is_empty()).then_some(name)
+}
+
+// `[+-]hh[:mm[:ss]]` in seconds.
+fn take_duration(text: &mut &str) -> Option<i64> {
+    let (sign, rest) = match text.as_bytes().first() {
+        Some(b'-') => (-1, &text[1..]),
+        Some(b'+') => (1, &text[1..]),
+        _ => (1, *text),
+    };
+    let length = rest
+        .find(|char: char| !char.is_ascii_digit() && char != ':')
+        .unwrap_or(rest.len());
+    let mut seconds = 0;
+    for (idx, part) in rest[..length].split(':').enumerate() {
+        if idx > 2 {
+            return None;
+        }
+        seconds += part.parse::<i64>().ok()? * [3600, 60, 1][idx];
+    }
+    *text = &rest[length..];
+    Some(sign * seconds)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Days since 1970-01-01 in the proleptic Gregorian calendar, and back.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = (month + 9) % 12;
+    let day_of_year = (153 * month as i64 + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month + 2) / 5 + 1) as u32;
+    let month = if month < 10 { month + 3 } else { month - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::time::{DateTime, civil_from_days, days_from_civil, parse_rule};
+
+    #[test]
+    fn check_time() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11017);
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(19416), (2023, 2, 28));
+        assert_eq!(
+            DateTime::utc(951827696),
+            DateTime {
+                year: 2000,
+                month: 2,
+                day: 29,
+                hour: 12,
+                minute: 34,
+                second: 56,
+            }
+        );
+
+        let berlin = parse_rule("CET-1CEST,M3.5.0,M10.5.0/3").unwrap();
+        assert_eq!(berlin.offset(1704067200), 3600);
+        assert_eq!(berlin.offset(1719792000), 7200);
+        // 2024-03-31 00:59:59 and 01:00:00 UTC, around the switch to summer time.
+        assert_eq!(berlin.offset(1711846799), 3600);
+        assert_eq!(berlin.offset(1711846800), 7200);
+        let sydney = parse_rule("AEST-10AEDT,M10.1.0,M4.1.0/3").unwrap();
+        assert_eq!(sydney.offset(1704067200), 39600);
+        assert_eq!(sydney.offset(1719792000), 36000);
+        assert_eq!(parse_rule("<+0330>-3:30").unwrap().offset(0), 12600);
+        assert!(parse_rule("CET-1CEST,M13.5.0,M10.5.0").is_none());
+    }
+}
@@ -0,0 +1,205 @@
+use {
+    crate::{
+        getopt::{self, Args, Opt},
+        time::DateTime,
+    },
+    std::{
+        fs::{File, FileTimes},
+        io::ErrorKind,
+        time::{Duration, SystemTime},
+    },
+};
+
+pub fn run(args: Vec<String>) -> i32 {
+    let mut access = false;
+    let mut modification = false;
+    let mut no_create = false;
+    let mut times = None;
+    let mut files = Vec::new();
+    let mut args = Args::new(args);
+    while let Some(opt) = args.next() {
+        match opt {
+            opt if opt.is('a', "") => access = true,
+            opt if opt.is('m', "") => modification = true,
+            opt if opt.is('c', "no-create") => no_create = true,
+            opt if opt.is('d', "date") || opt.is('t', "") => {
+                let Some(value) = args.value() else {
+                    return getopt::missing("touch", if opt.is('t', "") { "t" } else { "d" });
+                };
+                let now = SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .map_or(0, |now| now.as_secs() as i64);
+                let parsed = match opt.is('t', "") {
+                    true => parse_stamp(&value, now),
+                    false => parse_date(&value, now),
+                };
+                let Some(timestamp) = parsed else {
+                    eprintln!("touch: invalid date format '{}'", value);
+                    return 1;
+                };
+                let time = system_time(timestamp);
+                times = Some((time, time));
+            }
+            opt if opt.is('r', "reference") => {
+                let Some(reference) = args.value() else {
+                    return getopt::missing("touch", "r");
+                };
+                let metadata = std::fs::metadata(&reference)
+                    .and_then(|metadata| Ok((metadata.accessed()?, metadata.modified()?)));
+                match metadata {
+                    Ok(reference) => times = Some(reference),
+                    Err(e) => {
+                        eprintln!(
+                            "touch: failed to get attributes of '{}': {}",
+                            reference,
+                            crate::strerror(&e)
+                        );
+                        return 1;
+                    }
+                }
+            }
+            Opt::Operand(file) => files.push(file),
+            opt => return getopt::unknown("touch", &opt),
+        }
+    }
+    if files.is_empty() {
+        eprintln!("touch: missing file operand");
+        return 1;
+    }
+    if !access && !modification {
+        (access, modification) = (true, true);
+    }
+    let (accessed, modified) = times.unwrap_or_else(|| (SystemTime::now(), SystemTime::now()));
+    let mut file_times = FileTimes::new();
+    if access {
+        file_times = file_times.set_accessed(accessed);
+    }
+    if modification {
+        file_times = file_times.set_modified(modified);
+    }
+
+    let mut r_code = 0;
+    for file in &files {
+        // Directories and read-only files can only be opened for reading,
+        // missing files are created by opening them for writing.
+        let opened = File::open(file).or_else(|e| match e.kind() {
+            ErrorKind::NotFound if no_create => Err(e),
+            _ => File::options().write(true).create(!no_create).open(file),
+        });
+        let opened = match opened {
+            Ok(opened) => opened,
+            Err(e) if no_create && e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => {
+                eprintln!("touch: cannot touch '{}': {}", file, crate::strerror(&e));
+                r_code = 1;
+                continue;
+            }
+        };
+        if let Err(e) = opened.set_times(file_times) {
+            eprintln!(
+                "touch: setting times of '{}': {}",
+                file,
+                crate::strerror(&e)
+            );
+            r_code = 1;
+        }
+    }
+
+    r_code
+}
+
+fn system_time(timestamp: i64) -> SystemTime {
+    match timestamp >= 0 {
+        true => SystemTime::UNIX_EPOCH + Duration::from_secs(timestamp as u64),
+        false => SystemTime::UNIX_EPOCH - Duration::from_secs(timestamp.unsigned_abs()),
+    }
+}
+
+// Accepts `now`, `@SECONDS` and `YYYY-MM-DD[( |T)HH:MM[:SS]]`, in local time
+// unless followed by `Z` or ` UTC`.
+fn parse_date(text: &str, now: i64) -> Option<i64> {
+    if text == "now" {
+        return Some(now);
+    }
+    if let Some(seconds) = text.strip_prefix('@') {
+        return seconds.parse().ok();
+    }
+    let (text, utc) = match text.strip_suffix('Z').or(text.strip_suffix(" UTC")) {
+        Some(text) => (text, true),
+        None => (text, false),
+    };
+    let (date, time) = text.split_once(['T', ' ']).unwrap_or((text, "00:00"));
+    let mut date = date.split('-');
+    let mut time = time.split(':');
+    let date_time = DateTime {
+        year: date.next()?.parse().ok()?,
+        month: date.next()?.parse().ok()?,
+        day: date.next()?.parse().ok()?,
+        hour: time.next()?.parse().ok()?,
+        minute: time.next()?.parse().ok()?,
+        second: time.next().map_or(Some(0), |second| second.parse().ok())?,
+    };
+    if date.next().is_some() || time.next().is_some() {
+        return None;
+    }
+    match utc {
+        true => date_time.to_timestamp(),
+        false => date_time.to_local_timestamp(),
+    }
+}
+
+// `[[CC]YY]MMDDhhmm[.ss]` in local time, as for `touch -t`.
+fn parse_stamp(text: &str, now: i64) -> Option<i64> {
+    let (digits, second) = match text.split_once('.') {
+        Some((digits, second)) if second.len() == 2 => (digits, second.parse().ok()?),
+        Some(_) => return None,
+        None => (text, 0),
+    };
+    if !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    let (year, rest) = match digits.len() {
+        8 => (DateTime::local(now).year, digits),
+        10 => {
+            let year: i64 = digits[..2].parse().ok()?;
+            (
+                if year < 69 { 2000 + year } else { 1900 + year },
+                &digits[2..],
+            )
+        }
+        12 => (digits[..4].parse().ok()?, &digits[4..]),
+        _ => return None,
+    };
+    let field = |idx: usize| rest[idx * 2..idx * 2 + 2].parse().ok();
+    DateTime {
+        year,
+        month: field(0)?,
+        day: field(1)?,
+        hour: field(2)?,
+        minute: field(3)?,
+        second,
+    }
+    .to_local_timestamp()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::touch::{parse_date, parse_stamp};
+
+    #[test]
+    fn check_dates() {
+        assert_eq!(parse_date("now", 42), Some(42));
+        assert_eq!(parse_date("@-5", 0), Some(-5));
+        assert_eq!(parse_date("2000-02-29T12:34:56Z", 0), Some(951827696));
+        assert_eq!(parse_date("2000-02-29 12:34 UTC", 0), Some(951827640));
+        assert_eq!(parse_date("2001-02-29", 0), None);
+        assert_eq!(parse_date("2000-02-29 25:00", 0), None);
+        assert_eq!(parse_date("yesterday", 0), None);
+        let local = parse_date("2000-02-29 12:34:56", 0).unwrap();
+        assert_eq!(parse_stamp("200002291234.56", 0), Some(local));
+        assert_eq!(parse_stamp("0002291234.56", 0), Some(local));
+        assert_eq!(parse_stamp("02291234", 951827696), Some(local - 56));
+        assert_eq!(parse_stamp("0229123", 0), None);
+        assert_eq!(parse_stamp("02291234.5", 0), None);
+    }
+}
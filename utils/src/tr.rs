@@ -0,0 +1,310 @@
+use {
+    crate::getopt::{self, Args, Opt},
+    std::io::{ErrorKind, Read, Write},
+};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+type Class = fn(&u8) -> bool;
+
+const CLASSES: [(&str, Class); 12] = [
+    ("alnum", u8::is_ascii_alphanumeric),
+    ("alpha", u8::is_ascii_alphabetic),
+    ("blank", |byte| *byte == b' ' || *byte == b'\t'),
+    ("cntrl", u8::is_ascii_control),
+    ("digit", u8::is_ascii_digit),
+    ("graph", u8::is_ascii_graphic),
+    ("lower", u8::is_ascii_lowercase),
+    ("print", |byte| byte.is_ascii_graphic() || *byte == b' '),
+    ("punct", u8::is_ascii_punctuation),
+    ("space", |byte| byte.is_ascii_whitespace() || *byte == 0x0b),
+    ("upper", u8::is_ascii_uppercase),
+    ("xdigit", u8::is_ascii_hexdigit),
+];
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Byte(u8),
+    Hyphen,
+    Class(Vec<u8>),
+    Repeat(u8, Option<usize>),
+}
+
+struct Tr {
+    map: [u8; 256],
+    delete: [bool; 256],
+    squeeze: [bool; 256],
+    last: Option<u8>,
+}
+
+pub fn run(args: Vec<String>) -> i32 {
+    let mut complement = false;
+    let mut delete = false;
+    let mut squeeze = false;
+    let mut truncate = false;
+    let mut sets = Vec::new();
+    for opt in Args::new(args) {
+        match opt {
+            opt if opt.is('c', "complement") || opt.is('C', "complement") => complement = true,
+            opt if opt.is('d', "delete") => delete = true,
+            opt if opt.is('s', "squeeze-repeats") => squeeze = true,
+            opt if opt.is('t', "truncate-set1") => truncate = true,
+            Opt::Operand(set) => sets.push(set),
+            opt => return getopt::unknown("tr", &opt),
+        }
+    }
+    let expected = match (delete, squeeze) {
+        (true, true) => 2,
+        (true, false) => 1,
+        (false, true) => sets.len().clamp(1, 2),
+        (false, false) => 2,
+    };
+    if sets.len() != expected {
+        match sets.len() < expected {
+            true => eprintln!("tr: missing operand"),
+            false => eprintln!("tr: extra operand '{}'", sets[expected]),
+        }
+        return 1;
+    }
+
+    let tr = match Tr::new(&sets, complement, delete, squeeze, truncate) {
+        Ok(tr) => tr,
+        Err(e) => {
+            eprintln!("tr: {}", e);
+            return 1;
+        }
+    };
+    match tr.filter(std::io::stdin().lock(), std::io::stdout().lock()) {
+        Ok(_) => 0,
+        Err(e) => {
+            eprintln!("tr: {}", crate::strerror(&e));
+            1
+        }
+    }
+}
+
+impl Tr {
+    fn new(
+        sets: &[String],
+        complement: bool,
+        delete: bool,
+        squeeze: bool,
+        truncate: bool,
+    ) -> Result<Self, String> {
+        let mut set1 = expand(&tokenize(sets[0].as_bytes()), None)?;
+        if complement {
+            set1 = (0..=255).filter(|byte| !set1.contains(byte)).collect();
+        }
+        let set2 = match sets.get(1) {
+            Some(set) => Some(expand(&tokenize(set.as_bytes()), Some(set1.len()))?),
+            None => None,
+        };
+
+        let mut tr = Tr {
+            map: std::array::from_fn(|byte| byte as u8),
+            delete: [false; 256],
+            squeeze: [false; 256],
+            last: None,
+        };
+        let squeezed = match (delete, set2) {
+            (true, set2) => {
+                set1.iter()
+                    .for_each(|byte| tr.delete[*byte as usize] = true);
+                set2.unwrap_or_default()
+            }
+            (false, Some(set2)) => {
+                if set2.is_empty() && !set1.is_empty() {
+                    return Err("when not truncating set1, string2 must be non-empty".to_string());
+                }
+                let length = if truncate {
+                    set1.len().min(set2.len())
+                } else {
+                    set1.len()
+                };
+                for (idx, byte) in set1[..length].iter().enumerate() {
+                    tr.map[*byte as usize] = set2[idx.min(set2.len() - 1)];
+                }
+                set2
+            }
+            (false, None) => set1,
+        };
+        if squeeze {
+            squeezed
+                .iter()
+                .for_each(|byte| tr.squeeze[*byte as usize] = true);
+        }
+        Ok(tr)
+    }
+
+    fn filter(mut self, mut reader: impl Read, mut writer: impl Write) -> std::io::Result<()> {
+        let mut chunk = vec![0; CHUNK_SIZE];
+        let mut output = Vec::with_capacity(CHUNK_SIZE);
+        loop {
+            let read = match reader.read(&mut chunk) {
+                Ok(0) => return writer.flush(),
+                Ok(read) => read,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            output.clear();
+            for &byte in &chunk[..read] {
+                if self.delete[byte as usize] {
+                    continue;
+                }
+                let byte = self.map[byte as usize];
+                if self.squeeze[byte as usize] && self.last == Some(byte) {
+                    continue;
+                }
+                self.last = Some(byte);
+                output.push(byte);
+            }
+            writer.write_all(&output)?;
+        }
+    }
+}
+
+fn tokenize(set: &[u8]) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+    while idx < set.len() {
+        if set[idx] == b'['
+            && let Some((token, length)) = bracket(&set[idx..])
+        {
+            tokens.push(token);
+            idx += length;
+            continue;
+        }
+        let token = match set[idx] {
+            b'\\' if idx + 1 < set.len() => {
+                idx += 1;
+                Token::Byte(match set[idx] {
+                    b'a' => 0x07,
+                    b'b' => 0x08,
+                    b'f' => 0x0c,
+                    b'n' => b'\n',
+                    b'r' => b'\r',
+                    b't' => b'\t',
+                    b'v' => 0x0b,
+                    b'0'..=b'7' => {
+                        let length = set[idx..]
+                            .iter()
+                            .take(3)
+                            .take_while(|byte| (b'0'..=b'7').contains(*byte))
+                            .count();
+                        let value = set[idx..idx + length]
+                            .iter()
+                            .fold(0u32, |value, byte| value * 8 + (byte - b'0') as u32);
+                        idx += length - 1;
+                        value as u8
+                    }
+                    other => other,
+                })
+            }
+            b'-' => Token::Hyphen,
+            byte => Token::Byte(byte),
+        };
+        tokens.push(token);
+        idx += 1;
+    }
+    tokens
+}
+
+// Recognizes `[:class:]`, `[=c=]`, `[c*]` and `[c*n]` at the start of `set`.
+fn bracket(set: &[u8]) -> Option<(Token, usize)> {
+    let end = set.iter().skip(2).position(|byte| *byte == b']')? + 3;
+    let inner = &set[1..end - 1];
+    match inner {
+        [b':', name @ .., b':'] => {
+            let name = std::str::from_utf8(name).ok()?;
+            let (_, class) = CLASSES.iter().find(|(class, _)| *class == name)?;
+            Some((Token::Class((0..=255).filter(class).collect()), end))
+        }
+        [b'=', byte, b'='] => Some((Token::Class(vec![*byte]), end)),
+        [byte, b'*', count @ ..] => {
+            let count = std::str::from_utf8(count).ok()?;
+            let count = match count {
+                "" => None,
+                octal if octal.starts_with('0') => Some(usize::from_str_radix(octal, 8).ok()?),
+                decimal => Some(decimal.parse().ok()?),
+            };
+            Some((Token::Repeat(*byte, count.filter(|count| *count > 0)), end))
+        }
+        _ => None,
+    }
+}
+
+fn expand(tokens: &[Token], fill_to: Option<usize>) -> Result<Vec<u8>, String> {
+    let mut set = Vec::new();
+    let mut fill = None;
+    let mut idx = 0;
+    while idx < tokens.len() {
+        match (&tokens[idx], tokens.get(idx + 1), tokens.get(idx + 2)) {
+            (Token::Byte(start), Some(Token::Hyphen), Some(Token::Byte(end))) => {
+                if end < start {
+                    return Err(format!(
+                        "range-endpoints of '{}-{}' are in reverse collating sequence order",
+                        *start as char, *end as char
+                    ));
+                }
+                set.extend(*start..=*end);
+                idx += 3;
+                continue;
+            }
+            (Token::Byte(byte), _, _) => set.push(*byte),
+            (Token::Hyphen, _, _) => set.push(b'-'),
+            (Token::Class(bytes), _, _) => set.extend(bytes),
+            (Token::Repeat(byte, Some(count)), _, _) => {
+                set.extend(std::iter::repeat_n(*byte, *count))
+            }
+            (Token::Repeat(byte, None), _, _) => match fill_to {
+                Some(_) if fill.is_none() => fill = Some((set.len(), *byte)),
+                Some(_) => {
+                    return Err("only one [c*] repeat construct may appear in string2".to_string());
+                }
+                None => {
+                    return Err("the [c*] repeat construct may not appear in string1".to_string());
+                }
+            },
+        }
+        idx += 1;
+    }
+    if let (Some((position, byte)), Some(length)) = (fill, fill_to) {
+        let count = length.saturating_sub(set.len());
+        set.splice(position..position, std::iter::repeat_n(byte, count));
+    }
+    Ok(set)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::tr::Tr;
+
+    fn check(sets: &[&str], flags: &str, input: &str, expected: &str) {
+        let sets: Vec<String> = sets.iter().map(|set| set.to_string()).collect();
+        let tr = Tr::new(
+            &sets,
+            flags.contains('c'),
+            flags.contains('d'),
+            flags.contains('s'),
+            flags.contains('t'),
+        )
+        .unwrap();
+        let mut output = Vec::new();
+        tr.filter(input.as_bytes(), &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    #[test]
+    fn check_tr() {
+        check(&["a-z", "A-Z"], "", "hello, world", "HELLO, WORLD");
+        check(&["[:lower:]", "[:upper:]"], "", "abc", "ABC");
+        check(&["abc", "x"], "", "aabbcc", "xxxxxx");
+        check(&["abc", "xy"], "t", "abc", "xyc");
+        check(&["a-c", "[x*]"], "", "abcd", "xxxd");
+        check(&["[:digit:]"], "d", "a1b22c", "abc");
+        check(&["[:alpha:]\\n"], "cd", "a1b2\n", "ab\n");
+        check(&[" "], "s", "a   b  c", "a b c");
+        check(&["a-z", "\\n"], "cs", "ab12cd\n\n", "ab\ncd\n");
+        check(&["ab", "-"], "", "a-b", "---");
+    }
+}
@@ -0,0 +1,204 @@
+use {
+    crate::getopt::{self, Args, Opt},
+    std::io::{BufRead, BufWriter, Write},
+};
+
+#[derive(Default)]
+struct Uniq {
+    count: bool,
+    repeated: bool,
+    unique: bool,
+    ignore_case: bool,
+    skip_fields: usize,
+    skip_chars: usize,
+    check_chars: Option<usize>,
+}
+
+pub fn run(args: Vec<String>) -> i32 {
+    let mut uniq = Uniq::default();
+    let mut operands = Vec::new();
+    let mut args = Args::new(args);
+    while let Some(opt) = args.next() {
+        let target = match opt {
+            opt if opt.is('c', "count") => {
+                uniq.count = true;
+                continue;
+            }
+            opt if opt.is('d', "repeated") => {
+                uniq.repeated = true;
+                continue;
+            }
+            opt if opt.is('u', "unique") => {
+                uniq.unique = true;
+                continue;
+            }
+            opt if opt.is('i', "ignore-case") => {
+                uniq.ignore_case = true;
+                continue;
+            }
+            Opt::Operand(operand) => {
+                operands.push(operand);
+                continue;
+            }
+            opt if opt.is('f', "skip-fields") => &mut uniq.skip_fields,
+            opt if opt.is('s', "skip-chars") => &mut uniq.skip_chars,
+            opt if opt.is('w', "check-chars") => uniq.check_chars.insert(0),
+            opt => return getopt::unknown("uniq", &opt),
+        };
+        match args.value().map(|value| (value.parse(), value)) {
+            Some((Ok(number), _)) => *target = number,
+            Some((Err(_), value)) => {
+                eprintln!("uniq: {}: invalid number", value);
+                return 1;
+            }
+            None => {
+                eprintln!("uniq: option requires an argument");
+                return 1;
+            }
+        }
+    }
+    if operands.len() > 2 {
+        return getopt::unknown("uniq", &Opt::Operand(operands.swap_remove(2)));
+    }
+
+    let input = operands.first().map_or("-", |operand| operand.as_str());
+    let mut reader = match getopt::open(input) {
+        Ok(reader) => reader,
+        Err(e) => {
+            eprintln!("uniq: {}: {}", input, crate::strerror(&e));
+            return 1;
+        }
+    };
+    let result = match operands.get(1) {
+        Some(output) => std::fs::File::create(output)
+            .and_then(|file| uniq.filter(&mut reader, &mut BufWriter::new(file))),
+        None => uniq.filter(&mut reader, &mut BufWriter::new(std::io::stdout().lock())),
+    };
+    match result {
+        Ok(_) => 0,
+        Err(e) => {
+            eprintln!("uniq: {}", crate::strerror(&e));
+            1
+        }
+    }
+}
+
+impl Uniq {
+    fn filter(&self, reader: &mut dyn BufRead, writer: &mut impl Write) -> std::io::Result<()> {
+        let mut current: Option<(Vec<u8>, usize)> = None;
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            if line.last() == Some(&b'\n') {
+                line.pop();
+            }
+            match current.as_mut() {
+                Some((previous, count)) if self.equal(previous, &line) => *count += 1,
+                _ => {
+                    if let Some((previous, count)) = current.replace((line.clone(), 1)) {
+                        self.write(writer, &previous, count)?;
+                    }
+                }
+            }
+        }
+        if let Some((previous, count)) = current {
+            self.write(writer, &previous, count)?;
+        }
+        writer.flush()
+    }
+
+    fn write(&self, writer: &mut impl Write, line: &[u8], count: usize) -> std::io::Result<()> {
+        let selected = match (self.repeated, self.unique) {
+            (false, false) => true,
+            (true, false) => count > 1,
+            (false, true) => count == 1,
+            (true, true) => false,
+        };
+        if !selected {
+            return Ok(());
+        }
+        if self.count {
+            write!(writer, "{:>7} ", count)?;
+        }
+        writer.write_all(line)?;
+        writer.write_all(b"\n")
+    }
+
+    fn equal(&self, left: &[u8], right: &[u8]) -> bool {
+        let (left, right) = (self.key(left), self.key(right));
+        match self.ignore_case {
+            true => left.eq_ignore_ascii_case(right),
+            false => left == right,
+        }
+    }
+
+    fn key<'a>(&self, line: &'a [u8]) -> &'a [u8] {
+        let mut start = 0;
+        for _ in 0..self.skip_fields {
+            start += line[start..]
+                .iter()
+                .take_while(|byte| is_blank(**byte))
+                .count();
+            start += line[start..]
+                .iter()
+                .take_while(|byte| !is_blank(**byte))
+                .count();
+        }
+        let start = (start + self.skip_chars).min(line.len());
+        let end = match self.check_chars {
+            Some(chars) => (start + chars).min(line.len()),
+            None => line.len(),
+        };
+        &line[start..end]
+    }
+}
+
+fn is_blank(byte: u8) -> bool {
+    byte == b' ' || byte == b'\t'
+}
+
+#[cfg(test)]
+mod test {
+    use crate::uniq::Uniq;
+
+    fn check(uniq: Uniq, input: &str, expected: &str) {
+        let mut output = Vec::new();
+        uniq.filter(&mut input.as_bytes(), &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    #[test]
+    fn check_uniq() {
+        let input = "a\na\nb\nA\nc\nc\nc";
+        check(Uniq::default(), input, "a\nb\nA\nc\n");
+        let counted = Uniq {
+            count: true,
+            ..Default::default()
+        };
+        check(
+            counted,
+            input,
+            "      2 a\n      1 b\n      1 A\n      3 c\n",
+        );
+        let repeated = Uniq {
+            repeated: true,
+            ..Default::default()
+        };
+        check(repeated, input, "a\nc\n");
+        let unique = Uniq {
+            unique: true,
+            ignore_case: true,
+            ..Default::default()
+        };
+        check(unique, input, "b\nA\n");
+        let fields = Uniq {
+            skip_fields: 1,
+            check_chars: Some(2),
+            ..Default::default()
+        };
+        check(fields, "1 ab\n2 abc\n3 b\n", "1 ab\n3 b\n");
+    }
+}
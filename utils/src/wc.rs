@@ -0,0 +1,337 @@
+use std::io::{ErrorKind, Read};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Default, Clone, Copy)]
+struct Counts {
+    lines: usize,
+    words: usize,
+    chars: usize,
+    bytes: usize,
+    max_line: usize,
+}
+
+impl Counts {
+    fn add(&mut self, other: &Counts) {
+        self.lines += other.lines;
+        self.words += other.words;
+        self.chars += other.chars;
+        self.bytes += other.bytes;
+        self.max_line = self.max_line.max(other.max_line);
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq)]
+struct Columns {
+    lines: bool,
+    words: bool,
+    chars: bool,
+    bytes: bool,
+    max_line: bool,
+}
+
+impl Columns {
+    fn select(&self, counts: &Counts) -> Vec<usize> {
+        [
+            (self.lines, counts.lines),
+            (self.words, counts.words),
+            (self.chars, counts.chars),
+            (self.bytes, counts.bytes),
+            (self.max_line, counts.max_line),
+        ]
+        .into_iter()
+        .filter_map(|(selected, count)| selected.then_some(count))
+        .collect()
+    }
+}
+
+pub fn run(args: Vec<String>) -> i32 {
+    let mut columns = Columns::default();
+    let mut files = Vec::new();
+    let mut options_done = false;
+    for arg in &args[1..] {
+        if options_done || arg == "-" || !arg.starts_with('-') {
+            files.push(arg.as_str());
+            continue;
+        }
+        let flags: Vec<char> = match arg.as_str() {
+            "--" => {
+                options_done = true;
+                continue;
+            }
+            "--lines" => vec!['l'],
+            "--words" => vec!['w'],
+            "--chars" => vec!['m'],
+            "--bytes" => vec!['c'],
+            "--max-line-length" => vec!['L'],
+            long if long.starts_with("--") => {
+                eprintln!("wc: unrecognized option '{}'", long);
+                return 1;
+            }
+            short => short[1..].chars().collect(),
+        };
+        for flag in flags {
+            match flag {
+                'l' => columns.lines = true,
+                'w' => columns.words = true,
+                'm' => columns.chars = true,
+                'c' => columns.bytes = true,
+                'L' => columns.max_line = true,
+                flag => {
+                    eprintln!("wc: invalid option -- '{}'", flag);
+                    return 1;
+                }
+            }
+        }
+    }
+    if columns == Columns::default() {
+        columns = Columns {
+            lines: true,
+            words: true,
+            bytes: true,
+            ..Default::default()
+        };
+    }
+
+    let named = !files.is_empty();
+    if !named {
+        files.push("-");
+    }
+    let width = match columns.select(&Counts::default()).len() == 1 && files.len() == 1 {
+        true => 1,
+        false => number_width(&files),
+    };
+
+    let mut r_code = 0;
+    let mut total = Counts::default();
+    for file in &files {
+        let result = match *file {
+            "-" => calculate(std::io::stdin().lock()),
+            path => std::fs::File::open(path).and_then(calculate),
+        };
+        match result {
+            Ok(counts) => {
+                total.add(&counts);
+                print_row(&columns.select(&counts), width, named.then_some(file));
+            }
+            Err(e) => {
+                r_code = 1;
+                eprintln!("wc: {}: {}", file, crate::strerror(&e));
+            }
+        }
+    }
+    if files.len() > 1 {
+        print_row(&columns.select(&total), width, Some("total"));
+    }
+
+    r_code
+}
+
+fn print_row(counts: &[usize], width: usize, name: Option<&str>) {
+    let mut row: Vec<String> = counts
+        .iter()
+        .map(|count| format!("{:>width$}", count, width = width))
+        .collect();
+    row.extend(name.map(String::from));
+    println!("{}", row.join(" "));
+}
+
+fn number_width(files: &[&str]) -> usize {
+    let mut minimum = 1;
+    let mut total = 0;
+    for file in files {
+        match std::fs::metadata(file) {
+            Ok(metadata) if *file != "-" && metadata.is_file() => total += metadata.len(),
+            Ok(_) => minimum = 7,
+            Err(_) if *file == "-" => minimum = 7,
+            Err(_) => {}
+        }
+    }
+    total.to_string().len().max(minimum)
+}
+
+fn calculate(mut reader: impl Read) -> std::io::Result<Counts> {
+    let mut counter = Counter::default();
+    let mut chunk = vec![0; CHUNK_SIZE];
+    loop {
+        match reader.read(&mut chunk) {
+            Ok(0) => return Ok(counter.finish()),
+            Ok(read) => counter.update(&chunk[..read]),
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Counter {
+    counts: Counts,
+    in_word: bool,
+    line_width: usize,
+    pending: Vec<u8>,
+}
+
+impl Counter {
+    fn update(&mut self, chunk: &[u8]) {
+        self.counts.bytes += chunk.len();
+        match self.pending.is_empty() {
+            true => self.decode(chunk),
+            false => {
+                let mut joined = std::mem::take(&mut self.pending);
+                joined.extend_from_slice(chunk);
+                self.decode(&joined);
+            }
+        }
+    }
+
+    fn decode(&mut self, bytes: &[u8]) {
+        let mut idx = 0;
+        while idx < bytes.len() {
+            match decode_char(&bytes[idx..]) {
+                Decoded::Char(code, len) => {
+                    match char::from_u32(code) {
+                        Some(char) => self.count(char),
+                        None => self.counts.chars += 1,
+                    }
+                    idx += len;
+                }
+                Decoded::Invalid => idx += 1,
+                Decoded::Incomplete => {
+                    self.pending = bytes[idx..].to_vec();
+                    return;
+                }
+            }
+        }
+    }
+
+    fn count(&mut self, char: char) {
+        self.counts.chars += 1;
+        match char {
+            '\n' | '\r' | '\x0c' => {
+                self.counts.lines += usize::from(char == '\n');
+                self.end_line();
+                self.end_word();
+            }
+            '\t' => {
+                self.line_width += 8 - self.line_width % 8;
+                self.end_word();
+            }
+            ' ' => {
+                self.line_width += 1;
+                self.end_word();
+            }
+            '\x0b' => self.end_word(),
+            char if !char.is_control() => {
+                self.line_width += char_width(char);
+                match is_space(char) {
+                    true => self.end_word(),
+                    false => self.in_word = true,
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn end_word(&mut self) {
+        self.counts.words += usize::from(self.in_word);
+        self.in_word = false;
+    }
+
+    fn end_line(&mut self) {
+        self.counts.max_line = self.counts.max_line.max(self.line_width);
+        self.line_width = 0;
+    }
+
+    fn finish(mut self) -> Counts {
+        self.end_line();
+        self.end_word();
+        self.counts
+    }
+}
+
+enum Decoded {
+    Char(u32, usize),
+    Invalid,
+    Incomplete,
+}
+
+// Decodes one multibyte character the way glibc does in UTF-8 locales, which
+// also accepts the legacy 5 and 6 byte forms.
+fn decode_char(bytes: &[u8]) -> Decoded {
+    let (len, code) = match bytes[0] {
+        byte @ 0x00..0x80 => return Decoded::Char(byte as u32, 1),
+        byte @ 0xc2..0xe0 => (2, byte & 0x1f),
+        byte @ 0xe0..0xf0 => (3, byte & 0x0f),
+        byte @ 0xf0..0xf8 => (4, byte & 0x07),
+        byte @ 0xf8..0xfc => (5, byte & 0x03),
+        byte @ 0xfc..0xfe => (6, byte & 0x01),
+        _ => return Decoded::Invalid,
+    };
+    let mut code = code as u32;
+    for idx in 1..len {
+        match bytes.get(idx) {
+            None => return Decoded::Incomplete,
+            Some(byte) if byte & 0xc0 != 0x80 => return Decoded::Invalid,
+            Some(byte) => code = code << 6 | (byte & 0x3f) as u32,
+        }
+    }
+    let minimum = [0, 0, 0x80, 0x800, 0x10000, 0x200000, 0x4000000][len];
+    match code < minimum || (0xd800..0xe000).contains(&code) {
+        true => Decoded::Invalid,
+        false => Decoded::Char(code, len),
+    }
+}
+
+fn is_space(char: char) -> bool {
+    matches!(
+        char,
+        '\u{a0}' | '\u{1680}' | '\u{2000}'
+            ..='\u{200a}' | '\u{2028}' | '\u{2029}' | '\u{202f}' | '\u{205f}' | '\u{3000}'
+    )
+}
+
+fn char_width(char: char) -> usize {
+    match char {
+        '\u{300}'..='\u{36f}' | '\u{200b}'..='\u{200f}' | '\u{fe00}'..='\u{fe0f}' => 0,
+        '\u{1100}'..='\u{115f}'
+        | '\u{2e80}'..='\u{303e}'
+        | '\u{3041}'..='\u{a4cf}'
+        | '\u{ac00}'..='\u{d7a3}'
+        | '\u{f900}'..='\u{faff}'
+        | '\u{fe30}'..='\u{fe4f}'
+        | '\u{ff00}'..='\u{ff60}'
+        | '\u{ffe0}'..='\u{ffe6}'
+        | '\u{1f300}'..='\u{1f64f}'
+        | '\u{1f900}'..='\u{1f9ff}'
+        | '\u{20000}'..='\u{3fffd}' => 2,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        crate::wc::{Columns, calculate},
+        std::io::Read,
+    };
+
+    #[test]
+    fn check_counts() {
+        let counts = calculate("one two\n\tthree\nfour".as_bytes()).unwrap();
+        let all = Columns {
+            lines: true,
+            words: true,
+            chars: true,
+            bytes: true,
+            max_line: true,
+        };
+        assert_eq!(all.select(&counts), vec![2, 4, 19, 19, 13]);
+        let counts = calculate("привет мир\n".as_bytes()).unwrap();
+        assert_eq!(all.select(&counts), vec![1, 2, 11, 20, 10]);
+        let counts = calculate(&[0xff, b' ', 0xfe, 0x80, b'\n'][..]).unwrap();
+        assert_eq!(all.select(&counts), vec![1, 0, 2, 5, 1]);
+        let text = "ёж\tёлка\n".as_bytes();
+        let counts = calculate(text[..1].chain(&text[1..])).unwrap();
+        assert_eq!(all.select(&counts), vec![1, 2, 8, 14, 12]);
+    }
+}
@@ -0,0 +1,367 @@
+use {
+    crate::getopt::{self, Args, Opt},
+    std::{
+        io::{BufRead, ErrorKind},
+        os::unix::process::ExitStatusExt,
+        process::{Command, ExitStatus, Stdio},
+        sync::mpsc::{self, Receiver, Sender},
+    },
+};
+
+// Exit statuses of xargs itself, as in GNU and POSIX.
+const COMMAND_FAILED: i32 = 123;
+const COMMAND_STOPPED: i32 = 124;
+const COMMAND_KILLED: i32 = 125;
+const CANNOT_RUN: i32 = 126;
+const NOT_FOUND: i32 = 127;
+
+// Splits the input into items. Without a delimiter, items are separated by
+// blanks and newlines, or only by newlines with `lines`, and quotes and
+// backslashes escape them.
+struct Items<R> {
+    reader: R,
+    delimiter: Option<u8>,
+    lines: bool,
+}
+
+struct Xargs {
+    verbose: bool,
+    parallel: usize,
+    running: usize,
+    sender: Sender<(String, std::io::Result<ExitStatus>)>,
+    receiver: Receiver<(String, std::io::Result<ExitStatus>)>,
+    status: i32,
+    aborted: bool,
+}
+
+pub fn run(args: Vec<String>) -> i32 {
+    let mut delimiter = None;
+    let mut max_args = None;
+    let mut max_size = 128 * 1024;
+    let mut replace = None;
+    let mut no_run_if_empty = false;
+    let mut verbose = false;
+    let mut parallel = 1;
+    let mut command = Vec::new();
+    let mut args = Args::new(args);
+    while let Some(opt) = args.next() {
+        match opt {
+            opt if opt.is('0', "null") => delimiter = Some(b'\0'),
+            opt if opt.is('d', "delimiter") => {
+                let Some(value) = args.value() else {
+                    return getopt::missing("xargs", "d");
+                };
+                delimiter = match value.as_str() {
+                    "\\n" => Some(b'\n'),
+                    "\\t" => Some(b'\t'),
+                    "\\0" => Some(b'\0'),
+                    "\\\\" => Some(b'\\'),
+                    value if value.len() == 1 => Some(value.as_bytes()[0]),
+                    value => {
+                        eprintln!("xargs: invalid input delimiter specification {}", value);
+                        return 1;
+                    }
+                };
+            }
+            opt if opt.is('n', "max-args")
+                || opt.is('s', "max-chars")
+                || opt.is('P', "max-procs") =>
+            {
+                let name = match opt {
+                    opt if opt.is('n', "max-args") => "n",
+                    opt if opt.is('s', "max-chars") => "s",
+                    _ => "P",
+                };
+                let Some(value) = args.value() else {
+                    return getopt::missing("xargs", name);
+                };
+                let number = match value.parse::<usize>() {
+                    Ok(0) if name != "P" => {
+                        eprintln!("xargs: value 0 for -{} option should be >= 1", name);
+                        return 1;
+                    }
+                    Ok(number) => number,
+                    Err(_) => {
+                        eprintln!("xargs: invalid number '{}' for -{} option", value, name);
+                        return 1;
+                    }
+                };
+                match name {
+                    "n" => max_args = Some(number),
+                    "s" => max_size = number,
+                    _ => parallel = number,
+                }
+            }
+            opt if opt.is('I', "replace") => {
+                let Some(value) = args.value() else {
+                    return getopt::missing("xargs", "I");
+                };
+                replace = Some(value);
+            }
+            opt if opt.is('r', "no-run-if-empty") => no_run_if_empty = true,
+            opt if opt.is('t', "verbose") => verbose = true,
+            Opt::Operand(operand) => {
+                command.push(operand);
+                command.extend(args.rest());
+            }
+            opt => return getopt::unknown("xargs", &opt),
+        }
+    }
+    if command.is_empty() {
+        command.push("echo".to_string());
+    }
+    let base_size: usize = command.iter().map(|arg| arg.len() + 1).sum();
+    if base_size > max_size {
+        eprintln!("xargs: value for -s option should be >= {}", base_size);
+        return 1;
+    }
+
+    let mut items = Items {
+        reader: std::io::stdin().lock(),
+        delimiter,
+        lines: replace.is_some(),
+    };
+    let (sender, receiver) = mpsc::channel();
+    let mut xargs = Xargs {
+        verbose,
+        parallel,
+        running: 0,
+        sender,
+        receiver,
+        status: 0,
+        aborted: false,
+    };
+    let mut batch: Vec<String> = Vec::new();
+    let mut size = base_size;
+    let mut launched = false;
+    while !xargs.aborted {
+        let item = match items.next() {
+            Ok(Some(item)) => item,
+            Ok(None) => break,
+            Err(message) => {
+                eprintln!("xargs: {}", message);
+                xargs.status = 1;
+                xargs.aborted = true;
+                break;
+            }
+        };
+        if let Some(replace) = &replace {
+            xargs.launch(
+                command
+                    .iter()
+                    .map(|arg| arg.replace(replace, &item))
+                    .collect(),
+            );
+            launched = true;
+            continue;
+        }
+        if !batch.is_empty() && size + item.len() + 1 > max_size {
+            xargs.launch([command.clone(), std::mem::take(&mut batch)].concat());
+            size = base_size;
+            launched = true;
+        }
+        if base_size + item.len() + 1 > max_size {
+            eprintln!("xargs: argument line too long");
+            xargs.status = 1;
+            xargs.aborted = true;
+            break;
+        }
+        size += item.len() + 1;
+        batch.push(item);
+        if max_args.is_some_and(|max_args| batch.len() >= max_args) {
+            xargs.launch([command.clone(), std::mem::take(&mut batch)].concat());
+            size = base_size;
+            launched = true;
+        }
+    }
+    let empty_run = !launched && !no_run_if_empty && replace.is_none();
+    if !xargs.aborted && (!batch.is_empty() || empty_run) {
+        xargs.launch([command, batch].concat());
+    }
+    while xargs.running > 0 {
+        xargs.collect();
+    }
+
+    xargs.status
+}
+
+impl<R: BufRead> Items<R> {
+    fn next(&mut self) -> Result<Option<String>, String> {
+        if let Some(delimiter) = self.delimiter {
+            let mut item = Vec::new();
+            if self
+                .reader
+                .read_until(delimiter, &mut item)
+                .map_err(|e| e.to_string())?
+                == 0
+            {
+                return Ok(None);
+            }
+            if item.last() == Some(&delimiter) {
+                item.pop();
+            }
+            return Ok(Some(String::from_utf8_lossy(&item).into_owned()));
+        }
+
+        let mut item = Vec::new();
+        let mut started = false;
+        let mut quote = None;
+        loop {
+            let Some(byte) = self.byte()? else {
+                if let Some(quote) = quote {
+                    return Err(unmatched(quote));
+                }
+                return Ok(started.then(|| String::from_utf8_lossy(&item).into_owned()));
+            };
+            match (quote, byte) {
+                (Some(open), b'\n') => return Err(unmatched(open)),
+                (Some(open), byte) if byte == open => quote = None,
+                (Some(_), byte) => item.push(byte),
+                (None, b'\n') if started => break,
+                (None, b' ' | b'\t') if started && !self.lines => break,
+                (None, b'\n' | b' ' | b'\t') if !started => {}
+                (None, b'\'' | b'"') => {
+                    quote = Some(byte);
+                    started = true;
+                }
+                (None, b'\\') => {
+                    item.extend(self.byte()?);
+                    started = true;
+                }
+                (None, byte) => {
+                    item.push(byte);
+                    started = true;
+                }
+            }
+        }
+        Ok(Some(String::from_utf8_lossy(&item).into_owned()))
+    }
+
+    fn byte(&mut self) -> Result<Option<u8>, String> {
+        let buffer = self.reader.fill_buf().map_err(|e| e.to_string())?;
+        let Some(&byte) = buffer.first() else {
+            return Ok(None);
+        };
+        self.reader.consume(1);
+        Ok(Some(byte))
+    }
+}
+
+fn unmatched(quote: u8) -> String {
+    let kind = if quote == b'\'' { "single" } else { "double" };
+    format!(
+        "unmatched {} quote; by default quotes are special to xargs unless you use the -0 option",
+        kind
+    )
+}
+
+impl Xargs {
+    // Starts a command once fewer than `parallel` are running. Each child is
+    // waited for on its own thread, which reports back over the channel.
+    fn launch(&mut self, command: Vec<String>) {
+        while self.parallel != 0 && self.running >= self.parallel {
+            self.collect();
+        }
+        if self.aborted {
+            return;
+        }
+        if self.verbose {
+            eprintln!("{}", command.join(" "));
+        }
+        let child = Command::new(&command[0])
+            .args(&command[1..])
+            .stdin(Stdio::null())
+            .spawn();
+        match child {
+            Ok(mut child) => {
+                let sender = self.sender.clone();
+                let name = command[0].clone();
+                std::thread::spawn(move || sender.send((name, child.wait())));
+                self.running += 1;
+            }
+            Err(e) => {
+                eprintln!("xargs: {}: {}", command[0], crate::strerror(&e));
+                self.status = match e.kind() {
+                    ErrorKind::NotFound => NOT_FOUND,
+                    _ => CANNOT_RUN,
+                };
+                self.aborted = true;
+            }
+        }
+    }
+
+    // Waits for one command. Exit status 255 or a signal stops xargs, other
+    // failures only change its exit status.
+    fn collect(&mut self) {
+        let Ok((name, status)) = self.receiver.recv() else {
+            return;
+        };
+        self.running -= 1;
+        let status = match status {
+            Ok(status) => status,
+            Err(e) => {
+                eprintln!("xargs: {}: {}", name, crate::strerror(&e));
+                self.status = COMMAND_FAILED;
+                return;
+            }
+        };
+        match (status.code(), status.signal()) {
+            (Some(0), _) => {}
+            (Some(255), _) => {
+                eprintln!("xargs: {}: exited with status 255; aborting", name);
+                self.status = COMMAND_STOPPED;
+                self.aborted = true;
+            }
+            (Some(_), _) if !self.aborted => self.status = COMMAND_FAILED,
+            (Some(_), _) => {}
+            (None, signal) => {
+                eprintln!(
+                    "xargs: {}: terminated by signal {}",
+                    name,
+                    signal.unwrap_or(0)
+                );
+                self.status = COMMAND_KILLED;
+                self.aborted = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::xargs::Items;
+
+    #[test]
+    fn check_items() {
+        let split = |input: &str, delimiter: Option<u8>, lines: bool| {
+            let mut items = Items {
+                reader: input.as_bytes(),
+                delimiter,
+                lines,
+            };
+            let mut split = Vec::new();
+            loop {
+                match items.next() {
+                    Ok(Some(item)) => split.push(item),
+                    Ok(None) => return Ok(split),
+                    Err(e) => return Err(e),
+                }
+            }
+        };
+        assert_eq!(
+            split("  a b\t'c d' \"e'f\"\n\ng\\ h ''\n", None, false).unwrap(),
+            ["a", "b", "c d", "e'f", "g h", ""]
+        );
+        assert_eq!(
+            split("  one two\n'three' four\n", None, true).unwrap(),
+            ["one two", "three four"]
+        );
+        assert_eq!(
+            split("a b\0c\n\0", Some(b'\0'), false).unwrap(),
+            ["a b", "c\n"]
+        );
+        assert_eq!(split("x,,y", Some(b','), false).unwrap(), ["x", "", "y"]);
+        assert!(split("a 'b\nc'", None, false).is_err());
+        assert!(split("\"open", None, false).is_err());
+    }
+}